@@ -0,0 +1,221 @@
+use clap::ValueEnum;
+use octo_client::state::{
+    AssetTranche, Distribution, InvestorPosition, Pool, PoolRegistryEntry, PoolReserve,
+    PoolTemplate, RevenueConfig, ShareClass, VehicleAsset,
+};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+pub fn pool(address: &Pubkey, pool: &Pool) -> Value {
+    json!({
+        "address": address.to_string(),
+        "version": pool.version,
+        "creator": pool.creator.to_string(),
+        "authority": pool.authority.to_string(),
+        "reference": pool.reference.to_string(),
+        "mint": pool.mint.to_string(),
+        "shares": pool.shares,
+        "minted": pool.minted,
+        "status": format!("{:?}", pool.status),
+        "start_date": pool.start_date,
+        "maturity_date": pool.maturity_date,
+        "apy": pool.apy,
+        "lockup": pool.lockup.map(|lockup| json!({
+            "cliff": lockup.cliff,
+            "duration": lockup.duration,
+        })),
+        "valuator": pool.valuator.to_string(),
+        "share_price": pool.share_price,
+        "nav_per_share": pool.nav_per_share,
+        "nav_updated_at": pool.nav_updated_at,
+        "assets": pool.assets,
+        "tranches": pool.tranches,
+        "allocated": pool.allocated,
+        "share_classes": pool.share_classes,
+        "class_shares": pool.class_shares,
+        "eligibility": format!("{:?}", pool.eligibility),
+        "protocol_fee_bps": pool.protocol_fee_bps,
+        "id": pool.id,
+        "distributions": pool.distributions,
+        "open_distributions": pool.open_distributions,
+        "rewards": pool.rewards,
+        "reserve_configured": pool.reserve_configured,
+        "raised": pool.raised,
+    })
+}
+
+pub fn pool_entry(address: &Pubkey, entry: &PoolRegistryEntry) -> Value {
+    json!({
+        "address": address.to_string(),
+        "id": entry.id,
+        "pool": entry.pool.to_string(),
+        "creator": entry.creator.to_string(),
+        "creator_index": entry.creator_index,
+    })
+}
+
+pub fn distribution(address: &Pubkey, distribution: &Distribution) -> Value {
+    json!({
+        "address": address.to_string(),
+        "version": distribution.version,
+        "pool": distribution.pool.to_string(),
+        "id": distribution.id,
+        "authority": distribution.authority.to_string(),
+        "rewards": distribution.rewards,
+        "claimed": distribution.claimed,
+        "allocated": distribution.allocated,
+    })
+}
+
+pub fn position(address: &Pubkey, position: &InvestorPosition) -> Value {
+    json!({
+        "address": address.to_string(),
+        "version": position.version,
+        "pool": position.pool.to_string(),
+        "holder": position.holder.to_string(),
+        "shares": position.shares,
+        "cost_basis": position.cost_basis,
+        "realized_cost_basis": position.realized_cost_basis,
+        "principal_returned": position.principal_returned,
+        "rewards_claimed": position.rewards_claimed,
+        "rewards_owed": position.rewards_owed,
+        "opened_at": position.opened_at,
+        "last_acquired_at": position.last_acquired_at,
+    })
+}
+
+pub fn reserve(address: &Pubkey, reserve: &PoolReserve) -> Value {
+    json!({
+        "address": address.to_string(),
+        "version": reserve.version,
+        "pool": reserve.pool.to_string(),
+        "principal_bps": reserve.principal_bps,
+        "target": reserve.target,
+        "funded": reserve.funded,
+        "spent": reserve.spent,
+        "expenses": reserve.expenses,
+    })
+}
+
+pub fn revenue_config(address: &Pubkey, revenue_config: &RevenueConfig) -> Value {
+    json!({
+        "address": address.to_string(),
+        "version": revenue_config.version,
+        "pool": revenue_config.pool.to_string(),
+        "operator": revenue_config.operator.to_string(),
+        "driver_bps": revenue_config.driver_bps,
+        "maintenance_bps": revenue_config.maintenance_bps,
+        "reported": revenue_config.reported,
+    })
+}
+
+pub fn pool_template(address: &Pubkey, template: &PoolTemplate) -> Value {
+    json!({
+        "address": address.to_string(),
+        "version": template.version,
+        "creator": template.creator.to_string(),
+        "authority": template.authority.to_string(),
+        "name": template.name,
+        "symbol": template.symbol,
+        "uri_pattern": template.uri_pattern,
+        "share_price": template.share_price,
+        "term": template.term,
+        "apy": template.apy,
+        "lockup": template.lockup.map(|lockup| json!({
+            "cliff": lockup.cliff,
+            "duration": lockup.duration,
+        })),
+        "eligibility": format!("{:?}", template.eligibility),
+        "protocol_fee_bps": template.protocol_fee_bps,
+        "pools": template.pools,
+    })
+}
+
+pub fn share_class(address: &Pubkey, share_class: &ShareClass) -> Value {
+    json!({
+        "address": address.to_string(),
+        "version": share_class.version,
+        "pool": share_class.pool.to_string(),
+        "class_id": share_class.class_id,
+        "mint": share_class.mint.to_string(),
+        "shares": share_class.shares,
+        "minted": share_class.minted,
+        "apy": share_class.apy,
+        "eligibility": format!("{:?}", share_class.eligibility),
+        "rewards": share_class.rewards,
+        "claimed": share_class.claimed,
+    })
+}
+
+pub fn asset_tranche(address: &Pubkey, tranche: &AssetTranche) -> Value {
+    json!({
+        "address": address.to_string(),
+        "version": tranche.version,
+        "pool": tranche.pool.to_string(),
+        "index": tranche.index,
+        "vehicle_asset": tranche.vehicle_asset.to_string(),
+        "principal_allocation": tranche.principal_allocation,
+        "deployed": tranche.deployed,
+        "repaid": tranche.repaid,
+    })
+}
+
+pub fn vehicle_asset(address: &Pubkey, vehicle_asset: &VehicleAsset) -> Value {
+    json!({
+        "address": address.to_string(),
+        "version": vehicle_asset.version,
+        "creator": vehicle_asset.creator.to_string(),
+        "pool": vehicle_asset.pool.map(|pool| pool.to_string()),
+        "vin_hash": hex(&vehicle_asset.vin_hash),
+        "make": vehicle_asset.make,
+        "model": vehicle_asset.model,
+        "purchase_price": vehicle_asset.purchase_price,
+        "jurisdiction": vehicle_asset.jurisdiction,
+        "custodian": vehicle_asset.custodian.to_string(),
+        "title_hash": hex(&vehicle_asset.title_hash),
+        "status": format!("{:?}", vehicle_asset.status),
+        "registered_at": vehicle_asset.registered_at,
+        "tranche": vehicle_asset.tranche,
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Prints a flat JSON object, or a list of them, either as-is or as two column tables.
+pub fn print(value: &Value, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => println!("{value:#}"),
+        OutputFormat::Table if value.is_array() => {
+            for (index, item) in value.as_array().into_iter().flatten().enumerate() {
+                if index > 0 {
+                    println!();
+                }
+                print(item, format);
+            }
+        }
+        OutputFormat::Table => {
+            let Some(fields) = value.as_object() else {
+                println!("{value}");
+                return;
+            };
+            let width: usize = fields.keys().map(String::len).max().unwrap_or_default();
+            for (key, value) in fields {
+                let value: String = match value {
+                    Value::String(value) => value.clone(),
+                    Value::Null => "-".to_string(),
+                    value => value.to_string(),
+                };
+                println!("{key:<width$}  {value}");
+            }
+        }
+    }
+}
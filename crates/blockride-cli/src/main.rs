@@ -0,0 +1,1006 @@
+mod display;
+mod spec;
+
+use std::{fs, path::PathBuf};
+
+use anyhow::{bail, Context as _, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use clap::{Parser, Subcommand, ValueEnum};
+use octo_client::{instructions, pda, state};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+    transaction::Transaction,
+};
+
+use crate::{
+    display::OutputFormat,
+    spec::{PoolSpec, TemplateSpec, VehicleSpec},
+};
+
+#[derive(Debug, Parser)]
+#[command(name = "blockride", about = "Manage the BlockRide octo-program")]
+struct Cli {
+    /// RPC endpoint of the cluster.
+    #[arg(
+        long,
+        short,
+        env = "BLOCKRIDE_URL",
+        default_value = "http://127.0.0.1:8899"
+    )]
+    url: String,
+
+    /// Keypair paying for and signing transactions.
+    #[arg(long, short, env = "BLOCKRIDE_KEYPAIR", default_value_t = default_keypair())]
+    keypair: String,
+
+    /// Print the serialized transaction and simulate it instead of sending it.
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    #[arg(long, short, global = true, value_enum, default_value_t)]
+    output: OutputFormat,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Create the program's project account.
+    Init {
+        /// Defaults to the signing keypair.
+        #[arg(long)]
+        grand_authority: Option<Pubkey>,
+    },
+    /// Hand the grand authority over to another key.
+    SetGrandAuthority { new_grand_authority: Pubkey },
+    /// Set the protocol's cut of reported revenue, in basis points.
+    SetProtocolFee { protocol_fee_bps: u16 },
+    /// Register an address allowed to create pools.
+    AddCreator {
+        creator: Pubkey,
+        #[arg(long)]
+        disabled: bool,
+    },
+    /// Allow or forbid an existing creator to create pools.
+    UpdateCreator {
+        creator: Pubkey,
+        #[arg(long, action = clap::ArgAction::Set)]
+        can_create: bool,
+    },
+    /// Create the BlockRide collection every pool mint joins.
+    CreateCollection {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        symbol: String,
+        #[arg(long)]
+        uri: String,
+    },
+    /// Create a draft pool from a TOML or JSON spec; `transition-pool` opens it for sale.
+    CreatePool { spec: PathBuf },
+    /// Create a template for a recurring pool series from a TOML or JSON spec.
+    CreateTemplate { spec: PathBuf },
+    /// Replace the terms of a pool template; pools already launched keep theirs.
+    UpdateTemplate { template: Pubkey, spec: PathBuf },
+    /// Give a pool series a negotiated protocol fee, or clear it when omitted.
+    SetTemplateFee {
+        template: Pubkey,
+        #[arg(long)]
+        protocol_fee_bps: Option<u16>,
+    },
+    /// Launch the next pool of a series from its template.
+    LaunchPool {
+        template: Pubkey,
+        shares: u64,
+        #[arg(long)]
+        start_date: u64,
+        /// Seed of the pool address; a fresh one is generated when omitted.
+        #[arg(long)]
+        reference: Option<Pubkey>,
+    },
+    /// Change the name, symbol and URI of a pool's share token.
+    UpdatePoolMetadata {
+        pool: Pubkey,
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        symbol: String,
+        #[arg(long)]
+        uri: String,
+    },
+    /// Add a pool created before the collection existed to it.
+    JoinPoolCollection { pool: Pubkey },
+    /// Give a pool created before the pool registry existed its id.
+    RegisterPool { pool: Pubkey },
+    /// Deposit rewards into an active or settled pool's distribution.
+    Distribute {
+        pool: Pubkey,
+        amount: u64,
+        /// Id of the distribution; the primary one when omitted.
+        #[arg(long, default_value_t)]
+        distribution: u8,
+        /// Keypair of the distribution authority; defaults to the signing keypair.
+        #[arg(long)]
+        distribution_authority: Option<String>,
+        /// Index of the repaying tranche; required for fleet pools.
+        #[arg(long)]
+        tranche: Option<u16>,
+    },
+    /// Open another distribution for a pool, e.g. for principal or recoveries.
+    CreateDistribution {
+        pool: Pubkey,
+        /// Keypair of the distribution authority; defaults to the signing keypair.
+        #[arg(long)]
+        distribution_authority: Option<String>,
+    },
+    /// Hand a distribution over to a new authority; the signer is the current authority
+    /// or the pool authority.
+    SetDistributionAuthority {
+        pool: Pubkey,
+        /// Keypair of the new authority, which co-signs.
+        new_authority: String,
+        #[arg(long, default_value_t)]
+        distribution: u8,
+    },
+    /// Close a fully claimed distribution other than the primary one.
+    CloseDistribution { pool: Pubkey, distribution: u8 },
+    /// Move a pool to another state; activation and settlement have their own commands.
+    TransitionPool {
+        pool: Pubkey,
+        #[arg(value_enum)]
+        status: PoolStatusArg,
+    },
+    /// Activate a pool, opening its distribution for payouts during its term.
+    ActivatePool {
+        pool: Pubkey,
+        /// Keypair of the distribution authority; defaults to the signing keypair.
+        #[arg(long)]
+        distribution_authority: Option<String>,
+    },
+    /// Settle a pool, creating its distribution when it was never activated.
+    ClosePool {
+        pool: Pubkey,
+        /// Keypair of the distribution authority; defaults to the signing keypair.
+        #[arg(long)]
+        distribution_authority: Option<String>,
+    },
+    /// Register a vehicle from a TOML or JSON spec; the signer must be an approved creator.
+    RegisterVehicle { spec: PathBuf },
+    /// Change the status of a registered vehicle.
+    UpdateVehicleStatus {
+        vin: String,
+        #[arg(value_enum)]
+        status: VehicleStatusArg,
+    },
+    /// Record that a pool financed a registered vehicle.
+    LinkVehicle { pool: Pubkey, vin: String },
+    /// Finance a linked vehicle out of a fleet pool's principal.
+    AddTranche {
+        pool: Pubkey,
+        vin: String,
+        principal_allocation: u64,
+    },
+    /// Add the next share class to an unsold pool; class 0 is the most senior.
+    CreateShareClass {
+        pool: Pubkey,
+        shares: u64,
+        #[arg(long)]
+        apy: u8,
+        #[arg(long, value_enum, default_value = "open")]
+        eligibility: EligibilityArg,
+    },
+    /// Run a pool's new rewards down its share class waterfall.
+    AllocateClassRewards { pool: Pubkey },
+    /// Set the fleet operator and revenue waterfall of a pool, in basis points.
+    ConfigureRevenue {
+        pool: Pubkey,
+        #[arg(long)]
+        operator: Pubkey,
+        #[arg(long)]
+        driver_bps: u16,
+        #[arg(long)]
+        maintenance_bps: u16,
+    },
+    /// Set how much of withdrawn principal a pool reserves, and the reserve target.
+    ConfigureReserve {
+        pool: Pubkey,
+        #[arg(long)]
+        principal_bps: u16,
+        #[arg(long)]
+        target: u64,
+    },
+    /// Pay a vehicle expense out of a pool's reserve.
+    SpendReserve {
+        pool: Pubkey,
+        amount: u64,
+        #[arg(value_enum)]
+        category: ExpenseCategoryArg,
+        /// Receipt document, stored on-chain as its hash.
+        #[arg(long)]
+        receipt: PathBuf,
+        /// Defaults to the signer's USDC account.
+        #[arg(long)]
+        to: Option<Pubkey>,
+    },
+    /// Deposit gross ride revenue for a pool as its fleet operator.
+    ReportRevenue { pool: Pubkey, amount: u64 },
+    /// Pay out of a pool's driver or protocol revenue bucket.
+    WithdrawRevenue {
+        pool: Pubkey,
+        #[arg(value_enum)]
+        bucket: RevenueBucketArg,
+        amount: u64,
+        /// Defaults to the signer's USDC account.
+        #[arg(long)]
+        to: Option<Pubkey>,
+    },
+    /// Move a pool's investor revenue into its distribution.
+    DistributeRevenue {
+        pool: Pubkey,
+        /// Index of the repaying tranche; required for fleet pools.
+        #[arg(long)]
+        tranche: Option<u16>,
+    },
+    /// Upgrade the project account to the current layout.
+    MigrateProject,
+    /// Upgrade a pool account to the current layout.
+    MigratePool { pool: Pubkey },
+    /// Upgrade a pool's distribution account to the current layout.
+    MigrateDistribution { pool: Pubkey },
+    /// Print a pool account.
+    Pool { pool: Pubkey },
+    /// List registered pools by id, or those of one creator.
+    Pools {
+        #[arg(long)]
+        creator: Option<Pubkey>,
+    },
+    /// Print a pool's distribution account.
+    Distribution {
+        pool: Pubkey,
+        /// Id of the distribution; the primary one when omitted.
+        #[arg(long, default_value_t)]
+        id: u8,
+    },
+    /// Print a holder's position in a pool.
+    Position {
+        pool: Pubkey,
+        /// Defaults to the signing keypair.
+        #[arg(long)]
+        holder: Option<Pubkey>,
+    },
+    /// Print a pool's revenue configuration.
+    Revenue { pool: Pubkey },
+    /// Print a pool's reserve.
+    Reserve { pool: Pubkey },
+    /// Print a pool template.
+    Template { template: Pubkey },
+    /// Print a share class of a pool.
+    ShareClass { pool: Pubkey, class_id: u8 },
+    /// Print an asset tranche of a fleet pool.
+    Tranche { pool: Pubkey, index: u16 },
+    /// Print a registered vehicle.
+    Vehicle { vin: String },
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum VehicleStatusArg {
+    Active,
+    InMaintenance,
+    Sold,
+    WrittenOff,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum PoolStatusArg {
+    Fundraising,
+    Funded,
+    Matured,
+    Defaulted,
+    Refunding,
+    Cancelled,
+}
+
+impl From<PoolStatusArg> for state::PoolStatus {
+    fn from(status: PoolStatusArg) -> Self {
+        match status {
+            PoolStatusArg::Fundraising => Self::Fundraising,
+            PoolStatusArg::Funded => Self::Funded,
+            PoolStatusArg::Matured => Self::Matured,
+            PoolStatusArg::Defaulted => Self::Defaulted,
+            PoolStatusArg::Refunding => Self::Refunding,
+            PoolStatusArg::Cancelled => Self::Cancelled,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum RevenueBucketArg {
+    Driver,
+    Protocol,
+}
+
+impl From<RevenueBucketArg> for state::RevenueBucket {
+    fn from(bucket: RevenueBucketArg) -> Self {
+        match bucket {
+            RevenueBucketArg::Driver => Self::Driver,
+            RevenueBucketArg::Protocol => Self::Protocol,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ExpenseCategoryArg {
+    Maintenance,
+    Repair,
+    Insurance,
+    Registration,
+    Tracking,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum EligibilityArg {
+    Open,
+    Creator,
+    Approved,
+}
+
+impl From<EligibilityArg> for state::BuyerEligibility {
+    fn from(eligibility: EligibilityArg) -> Self {
+        match eligibility {
+            EligibilityArg::Open => Self::Open,
+            EligibilityArg::Creator => Self::Creator,
+            EligibilityArg::Approved => Self::Approved,
+        }
+    }
+}
+
+impl From<ExpenseCategoryArg> for state::ExpenseCategory {
+    fn from(category: ExpenseCategoryArg) -> Self {
+        match category {
+            ExpenseCategoryArg::Maintenance => Self::Maintenance,
+            ExpenseCategoryArg::Repair => Self::Repair,
+            ExpenseCategoryArg::Insurance => Self::Insurance,
+            ExpenseCategoryArg::Registration => Self::Registration,
+            ExpenseCategoryArg::Tracking => Self::Tracking,
+        }
+    }
+}
+
+impl From<VehicleStatusArg> for state::VehicleStatus {
+    fn from(status: VehicleStatusArg) -> Self {
+        match status {
+            VehicleStatusArg::Active => Self::Active,
+            VehicleStatusArg::InMaintenance => Self::InMaintenance,
+            VehicleStatusArg::Sold => Self::Sold,
+            VehicleStatusArg::WrittenOff => Self::WrittenOff,
+        }
+    }
+}
+
+struct Context {
+    rpc: RpcClient,
+    payer: Keypair,
+    dry_run: bool,
+    output: OutputFormat,
+}
+
+fn main() -> Result<()> {
+    let cli: Cli = Cli::parse();
+    let context: Context = Context {
+        rpc: RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed()),
+        payer: read_keypair(&cli.keypair)?,
+        dry_run: cli.dry_run,
+        output: cli.output,
+    };
+    let payer: Pubkey = context.payer.pubkey();
+
+    match cli.command {
+        Command::Init { grand_authority } => context.send(
+            &[instructions::initialize_program(
+                payer,
+                grand_authority.unwrap_or(payer),
+            )],
+            &[],
+        ),
+        Command::SetGrandAuthority {
+            new_grand_authority,
+        } => context.send(
+            &[instructions::update_program_grand_authority(
+                payer,
+                new_grand_authority,
+            )],
+            &[],
+        ),
+        Command::SetProtocolFee { protocol_fee_bps } => context.send(
+            &[instructions::set_protocol_fee(payer, protocol_fee_bps)],
+            &[],
+        ),
+        Command::AddCreator { creator, disabled } => context.send(
+            &[instructions::add_pool_creator(payer, creator, !disabled)],
+            &[],
+        ),
+        Command::UpdateCreator {
+            creator,
+            can_create,
+        } => context.send(
+            &[instructions::update_pool_creator(
+                payer, creator, can_create,
+            )],
+            &[],
+        ),
+        Command::CreateCollection { name, symbol, uri } => context.send(
+            &[instructions::create_collection(payer, name, symbol, uri)],
+            &[],
+        ),
+        Command::CreatePool { spec } => {
+            let spec: PoolSpec = PoolSpec::read(&spec)?;
+            let reference: Pubkey = spec.reference()?.unwrap_or_else(|| Keypair::new().pubkey());
+            let pool: Pubkey = pda::find_pool_address(&reference).0;
+            let (id, creator_index): (u64, u64) = context.registry_slots(&payer)?;
+
+            context.send(
+                &[instructions::create_pool(
+                    payer,
+                    octo_program::instruction::CreatePool {
+                        reference,
+                        authority: spec.authority()?,
+                        shares: spec.shares,
+                        deposit: spec.deposit,
+                        name: spec.name.clone(),
+                        symbol: spec.symbol.clone(),
+                        uri: spec.uri.clone(),
+                        start_date: spec.start_date,
+                        maturity_date: spec.maturity_date,
+                        apy: spec.apy,
+                        lockup: spec.lockup(),
+                    },
+                    id,
+                    creator_index,
+                )],
+                &[],
+            )?;
+            println!("Pool: {pool}");
+            Ok(())
+        }
+        Command::CreateTemplate { spec } => {
+            let terms: state::PoolTemplateTerms = TemplateSpec::read(&spec)?.terms()?;
+            let reference: Pubkey = Keypair::new().pubkey();
+
+            context.send(
+                &[instructions::create_pool_template(payer, reference, terms)],
+                &[],
+            )?;
+            println!("Template: {}", pda::find_template_address(&reference).0);
+            Ok(())
+        }
+        Command::UpdateTemplate { template, spec } => {
+            let terms: state::PoolTemplateTerms = TemplateSpec::read(&spec)?.terms()?;
+            context.send(
+                &[instructions::update_pool_template(payer, template, terms)],
+                &[],
+            )
+        }
+        Command::SetTemplateFee {
+            template,
+            protocol_fee_bps,
+        } => context.send(
+            &[instructions::set_template_fee_override(
+                payer,
+                template,
+                protocol_fee_bps,
+            )],
+            &[],
+        ),
+        Command::LaunchPool {
+            template,
+            shares,
+            start_date,
+            reference,
+        } => {
+            let reference: Pubkey = reference.unwrap_or_else(|| Keypair::new().pubkey());
+            let pool: Pubkey = pda::find_pool_address(&reference).0;
+            let (id, creator_index): (u64, u64) = context.registry_slots(&payer)?;
+
+            context.send(
+                &[instructions::create_pool_from_template(
+                    payer,
+                    template,
+                    reference,
+                    shares,
+                    start_date,
+                    id,
+                    creator_index,
+                )],
+                &[],
+            )?;
+            println!("Pool: {pool}");
+            Ok(())
+        }
+        Command::Distribute {
+            pool,
+            amount,
+            distribution,
+            distribution_authority,
+            tranche,
+        } => {
+            let distribution_authority: Option<Keypair> = distribution_authority
+                .as_deref()
+                .map(read_keypair)
+                .transpose()?;
+            let authority: &Keypair = distribution_authority.as_ref().unwrap_or(&context.payer);
+
+            context.send(
+                &[instructions::distribute(
+                    payer,
+                    authority.pubkey(),
+                    pool,
+                    distribution,
+                    amount,
+                    tranche,
+                )],
+                &[authority],
+            )
+        }
+        Command::CreateDistribution {
+            pool,
+            distribution_authority,
+        } => {
+            let distribution_authority: Option<Keypair> = distribution_authority
+                .as_deref()
+                .map(read_keypair)
+                .transpose()?;
+            let authority: &Keypair = distribution_authority.as_ref().unwrap_or(&context.payer);
+            let distribution_id: u8 = context
+                .fetch::<state::Pool>(&pool)?
+                .distributions
+                .checked_add(1)
+                .context("pool has too many distributions")?;
+
+            context.send(
+                &[instructions::create_distribution(
+                    payer,
+                    authority.pubkey(),
+                    pool,
+                    distribution_id,
+                )],
+                &[authority],
+            )?;
+            println!("Distribution: {distribution_id}");
+            Ok(())
+        }
+        Command::SetDistributionAuthority {
+            pool,
+            new_authority,
+            distribution,
+        } => {
+            let new_authority: Keypair = read_keypair(&new_authority)?;
+
+            context.send(
+                &[instructions::set_distribution_authority(
+                    payer,
+                    new_authority.pubkey(),
+                    pool,
+                    distribution,
+                )],
+                &[&new_authority],
+            )
+        }
+        Command::CloseDistribution { pool, distribution } => context.send(
+            &[instructions::close_distribution(payer, pool, distribution)],
+            &[],
+        ),
+        Command::TransitionPool { pool, status } => context.send(
+            &[instructions::transition_pool(payer, pool, status.into())],
+            &[],
+        ),
+        Command::ActivatePool {
+            pool,
+            distribution_authority,
+        } => {
+            let distribution_authority: Option<Keypair> = distribution_authority
+                .as_deref()
+                .map(read_keypair)
+                .transpose()?;
+            let authority: &Keypair = distribution_authority.as_ref().unwrap_or(&context.payer);
+
+            context.send(
+                &[instructions::activate_pool(payer, authority.pubkey(), pool)],
+                &[authority],
+            )
+        }
+        Command::ClosePool {
+            pool,
+            distribution_authority,
+        } => {
+            let distribution_authority: Option<Keypair> = distribution_authority
+                .as_deref()
+                .map(read_keypair)
+                .transpose()?;
+            let authority: &Keypair = distribution_authority.as_ref().unwrap_or(&context.payer);
+
+            context.send(
+                &[instructions::close_pool(payer, authority.pubkey(), pool)],
+                &[authority],
+            )
+        }
+        Command::MigrateProject => context.send(&[instructions::migrate_project(payer)], &[]),
+        Command::UpdatePoolMetadata {
+            pool,
+            name,
+            symbol,
+            uri,
+        } => context.send(
+            &[instructions::update_pool_metadata(
+                payer, pool, name, symbol, uri,
+            )],
+            &[],
+        ),
+        Command::JoinPoolCollection { pool } => {
+            context.send(&[instructions::join_pool_collection(payer, pool)], &[])
+        }
+        Command::RegisterPool { pool } => {
+            let account: state::Pool = context.fetch(&pool)?;
+            let (id, creator_index): (u64, u64) = context.registry_slots(&account.creator)?;
+            context.send(
+                &[instructions::register_pool(
+                    payer,
+                    pool,
+                    account.creator,
+                    id,
+                    creator_index,
+                )],
+                &[],
+            )
+        }
+        Command::RegisterVehicle { spec } => {
+            let spec: VehicleSpec = VehicleSpec::read(&spec)?;
+            let details: state::VehicleAssetDetails = spec.details()?;
+            let vehicle_asset: Pubkey = pda::find_vehicle_asset_address(&details.vin_hash).0;
+
+            context.send(&[instructions::register_vehicle_asset(payer, details)], &[])?;
+            println!("Vehicle: {vehicle_asset}");
+            Ok(())
+        }
+        Command::UpdateVehicleStatus { vin, status } => context.send(
+            &[instructions::update_vehicle_asset_status(
+                payer,
+                &pda::hash_vin(&vin),
+                status.into(),
+            )],
+            &[],
+        ),
+        Command::LinkVehicle { pool, vin } => context.send(
+            &[instructions::link_vehicle_asset(
+                payer,
+                pool,
+                &pda::hash_vin(&vin),
+            )],
+            &[],
+        ),
+        Command::AddTranche {
+            pool,
+            vin,
+            principal_allocation,
+        } => {
+            let account: state::Pool = context.fetch(&pool)?;
+
+            context.send(
+                &[instructions::add_asset_tranche(
+                    payer,
+                    pool,
+                    account.tranches,
+                    &pda::hash_vin(&vin),
+                    principal_allocation,
+                )],
+                &[],
+            )
+        }
+        Command::CreateShareClass {
+            pool,
+            shares,
+            apy,
+            eligibility,
+        } => {
+            let account: state::Pool = context.fetch(&pool)?;
+
+            context.send(
+                &[instructions::create_share_class(
+                    payer,
+                    pool,
+                    account.share_classes,
+                    shares,
+                    apy,
+                    eligibility.into(),
+                )],
+                &[],
+            )
+        }
+        Command::AllocateClassRewards { pool } => {
+            let account: state::Pool = context.fetch(&pool)?;
+
+            context.send(
+                &[instructions::allocate_class_rewards(
+                    pool,
+                    account.share_classes,
+                )],
+                &[],
+            )
+        }
+        Command::ConfigureRevenue {
+            pool,
+            operator,
+            driver_bps,
+            maintenance_bps,
+        } => context.send(
+            &[instructions::configure_revenue(
+                payer,
+                pool,
+                operator,
+                driver_bps,
+                maintenance_bps,
+            )],
+            &[],
+        ),
+        Command::ConfigureReserve {
+            pool,
+            principal_bps,
+            target,
+        } => context.send(
+            &[instructions::configure_reserve(
+                payer,
+                pool,
+                principal_bps,
+                target,
+            )],
+            &[],
+        ),
+        Command::SpendReserve {
+            pool,
+            amount,
+            category,
+            receipt,
+            to,
+        } => {
+            let reserve: state::PoolReserve = context.fetch(&pda::find_reserve_address(&pool).0)?;
+            let receipt: Vec<u8> =
+                fs::read(&receipt).with_context(|| format!("reading {}", receipt.display()))?;
+            let to: Pubkey = to.unwrap_or_else(|| pda::get_usdc_address(&payer));
+
+            context.send(
+                &[instructions::spend_reserve(
+                    payer,
+                    pool,
+                    reserve.expenses,
+                    to,
+                    state::ReceiptedExpense {
+                        category: category.into(),
+                        amount,
+                        recipient: to,
+                        receipt_hash: hash(&receipt).to_bytes(),
+                    },
+                )],
+                &[],
+            )
+        }
+        Command::ReportRevenue { pool, amount } => {
+            let account: state::Pool = context.fetch(&pool)?;
+            context.send(
+                &[instructions::report_revenue(
+                    payer,
+                    pool,
+                    amount,
+                    account.reserve_configured,
+                )],
+                &[],
+            )
+        }
+        Command::WithdrawRevenue {
+            pool,
+            bucket,
+            amount,
+            to,
+        } => context.send(
+            &[instructions::withdraw_revenue(
+                payer,
+                pool,
+                bucket.into(),
+                to.unwrap_or_else(|| pda::get_usdc_address(&payer)),
+                amount,
+            )],
+            &[],
+        ),
+        Command::DistributeRevenue { pool, tranche } => context.send(
+            &[instructions::distribute_revenue(payer, pool, tranche)],
+            &[],
+        ),
+        Command::MigratePool { pool } => {
+            context.send(&[instructions::migrate_pool(payer, pool)], &[])
+        }
+        Command::MigrateDistribution { pool } => {
+            context.send(&[instructions::migrate_distribution(payer, pool)], &[])
+        }
+        Command::Pool { pool } => {
+            let account: state::Pool = context.fetch(&pool)?;
+            display::print(&display::pool(&pool, &account), context.output);
+            Ok(())
+        }
+        Command::Pools { creator } => {
+            let addresses: Vec<Pubkey> = match creator {
+                Some(creator) => {
+                    let pools: u64 = context
+                        .fetch_optional::<state::CreatorPools>(
+                            &pda::find_creator_pools_address(&creator).0,
+                        )?
+                        .map_or(0, |creator_pools| creator_pools.pools);
+                    (0..pools)
+                        .map(|index| pda::find_creator_pool_entry_address(&creator, index).0)
+                        .collect()
+                }
+                None => {
+                    let project: state::ProjectData =
+                        context.fetch(&pda::find_project_address().0)?;
+                    (0..project.pools)
+                        .map(|id| pda::find_pool_entry_address(id).0)
+                        .collect()
+                }
+            };
+
+            let mut entries: Vec<serde_json::Value> = Vec::with_capacity(addresses.len());
+            for chunk in addresses.chunks(100) {
+                for (address, account) in
+                    chunk.iter().zip(context.rpc.get_multiple_accounts(chunk)?)
+                {
+                    let account = account.with_context(|| format!("fetching {address}"))?;
+                    let entry: state::PoolRegistryEntry = state::decode_account(&account.data)
+                        .with_context(|| format!("decoding {address}"))?;
+                    entries.push(display::pool_entry(address, &entry));
+                }
+            }
+            display::print(&serde_json::Value::Array(entries), context.output);
+            Ok(())
+        }
+        Command::Reserve { pool } => {
+            let address: Pubkey = pda::find_reserve_address(&pool).0;
+            let account: state::PoolReserve = context.fetch(&address)?;
+            display::print(&display::reserve(&address, &account), context.output);
+            Ok(())
+        }
+        Command::Revenue { pool } => {
+            let address: Pubkey = pda::find_revenue_config_address(&pool).0;
+            let account: state::RevenueConfig = context.fetch(&address)?;
+            display::print(&display::revenue_config(&address, &account), context.output);
+            Ok(())
+        }
+        Command::Template { template } => {
+            let account: state::PoolTemplate = context.fetch(&template)?;
+            display::print(&display::pool_template(&template, &account), context.output);
+            Ok(())
+        }
+        Command::ShareClass { pool, class_id } => {
+            let address: Pubkey = pda::find_share_class_address(&pool, class_id).0;
+            let account: state::ShareClass = context.fetch(&address)?;
+            display::print(&display::share_class(&address, &account), context.output);
+            Ok(())
+        }
+        Command::Tranche { pool, index } => {
+            let address: Pubkey = pda::find_tranche_address(&pool, index).0;
+            let account: state::AssetTranche = context.fetch(&address)?;
+            display::print(&display::asset_tranche(&address, &account), context.output);
+            Ok(())
+        }
+        Command::Vehicle { vin } => {
+            let address: Pubkey = pda::find_vehicle_asset_address(&pda::hash_vin(&vin)).0;
+            let account: state::VehicleAsset = context.fetch(&address)?;
+            display::print(&display::vehicle_asset(&address, &account), context.output);
+            Ok(())
+        }
+        Command::Distribution { pool, id } => {
+            let address: Pubkey = pda::find_distribution_address(&pool, id).0;
+            let account: state::Distribution = context.fetch(&address)?;
+            display::print(&display::distribution(&address, &account), context.output);
+            Ok(())
+        }
+        Command::Position { pool, holder } => {
+            let address: Pubkey = pda::find_position_address(&pool, &holder.unwrap_or(payer)).0;
+            let account: state::InvestorPosition = context.fetch(&address)?;
+            display::print(&display::position(&address, &account), context.output);
+            Ok(())
+        }
+    }
+}
+
+impl Context {
+    fn fetch<T: anchor_lang::AccountDeserialize>(&self, address: &Pubkey) -> Result<T> {
+        let data: Vec<u8> = self
+            .rpc
+            .get_account_data(address)
+            .with_context(|| format!("fetching {address}"))?;
+        state::decode_account(&data).with_context(|| format!("decoding {address}"))
+    }
+
+    /// Like `fetch`, but `None` when the account does not exist.
+    fn fetch_optional<T: anchor_lang::AccountDeserialize>(
+        &self,
+        address: &Pubkey,
+    ) -> Result<Option<T>> {
+        let account = self
+            .rpc
+            .get_account_with_commitment(address, self.rpc.commitment())
+            .with_context(|| format!("fetching {address}"))?
+            .value;
+        account
+            .map(|account| state::decode_account(&account.data))
+            .transpose()
+            .with_context(|| format!("decoding {address}"))
+    }
+
+    /// The registry id and creator list index the next pool of `creator` takes.
+    fn registry_slots(&self, creator: &Pubkey) -> Result<(u64, u64)> {
+        let project: state::ProjectData = self.fetch(&pda::find_project_address().0)?;
+        let creator_index: u64 = self
+            .fetch_optional::<state::CreatorPools>(&pda::find_creator_pools_address(creator).0)?
+            .map_or(0, |creator_pools| creator_pools.pools);
+        Ok((project.pools, creator_index))
+    }
+
+    fn send(&self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<()> {
+        let mut all_signers: Vec<&Keypair> = vec![&self.payer];
+        all_signers.extend(
+            signers
+                .iter()
+                .filter(|signer| signer.pubkey() != self.payer.pubkey()),
+        );
+
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let transaction: Transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+
+        if !self.dry_run {
+            let signature = self.rpc.send_and_confirm_transaction(&transaction)?;
+            println!("Signature: {signature}");
+            return Ok(());
+        }
+
+        println!(
+            "Transaction: {}",
+            STANDARD.encode(bincode::serialize(&transaction)?)
+        );
+        let simulation = self.rpc.simulate_transaction(&transaction)?.value;
+        for log in simulation.logs.unwrap_or_default() {
+            println!("  {log}");
+        }
+        if let Some(units) = simulation.units_consumed {
+            println!("Compute units: {units}");
+        }
+        if let Some(err) = simulation.err {
+            bail!("simulation failed: {err}");
+        }
+        println!("Simulation succeeded");
+        Ok(())
+    }
+}
+
+fn read_keypair(path: &str) -> Result<Keypair> {
+    read_keypair_file(expand_home(path)).map_err(|err| anyhow::anyhow!("reading {path}: {err}"))
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
+
+fn default_keypair() -> String {
+    "~/.config/solana/id.json".to_string()
+}
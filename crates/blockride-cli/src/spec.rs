@@ -0,0 +1,216 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context, Result};
+use octo_client::{
+    pda,
+    state::{BuyerEligibility, LockupConfig, PoolTemplateTerms, VehicleAssetDetails},
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use solana_sdk::{hash::hash, pubkey::Pubkey};
+
+/// A pool definition read from a TOML or JSON file.
+///
+/// ```toml
+/// authority = "..."
+/// shares = 1000
+/// deposit = 100000000
+/// name = "BlockRide Pool #12"
+/// symbol = "BRP12"
+/// uri = "https://..."
+/// start_date = 1735689600
+/// maturity_date = 1767225600
+/// apy = 12
+///
+/// [lockup]
+/// cliff = 0
+/// duration = 2592000
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct PoolSpec {
+    /// Seed of the pool address; a fresh one is generated when omitted.
+    pub reference: Option<String>,
+    pub authority: String,
+    pub shares: u64,
+    pub deposit: u64,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub start_date: u64,
+    pub maturity_date: u64,
+    pub apy: u8,
+    pub lockup: Option<LockupSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LockupSpec {
+    pub cliff: u64,
+    pub duration: u64,
+}
+
+impl PoolSpec {
+    pub fn read(path: &Path) -> Result<Self> {
+        read_spec(path, "pool")
+    }
+
+    pub fn reference(&self) -> Result<Option<Pubkey>> {
+        self.reference
+            .as_deref()
+            .map(parse_pubkey)
+            .transpose()
+            .context("invalid reference")
+    }
+
+    pub fn authority(&self) -> Result<Pubkey> {
+        parse_pubkey(&self.authority).context("invalid authority")
+    }
+
+    pub fn lockup(&self) -> Option<LockupConfig> {
+        self.lockup.as_ref().map(|lockup| LockupConfig {
+            cliff: lockup.cliff,
+            duration: lockup.duration,
+        })
+    }
+}
+
+/// The terms of a recurring pool series read from a TOML or JSON file.
+///
+/// ```toml
+/// authority = "..."
+/// name = "BlockRide Weekly"
+/// symbol = "BRW"
+/// uri_pattern = "https://.../weekly/{}.json"
+/// share_price = 1000000
+/// term = 31536000
+/// apy = 12
+/// eligibility = "open"
+///
+/// [lockup]
+/// cliff = 0
+/// duration = 2592000
+/// ```
+///
+/// `{}` in `uri_pattern` becomes each pool's number within the series.
+#[derive(Debug, Deserialize)]
+pub struct TemplateSpec {
+    pub authority: String,
+    pub name: String,
+    pub symbol: String,
+    pub uri_pattern: String,
+    /// USDC, in base units, each share is issued at.
+    pub share_price: u64,
+    /// Seconds from a pool's start date to its maturity date.
+    pub term: u64,
+    pub apy: u8,
+    #[serde(default)]
+    pub eligibility: EligibilitySpec,
+    pub lockup: Option<LockupSpec>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EligibilitySpec {
+    #[default]
+    Open,
+    Creator,
+    Approved,
+}
+
+impl TemplateSpec {
+    pub fn read(path: &Path) -> Result<Self> {
+        read_spec(path, "template")
+    }
+
+    pub fn terms(&self) -> Result<PoolTemplateTerms> {
+        Ok(PoolTemplateTerms {
+            authority: parse_pubkey(&self.authority).context("invalid authority")?,
+            name: self.name.clone(),
+            symbol: self.symbol.clone(),
+            uri_pattern: self.uri_pattern.clone(),
+            share_price: self.share_price,
+            term: self.term,
+            apy: self.apy,
+            lockup: self.lockup.as_ref().map(|lockup| LockupConfig {
+                cliff: lockup.cliff,
+                duration: lockup.duration,
+            }),
+            eligibility: match self.eligibility {
+                EligibilitySpec::Open => BuyerEligibility::Open,
+                EligibilitySpec::Creator => BuyerEligibility::Creator,
+                EligibilitySpec::Approved => BuyerEligibility::Approved,
+            },
+        })
+    }
+}
+
+/// A vehicle asset definition read from a TOML or JSON file.
+///
+/// ```toml
+/// vin = "JTDBR32E720123456"
+/// make = "Toyota"
+/// model = "Corolla"
+/// purchase_price = 12000000000
+/// jurisdiction = "NG-LA"
+/// custodian = "..."
+/// title_document = "title.pdf"
+/// ```
+///
+/// Only hashes of the VIN and of the title document go on-chain.
+#[derive(Debug, Deserialize)]
+pub struct VehicleSpec {
+    pub vin: String,
+    pub make: String,
+    pub model: String,
+    pub purchase_price: u64,
+    pub jurisdiction: String,
+    pub custodian: String,
+    /// Path of the title document, relative to the spec file.
+    pub title_document: PathBuf,
+}
+
+impl VehicleSpec {
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut spec: Self = read_spec(path, "vehicle")?;
+        if let Some(directory) = path.parent() {
+            spec.title_document = directory.join(&spec.title_document);
+        }
+        Ok(spec)
+    }
+
+    pub fn details(&self) -> Result<VehicleAssetDetails> {
+        let title: Vec<u8> = fs::read(&self.title_document)
+            .with_context(|| format!("reading {}", self.title_document.display()))?;
+
+        Ok(VehicleAssetDetails {
+            vin_hash: pda::hash_vin(&self.vin),
+            make: self.make.clone(),
+            model: self.model.clone(),
+            purchase_price: self.purchase_price,
+            jurisdiction: self.jurisdiction.clone(),
+            custodian: parse_pubkey(&self.custodian).context("invalid custodian")?,
+            title_hash: hash(&title).to_bytes(),
+        })
+    }
+}
+
+fn read_spec<T: DeserializeOwned>(path: &Path, kind: &str) -> Result<T> {
+    let contents: String =
+        fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => {
+            toml::from_str(&contents).with_context(|| format!("parsing TOML {kind} spec"))
+        }
+        Some("json") => {
+            serde_json::from_str(&contents).with_context(|| format!("parsing JSON {kind} spec"))
+        }
+        _ => bail!("{kind} spec must be a .toml or .json file"),
+    }
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey> {
+    Ok(Pubkey::from_str(value)?)
+}
@@ -0,0 +1,656 @@
+use std::{collections::HashSet, path::Path, str::FromStr};
+
+use anyhow::{Context, Result};
+use octo_program::ID;
+use rusqlite::{params, Connection, OptionalExtension};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    decode::{self, Activity, State},
+    source::{AccountUpdate, Transaction, Update},
+};
+
+/// Every table carries the slot its row was written at, which is what resyncing rolls
+/// back by. `accounts` keeps each account update so the state tables can be rebuilt as
+/// of an earlier slot.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transactions (
+    signature TEXT PRIMARY KEY,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    failed INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS accounts (
+    address TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    owner TEXT NOT NULL,
+    lamports INTEGER NOT NULL,
+    data BLOB NOT NULL,
+    PRIMARY KEY (address, slot)
+);
+CREATE TABLE IF NOT EXISTS closed_accounts (
+    address TEXT PRIMARY KEY,
+    slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS pools (
+    address TEXT PRIMARY KEY,
+    id INTEGER,
+    creator TEXT NOT NULL,
+    authority TEXT NOT NULL,
+    reference TEXT NOT NULL,
+    mint TEXT NOT NULL,
+    shares INTEGER NOT NULL,
+    minted INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    start_date INTEGER NOT NULL,
+    maturity_date INTEGER NOT NULL,
+    apy INTEGER NOT NULL,
+    nav_per_share INTEGER NOT NULL,
+    nav_updated_at INTEGER NOT NULL,
+    slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS distributions (
+    address TEXT PRIMARY KEY,
+    pool TEXT NOT NULL,
+    id INTEGER NOT NULL,
+    authority TEXT NOT NULL,
+    rewards INTEGER NOT NULL,
+    claimed INTEGER NOT NULL,
+    allocated INTEGER NOT NULL,
+    slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS holders (
+    address TEXT PRIMARY KEY,
+    pool TEXT NOT NULL,
+    holder TEXT NOT NULL,
+    shares INTEGER NOT NULL,
+    cost_basis INTEGER NOT NULL,
+    realized_cost_basis INTEGER NOT NULL,
+    principal_returned INTEGER NOT NULL,
+    rewards_claimed INTEGER NOT NULL,
+    opened_at INTEGER NOT NULL,
+    last_acquired_at INTEGER NOT NULL,
+    slot INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS holders_by_pool ON holders (pool, holder);
+CREATE TABLE IF NOT EXISTS purchases (
+    signature TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    pool TEXT NOT NULL,
+    buyer TEXT NOT NULL,
+    seller TEXT,
+    shares INTEGER NOT NULL,
+    cost INTEGER NOT NULL,
+    PRIMARY KEY (signature, seq)
+);
+CREATE INDEX IF NOT EXISTS purchases_by_pool ON purchases (pool, slot);
+CREATE TABLE IF NOT EXISTS claims (
+    signature TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    pool TEXT NOT NULL,
+    distribution TEXT NOT NULL,
+    holder TEXT NOT NULL,
+    share_class TEXT,
+    amount INTEGER NOT NULL,
+    PRIMARY KEY (signature, seq)
+);
+CREATE INDEX IF NOT EXISTS claims_by_pool ON claims (pool, slot);
+CREATE TABLE IF NOT EXISTS withdrawals (
+    signature TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    pool TEXT NOT NULL,
+    destination TEXT NOT NULL,
+    shares INTEGER NOT NULL,
+    amount INTEGER NOT NULL,
+    reserved INTEGER NOT NULL,
+    PRIMARY KEY (signature, seq)
+);
+CREATE INDEX IF NOT EXISTS withdrawals_by_pool ON withdrawals (pool, slot);
+";
+
+const TABLES: [&str; 9] = [
+    "transactions",
+    "accounts",
+    "closed_accounts",
+    "pools",
+    "distributions",
+    "holders",
+    "purchases",
+    "claims",
+    "withdrawals",
+];
+
+const STATE_TABLES: [&str; 3] = ["pools", "distributions", "holders"];
+
+/// What one call to `Database::index` wrote.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Summary {
+    pub transactions: u64,
+    /// Transactions indexed by an earlier run and left alone.
+    pub duplicates: u64,
+    pub activity: u64,
+    pub accounts: u64,
+}
+
+pub struct Database {
+    connection: Connection,
+}
+
+impl Database {
+    pub fn open(path: &Path) -> Result<Self> {
+        let connection: Connection =
+            Connection::open(path).with_context(|| format!("opening {}", path.display()))?;
+        Self::new(connection)
+    }
+
+    fn new(connection: Connection) -> Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// The newest indexed transaction, which RPC syncs continue from.
+    pub fn get_last_transaction(&self) -> Result<Option<(String, u64)>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT signature, slot FROM transactions ORDER BY slot DESC LIMIT 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?)
+    }
+
+    /// Forgets everything written at or after `slot` so it can be indexed again. Accounts
+    /// updated since go back to the state of their last earlier update.
+    pub fn resync(&mut self, slot: u64) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        let addresses: Vec<String> = transaction
+            .prepare("SELECT DISTINCT address FROM accounts WHERE slot >= ?1")?
+            .query_map([slot], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        for table in TABLES {
+            transaction.execute(&format!("DELETE FROM {table} WHERE slot >= ?1"), [slot])?;
+        }
+
+        for address in &addresses {
+            for table in STATE_TABLES.into_iter().chain(["closed_accounts"]) {
+                transaction.execute(
+                    &format!("DELETE FROM {table} WHERE address = ?1"),
+                    [address],
+                )?;
+            }
+            let earlier: Option<(u64, String, u64, Vec<u8>)> = transaction
+                .query_row(
+                    "SELECT slot, owner, lamports, data FROM accounts
+                     WHERE address = ?1 ORDER BY slot DESC LIMIT 1",
+                    [address],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )
+                .optional()?;
+            if let Some((slot, owner, lamports, data)) = earlier {
+                write_account(
+                    &transaction,
+                    &AccountUpdate {
+                        slot,
+                        pubkey: Pubkey::from_str(address)?,
+                        owner: Pubkey::from_str(&owner)?,
+                        lamports,
+                        data,
+                    },
+                )?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Writes `updates` in a single database transaction. Transactions already indexed
+    /// are skipped and account state never moves back to an older slot, so replaying the
+    /// same input is a no-op.
+    pub fn index(&mut self, updates: &[Update]) -> Result<Summary> {
+        let transaction = self.connection.transaction()?;
+        let mut summary: Summary = Summary::default();
+        for update in updates {
+            match update {
+                Update::Transaction(program_transaction) => {
+                    if insert_transaction(&transaction, program_transaction, &mut summary)? {
+                        summary.transactions += 1;
+                    } else {
+                        summary.duplicates += 1;
+                    }
+                }
+                Update::Account(account) => {
+                    if write_account(&transaction, account)? {
+                        summary.accounts += 1;
+                    }
+                }
+                Update::Snapshot { slot, accounts } => {
+                    summary.accounts += close_missing(&transaction, *slot, accounts)?;
+                }
+            }
+        }
+        transaction.commit()?;
+        Ok(summary)
+    }
+}
+
+fn insert_transaction(
+    connection: &Connection,
+    transaction: &Transaction,
+    summary: &mut Summary,
+) -> Result<bool> {
+    let inserted: usize = connection.execute(
+        "INSERT OR IGNORE INTO transactions (signature, slot, block_time, failed)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            transaction.signature,
+            transaction.slot,
+            transaction.block_time,
+            transaction.failed
+        ],
+    )?;
+    if inserted == 0 || transaction.failed {
+        return Ok(inserted > 0);
+    }
+
+    let activity: Vec<Activity> = decode::decode_activity(transaction)
+        .with_context(|| format!("decoding transaction {}", transaction.signature))?;
+    for (seq, activity) in activity.iter().enumerate() {
+        match activity {
+            Activity::Purchase {
+                pool,
+                buyer,
+                seller,
+                shares,
+                cost,
+            } => connection.execute(
+                "INSERT INTO purchases
+                 (signature, seq, slot, block_time, pool, buyer, seller, shares, cost)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    transaction.signature,
+                    seq,
+                    transaction.slot,
+                    transaction.block_time,
+                    pool.to_string(),
+                    buyer.to_string(),
+                    seller.map(|seller| seller.to_string()),
+                    shares,
+                    cost
+                ],
+            )?,
+            Activity::Claim {
+                pool,
+                distribution,
+                holder,
+                share_class,
+                amount,
+            } => connection.execute(
+                "INSERT INTO claims
+                 (signature, seq, slot, block_time, pool, distribution, holder, share_class, amount)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    transaction.signature,
+                    seq,
+                    transaction.slot,
+                    transaction.block_time,
+                    pool.to_string(),
+                    distribution.to_string(),
+                    holder.to_string(),
+                    share_class.map(|share_class| share_class.to_string()),
+                    amount
+                ],
+            )?,
+            Activity::Withdrawal {
+                pool,
+                to,
+                shares,
+                amount,
+                reserved,
+            } => connection.execute(
+                "INSERT INTO withdrawals
+                 (signature, seq, slot, block_time, pool, destination, shares, amount, reserved)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    transaction.signature,
+                    seq,
+                    transaction.slot,
+                    transaction.block_time,
+                    pool.to_string(),
+                    to.to_string(),
+                    shares,
+                    amount,
+                    reserved
+                ],
+            )?,
+        };
+        summary.activity += 1;
+    }
+    Ok(true)
+}
+
+/// Closes the mirrored accounts a snapshot taken at `slot` no longer has, returning how
+/// many there were.
+fn close_missing(connection: &Connection, slot: u64, accounts: &[Pubkey]) -> Result<u64> {
+    let accounts: HashSet<String> = accounts.iter().map(ToString::to_string).collect();
+    let mut closed: u64 = 0;
+    for table in STATE_TABLES {
+        let missing: Vec<String> = connection
+            .prepare(&format!("SELECT address FROM {table} WHERE slot <= ?1"))?
+            .query_map([slot], |row| row.get(0))?
+            .filter(|address| {
+                address
+                    .as_ref()
+                    .map_or(true, |address| !accounts.contains(address))
+            })
+            .collect::<rusqlite::Result<_>>()?;
+        for address in missing {
+            let account: AccountUpdate = AccountUpdate {
+                slot,
+                pubkey: Pubkey::from_str(&address)?,
+                owner: ID,
+                lamports: 0,
+                data: Vec::new(),
+            };
+            if write_account(connection, &account)? {
+                closed += 1;
+            }
+        }
+    }
+    Ok(closed)
+}
+
+/// Mirrors an account into its state table, or removes it once closed. Returns whether
+/// anything was written.
+fn write_account(connection: &Connection, account: &AccountUpdate) -> Result<bool> {
+    let address: String = account.pubkey.to_string();
+    connection.execute(
+        "INSERT OR IGNORE INTO accounts (address, slot, owner, lamports, data)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            address,
+            account.slot,
+            account.owner.to_string(),
+            account.lamports,
+            account.data
+        ],
+    )?;
+    let closed_at: Option<u64> = connection
+        .query_row(
+            "SELECT slot FROM closed_accounts WHERE address = ?1",
+            [&address],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if closed_at.is_some_and(|closed_at| closed_at > account.slot) {
+        return Ok(false);
+    }
+
+    if account.lamports == 0 {
+        let mut written: usize = connection.execute(
+            "INSERT INTO closed_accounts (address, slot) VALUES (?1, ?2)
+             ON CONFLICT (address) DO UPDATE SET slot = excluded.slot",
+            params![address, account.slot],
+        )?;
+        for table in STATE_TABLES {
+            written += connection.execute(
+                &format!("DELETE FROM {table} WHERE address = ?1 AND slot <= ?2"),
+                params![address, account.slot],
+            )?;
+        }
+        return Ok(written > 0);
+    }
+
+    let Some(state) = decode::decode_state(account) else {
+        return Ok(false);
+    };
+    let written: usize = match state {
+        State::Pool(pool) => connection.execute(
+            "INSERT INTO pools
+             (address, id, creator, authority, reference, mint, shares, minted, status,
+              start_date, maturity_date, apy, nav_per_share, nav_updated_at, slot)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+             ON CONFLICT (address) DO UPDATE SET
+                id = excluded.id, authority = excluded.authority, shares = excluded.shares,
+                minted = excluded.minted, status = excluded.status,
+                start_date = excluded.start_date, maturity_date = excluded.maturity_date,
+                apy = excluded.apy, nav_per_share = excluded.nav_per_share,
+                nav_updated_at = excluded.nav_updated_at, slot = excluded.slot
+             WHERE excluded.slot >= pools.slot",
+            params![
+                address,
+                pool.id,
+                pool.creator.to_string(),
+                pool.authority.to_string(),
+                pool.reference.to_string(),
+                pool.mint.to_string(),
+                pool.shares,
+                pool.minted,
+                format!("{:?}", pool.status),
+                pool.start_date,
+                pool.maturity_date,
+                pool.apy,
+                pool.nav_per_share,
+                pool.nav_updated_at,
+                account.slot
+            ],
+        )?,
+        State::Distribution(distribution) => connection.execute(
+            "INSERT INTO distributions
+             (address, pool, id, authority, rewards, claimed, allocated, slot)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT (address) DO UPDATE SET
+                authority = excluded.authority, rewards = excluded.rewards,
+                claimed = excluded.claimed, allocated = excluded.allocated,
+                slot = excluded.slot
+             WHERE excluded.slot >= distributions.slot",
+            params![
+                address,
+                distribution.pool.to_string(),
+                distribution.id,
+                distribution.authority.to_string(),
+                distribution.rewards,
+                distribution.claimed,
+                distribution.allocated,
+                account.slot
+            ],
+        )?,
+        State::Position(position) => connection.execute(
+            "INSERT INTO holders
+             (address, pool, holder, shares, cost_basis, realized_cost_basis,
+              principal_returned, rewards_claimed, opened_at, last_acquired_at, slot)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+             ON CONFLICT (address) DO UPDATE SET
+                shares = excluded.shares, cost_basis = excluded.cost_basis,
+                realized_cost_basis = excluded.realized_cost_basis,
+                principal_returned = excluded.principal_returned,
+                rewards_claimed = excluded.rewards_claimed, opened_at = excluded.opened_at,
+                last_acquired_at = excluded.last_acquired_at, slot = excluded.slot
+             WHERE excluded.slot >= holders.slot",
+            params![
+                address,
+                position.pool.to_string(),
+                position.holder.to_string(),
+                position.shares,
+                position.cost_basis,
+                position.realized_cost_basis,
+                position.principal_returned,
+                position.rewards_claimed,
+                position.opened_at,
+                position.last_acquired_at,
+                account.slot
+            ],
+        )?,
+    };
+    Ok(written > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountSerialize, Event};
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    use octo_program::{InvestorPosition, SharesPurchased};
+
+    use super::*;
+
+    fn position(slot: u64, address: Pubkey, shares: u64) -> Update {
+        let position: InvestorPosition = InvestorPosition {
+            version: InvestorPosition::VERSION,
+            pool: Pubkey::default(),
+            holder: Pubkey::default(),
+            shares,
+            cost_basis: shares * 1_000_000,
+            realized_cost_basis: 0,
+            principal_returned: 0,
+            rewards_claimed: 0,
+            opened_at: 0,
+            last_acquired_at: 0,
+            bump: 0,
+            reward_per_share_paid: 0,
+            rewards_owed: 0,
+            reserved: [0; 8],
+        };
+        let mut data: Vec<u8> = Vec::new();
+        position.try_serialize(&mut data).unwrap();
+        Update::Account(AccountUpdate {
+            slot,
+            pubkey: address,
+            owner: ID,
+            lamports: 1,
+            data,
+        })
+    }
+
+    fn purchase(slot: u64, signature: &str) -> Update {
+        let event: SharesPurchased = SharesPurchased {
+            pool: Pubkey::default(),
+            buyer: Pubkey::default(),
+            shares: 1,
+            cost: 1_000_000,
+        };
+        Update::Transaction(Transaction {
+            signature: signature.to_string(),
+            slot,
+            block_time: None,
+            failed: false,
+            instructions: Vec::new(),
+            logs: vec![
+                format!("Program {ID} invoke [1]"),
+                format!("Program data: {}", STANDARD.encode(event.data())),
+                format!("Program {ID} success"),
+            ],
+        })
+    }
+
+    fn count(db: &Database, table: &str) -> u64 {
+        db.connection
+            .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+
+    fn shares(db: &Database, address: &Pubkey) -> Option<u64> {
+        db.connection
+            .query_row(
+                "SELECT shares FROM holders WHERE address = ?1",
+                [address.to_string()],
+                |row| row.get(0),
+            )
+            .optional()
+            .unwrap()
+    }
+
+    #[test]
+    fn replaying_the_same_input_changes_nothing() {
+        let mut db: Database = Database::new(Connection::open_in_memory().unwrap()).unwrap();
+        let address: Pubkey = Pubkey::new_unique();
+        let updates: Vec<Update> = vec![
+            purchase(10, "first"),
+            position(10, address, 5),
+            purchase(20, "second"),
+            position(20, address, 8),
+        ];
+
+        let summary: Summary = db.index(&updates).unwrap();
+        assert_eq!(summary.transactions, 2);
+        assert_eq!(summary.activity, 2);
+        let summary: Summary = db.index(&updates).unwrap();
+        assert_eq!(summary.transactions, 0);
+        assert_eq!(summary.duplicates, 2);
+        assert_eq!(summary.activity, 0);
+        assert_eq!(count(&db, "purchases"), 2);
+
+        // An older update arriving late leaves the newer state alone
+        db.index(&[position(10, address, 5)]).unwrap();
+        assert_eq!(shares(&db, &address), Some(8));
+    }
+
+    #[test]
+    fn resync_restores_the_earlier_state() {
+        let mut db: Database = Database::new(Connection::open_in_memory().unwrap()).unwrap();
+        let updated: Pubkey = Pubkey::new_unique();
+        let closed: Pubkey = Pubkey::new_unique();
+        db.index(&[
+            purchase(10, "first"),
+            position(10, updated, 5),
+            position(10, closed, 3),
+            purchase(20, "second"),
+            position(20, updated, 8),
+            Update::Account(AccountUpdate {
+                slot: 20,
+                pubkey: closed,
+                owner: ID,
+                lamports: 0,
+                data: Vec::new(),
+            }),
+        ])
+        .unwrap();
+        assert_eq!(shares(&db, &closed), None);
+
+        db.resync(15).unwrap();
+        assert_eq!(shares(&db, &updated), Some(5));
+        assert_eq!(shares(&db, &closed), Some(3));
+        assert_eq!(count(&db, "purchases"), 1);
+        assert_eq!(count(&db, "closed_accounts"), 0);
+
+        // Indexing the slot again lands where the first run did
+        let summary: Summary = db
+            .index(&[purchase(20, "second"), position(20, updated, 8)])
+            .unwrap();
+        assert_eq!(summary.transactions, 1);
+        assert_eq!(shares(&db, &updated), Some(8));
+    }
+
+    #[test]
+    fn snapshots_close_the_accounts_they_miss() {
+        let mut db: Database = Database::new(Connection::open_in_memory().unwrap()).unwrap();
+        let kept: Pubkey = Pubkey::new_unique();
+        let closed: Pubkey = Pubkey::new_unique();
+        db.index(&[position(10, kept, 5), position(10, closed, 3)])
+            .unwrap();
+
+        let summary: Summary = db
+            .index(&[
+                position(30, kept, 5),
+                Update::Snapshot {
+                    slot: 30,
+                    accounts: vec![kept],
+                },
+            ])
+            .unwrap();
+        assert_eq!(summary.accounts, 2);
+        assert_eq!(shares(&db, &kept), Some(5));
+        assert_eq!(shares(&db, &closed), None);
+
+        // Replaying the account from before the snapshot does not bring it back
+        db.index(&[position(10, closed, 3)]).unwrap();
+        assert_eq!(shares(&db, &closed), None);
+    }
+}
@@ -0,0 +1,278 @@
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use octo_program::{
+    instruction as ix, Distribution, InvestorPosition, Pool, PrincipalWithdrawn, SharesPurchased,
+    SharesTraded, ID,
+};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::source::{AccountUpdate, Instruction, Transaction};
+
+/// Positions of the claimed accounts in `ClaimRewards` and `ClaimClassRewards`.
+const CLAIM_HOLDER: usize = 0;
+const CLAIM_POOL: usize = 2;
+const CLAIM_DISTRIBUTION: usize = 3;
+const CLAIM_SHARE_CLASS: usize = 4;
+
+/// Something a transaction did, in the order it happened within the transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Activity {
+    /// Shares bought from the pool (no seller) or from a listing.
+    Purchase {
+        pool: Pubkey,
+        buyer: Pubkey,
+        seller: Option<Pubkey>,
+        shares: u64,
+        cost: u64,
+    },
+    Claim {
+        pool: Pubkey,
+        distribution: Pubkey,
+        holder: Pubkey,
+        share_class: Option<Pubkey>,
+        amount: u64,
+    },
+    Withdrawal {
+        pool: Pubkey,
+        to: Pubkey,
+        shares: u64,
+        amount: u64,
+        reserved: u64,
+    },
+}
+
+/// Program accounts mirrored into the state tables.
+#[derive(Clone)]
+pub enum State {
+    Pool(Box<Pool>),
+    Distribution(Distribution),
+    Position(InvestorPosition),
+}
+
+/// Decodes the claims from the program's instructions and everything else from its events.
+pub fn decode_activity(transaction: &Transaction) -> Result<Vec<Activity>> {
+    let mut activity: Vec<Activity> = Vec::new();
+    for instruction in &transaction.instructions {
+        if instruction.program_id == ID {
+            activity.extend(decode_instruction(instruction)?);
+        }
+    }
+    for data in get_program_events(&transaction.logs) {
+        activity.extend(decode_event(&data)?);
+    }
+    Ok(activity)
+}
+
+/// Decoded `Program data:` logs written while the program was the one executing. The
+/// invoke, success and failed logs track which program that is.
+fn get_program_events(logs: &[String]) -> Vec<Vec<u8>> {
+    let program_id: String = ID.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut events: Vec<Vec<u8>> = Vec::new();
+    for log in logs {
+        let Some(message) = log.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(data) = message.strip_prefix("data: ") {
+            if stack.last() == Some(&program_id.as_str()) {
+                if let Ok(data) = STANDARD.decode(data) {
+                    events.push(data);
+                }
+            }
+        } else if let Some((program, status)) = message.split_once(' ') {
+            if status.starts_with("invoke [") {
+                stack.push(program);
+            } else if status == "success" || status.starts_with("failed") {
+                stack.pop();
+            }
+        }
+    }
+    events
+}
+
+fn decode_instruction(instruction: &Instruction) -> Result<Option<Activity>> {
+    if instruction.data.len() < 8 {
+        return Ok(None);
+    }
+    let account = |position: usize| -> Result<Pubkey> {
+        instruction
+            .accounts
+            .get(position)
+            .copied()
+            .with_context(|| format!("missing account {position} of a claim"))
+    };
+    let (discriminator, args) = instruction.data.split_at(8);
+
+    let (amount, share_class): (u64, Option<Pubkey>) =
+        if discriminator == ix::ClaimRewards::DISCRIMINATOR {
+            (ix::ClaimRewards::try_from_slice(args)?.rewards, None)
+        } else if discriminator == ix::ClaimClassRewards::DISCRIMINATOR {
+            (
+                ix::ClaimClassRewards::try_from_slice(args)?.rewards,
+                Some(account(CLAIM_SHARE_CLASS)?),
+            )
+        } else {
+            return Ok(None);
+        };
+    Ok(Some(Activity::Claim {
+        pool: account(CLAIM_POOL)?,
+        distribution: account(CLAIM_DISTRIBUTION)?,
+        holder: account(CLAIM_HOLDER)?,
+        share_class,
+        amount,
+    }))
+}
+
+fn decode_event(data: &[u8]) -> Result<Option<Activity>> {
+    if data.len() < 8 {
+        return Ok(None);
+    }
+    let (discriminator, mut event) = data.split_at(8);
+
+    let activity: Activity = if discriminator == SharesPurchased::DISCRIMINATOR {
+        let event: SharesPurchased = AnchorDeserialize::deserialize(&mut event)?;
+        Activity::Purchase {
+            pool: event.pool,
+            buyer: event.buyer,
+            seller: None,
+            shares: event.shares,
+            cost: event.cost,
+        }
+    } else if discriminator == SharesTraded::DISCRIMINATOR {
+        let event: SharesTraded = AnchorDeserialize::deserialize(&mut event)?;
+        Activity::Purchase {
+            pool: event.pool,
+            buyer: event.buyer,
+            seller: Some(event.seller),
+            shares: event.shares,
+            cost: event
+                .shares
+                .checked_mul(event.price_per_share)
+                .context("trade price overflows")?,
+        }
+    } else if discriminator == PrincipalWithdrawn::DISCRIMINATOR {
+        let event: PrincipalWithdrawn = AnchorDeserialize::deserialize(&mut event)?;
+        Activity::Withdrawal {
+            pool: event.pool,
+            to: event.to,
+            shares: event.shares,
+            amount: event.amount,
+            reserved: event.reserved,
+        }
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(activity))
+}
+
+/// Decodes the accounts the indexer mirrors. Other accounts, and accounts still in a
+/// layout that predates versioning, decode to `None`.
+pub fn decode_state(account: &AccountUpdate) -> Option<State> {
+    if account.owner != ID || account.data.len() < 8 {
+        return None;
+    }
+    let discriminator: &[u8] = &account.data[..8];
+    let mut data: &[u8] = &account.data;
+
+    if discriminator == Pool::DISCRIMINATOR {
+        Pool::try_deserialize(&mut data)
+            .ok()
+            .map(|pool| State::Pool(Box::new(pool)))
+    } else if discriminator == Distribution::DISCRIMINATOR {
+        Distribution::try_deserialize(&mut data)
+            .ok()
+            .map(State::Distribution)
+    } else if discriminator == InvestorPosition::DISCRIMINATOR {
+        InvestorPosition::try_deserialize(&mut data)
+            .ok()
+            .map(State::Position)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{Event, InstructionData};
+
+    use super::*;
+
+    fn transaction(instructions: Vec<Instruction>, logs: Vec<String>) -> Transaction {
+        Transaction {
+            signature: "5ig".to_string(),
+            slot: 10,
+            block_time: Some(1_700_000_000),
+            failed: false,
+            instructions,
+            logs,
+        }
+    }
+
+    #[test]
+    fn only_the_program_events_are_decoded() {
+        let pool: Pubkey = Pubkey::new_unique();
+        let buyer: Pubkey = Pubkey::new_unique();
+        let other: Pubkey = Pubkey::new_unique();
+        let purchase = |shares: u64| {
+            let event: SharesPurchased = SharesPurchased {
+                pool,
+                buyer,
+                shares,
+                cost: shares * 1_000_000,
+            };
+            format!("Program data: {}", STANDARD.encode(event.data()))
+        };
+
+        let logs: Vec<String> = vec![
+            format!("Program {ID} invoke [1]"),
+            format!("Program {other} invoke [2]"),
+            purchase(1),
+            format!("Program {other} success"),
+            purchase(10),
+            format!("Program {ID} success"),
+            format!("Program {other} invoke [1]"),
+            purchase(100),
+            format!("Program {other} failed: custom program error: 0x0"),
+        ];
+
+        assert_eq!(
+            decode_activity(&transaction(Vec::new(), logs)).unwrap(),
+            [Activity::Purchase {
+                pool,
+                buyer,
+                seller: None,
+                shares: 10,
+                cost: 10_000_000,
+            }]
+        );
+    }
+
+    #[test]
+    fn class_claims_name_their_share_class() {
+        let [holder, pool, distribution, share_class]: [Pubkey; 4] =
+            std::array::from_fn(|_| Pubkey::new_unique());
+        let claim: Instruction = Instruction {
+            program_id: ID,
+            accounts: vec![
+                holder,
+                Pubkey::new_unique(),
+                pool,
+                distribution,
+                share_class,
+            ],
+            data: ix::ClaimClassRewards { rewards: 5 }.data(),
+        };
+
+        assert_eq!(
+            decode_activity(&transaction(vec![claim], Vec::new())).unwrap(),
+            [Activity::Claim {
+                pool,
+                distribution,
+                holder,
+                share_class: Some(share_class),
+                amount: 5,
+            }]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Materializes octo-program state into SQLite for dashboards.
+//!
+//! Transactions fill the activity tables (`purchases`, `claims`, `withdrawals`) and account
+//! updates fill the state tables (`pools`, `distributions`, `holders`). Input can be
+//! exported `getTransaction` results, a JSONL feed written by a Geyser plugin, a
+//! validator ledger directory or a cluster's RPC. Indexing the same input twice changes
+//! nothing, and `--from-slot` rolls the database back to a slot before indexing again
+//! from there.
+
+mod db;
+mod decode;
+mod source;
+
+use std::{path::PathBuf, str::FromStr};
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+
+use crate::{
+    db::{Database, Summary},
+    source::Update,
+};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "blockride-indexer",
+    about = "Index the BlockRide octo-program into SQLite"
+)]
+struct Cli {
+    /// SQLite database to write, created when missing.
+    #[arg(long, default_value = "blockride.sqlite")]
+    db: PathBuf,
+
+    /// Drop everything indexed at or after this slot and index it again.
+    #[arg(long, global = true)]
+    from_slot: Option<u64>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Index exported `getTransaction` results: a JSON array, one per line, or JSON-RPC
+    /// responses.
+    Replay {
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+    },
+    /// Index a JSONL feed of `{"account": …}` and `{"transaction": …}` lines; `-` reads
+    /// stdin.
+    Feed {
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+    },
+    /// Index the program's transactions in a validator ledger directory, read with
+    /// `solana-ledger-tool`.
+    Ledger {
+        ledger: PathBuf,
+
+        /// Last slot to index; defaults to the newest slot in the ledger.
+        #[arg(long)]
+        to_slot: Option<u64>,
+    },
+    /// Index the transactions since the last sync and snapshot the program's accounts.
+    Sync {
+        /// RPC endpoint of the cluster.
+        #[arg(
+            long,
+            short,
+            env = "BLOCKRIDE_URL",
+            default_value = "http://127.0.0.1:8899"
+        )]
+        url: String,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli: Cli = Cli::parse();
+    let mut db: Database = Database::open(&cli.db)?;
+    if let Some(from_slot) = cli.from_slot {
+        db.resync(from_slot)?;
+    }
+    let from_slot: u64 = cli.from_slot.unwrap_or_default();
+
+    let mut updates: Vec<Update> = match &cli.command {
+        Command::Replay { inputs } => inputs
+            .iter()
+            .map(|input| source::load_replay(input))
+            .collect::<Result<Vec<_>>>()?
+            .concat(),
+        Command::Feed { inputs } => inputs
+            .iter()
+            .map(|input| source::load_feed(input))
+            .collect::<Result<Vec<_>>>()?
+            .concat(),
+        Command::Ledger { ledger, to_slot } => source::load_ledger(ledger, from_slot, *to_slot)?,
+        Command::Sync { url } => {
+            let rpc: RpcClient =
+                RpcClient::new_with_commitment(url.clone(), CommitmentConfig::confirmed());
+            // A resync walks back to its slot; otherwise continue after the last transaction
+            let until: Option<Signature> = match cli.from_slot {
+                Some(_) => None,
+                None => db
+                    .get_last_transaction()?
+                    .map(|(signature, _)| Signature::from_str(&signature))
+                    .transpose()?,
+            };
+            let mut updates: Vec<Update> = source::fetch_transactions(&rpc, from_slot, until)?;
+            updates.extend(source::fetch_accounts(&rpc)?);
+            updates
+        }
+    };
+
+    // Input of one slot keeps the order it was read in
+    updates.retain(|update| update.get_slot() >= from_slot);
+    updates.sort_by_key(Update::get_slot);
+
+    let summary: Summary = db.index(&updates)?;
+    eprintln!(
+        "Indexed {} transactions ({} already indexed), {} activity rows and {} accounts into {}",
+        summary.transactions,
+        summary.duplicates,
+        summary.activity,
+        summary.accounts,
+        cli.db.display()
+    );
+    Ok(())
+}
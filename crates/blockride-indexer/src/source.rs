@@ -0,0 +1,505 @@
+use std::{
+    fs,
+    io::{self, BufRead, BufReader},
+    path::Path,
+    process::{Command, Output},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use octo_program::ID;
+use serde::Deserialize;
+use serde_json::Value;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionConfig},
+};
+use solana_sdk::{bs58, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction,
+    EncodedTransactionWithStatusMeta, UiInnerInstructions, UiInstruction, UiLoadedAddresses,
+    UiMessage, UiTransactionEncoding,
+};
+
+/// Most signatures `getSignaturesForAddress` returns per call.
+const SIGNATURES_PER_PAGE: usize = 1000;
+
+/// Reads blocks out of a validator ledger directory.
+const LEDGER_TOOL: &str = "solana-ledger-tool";
+
+/// An instruction with its accounts resolved.
+#[derive(Clone, Debug)]
+pub struct Instruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Transaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub failed: bool,
+    /// Each top-level instruction followed by the instructions it invoked.
+    pub instructions: Vec<Instruction>,
+    pub logs: Vec<String>,
+}
+
+/// The contents of an account as of a slot; closed accounts have no lamports.
+#[derive(Clone, Debug)]
+pub struct AccountUpdate {
+    pub slot: u64,
+    pub pubkey: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Update {
+    Transaction(Transaction),
+    Account(AccountUpdate),
+    /// Every account the program owned as of `slot`; accounts missing from it were closed.
+    Snapshot {
+        slot: u64,
+        accounts: Vec<Pubkey>,
+    },
+}
+
+impl Update {
+    pub fn get_slot(&self) -> u64 {
+        match self {
+            Update::Transaction(transaction) => transaction.slot,
+            Update::Account(account) => account.slot,
+            Update::Snapshot { slot, .. } => *slot,
+        }
+    }
+}
+
+/// One line of a feed, as a Geyser plugin would write it.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FeedLine {
+    Account(FeedAccount),
+    Transaction(Box<EncodedConfirmedTransactionWithStatusMeta>),
+}
+
+#[derive(Deserialize)]
+struct FeedAccount {
+    slot: u64,
+    pubkey: String,
+    owner: String,
+    lamports: u64,
+    /// Base64 encoded account data.
+    data: String,
+}
+
+/// Reads `getTransaction` results from a JSON array, a single RPC response or one result
+/// per line.
+pub fn load_replay(path: &Path) -> Result<Vec<Update>> {
+    let text: String =
+        fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let values: Vec<Value> = match serde_json::from_str(&text) {
+        Ok(Value::Array(values)) => values,
+        Ok(value) => vec![value],
+        Err(_) => text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<serde_json::Result<_>>()
+            .with_context(|| format!("parsing {}", path.display()))?,
+    };
+
+    let mut updates: Vec<Update> = Vec::new();
+    for (index, value) in values.into_iter().enumerate() {
+        // Unwrap JSON-RPC responses and drop the ones for unknown signatures
+        let value: Value = match value.get("result") {
+            Some(Value::Null) => continue,
+            Some(result) => result.clone(),
+            None => value,
+        };
+        let transaction: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_value(value)
+            .with_context(|| format!("decoding transaction {index} of {}", path.display()))?;
+        updates.push(Update::Transaction(decode_transaction(&transaction)?));
+    }
+    Ok(updates)
+}
+
+/// Reads a feed of `{"account": …}` and `{"transaction": …}` lines from a file, or from
+/// stdin when `path` is `-`.
+pub fn load_feed(path: &Path) -> Result<Vec<Update>> {
+    let reader: Box<dyn BufRead> = if path == Path::new("-") {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(BufReader::new(
+            fs::File::open(path).with_context(|| format!("opening {}", path.display()))?,
+        ))
+    };
+
+    let mut updates: Vec<Update> = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line: String = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let update: Update = match serde_json::from_str(&line)
+            .with_context(|| format!("parsing line {} of {}", number + 1, path.display()))?
+        {
+            FeedLine::Account(account) => Update::Account(AccountUpdate {
+                slot: account.slot,
+                pubkey: Pubkey::from_str(&account.pubkey)?,
+                owner: Pubkey::from_str(&account.owner)?,
+                lamports: account.lamports,
+                data: STANDARD.decode(&account.data)?,
+            }),
+            FeedLine::Transaction(transaction) => {
+                Update::Transaction(decode_transaction(&transaction)?)
+            }
+        };
+        updates.push(update);
+    }
+    Ok(updates)
+}
+
+/// Reads the program's transactions from a validator ledger directory, from `from_slot`
+/// through `to_slot` or the newest slot the ledger holds. Blocks are printed one slot at
+/// a time by `solana-ledger-tool`, which must be on the `PATH`.
+pub fn load_ledger(ledger: &Path, from_slot: u64, to_slot: Option<u64>) -> Result<Vec<Update>> {
+    // "Ledger has data for <count> slots <first> to <last>"
+    let bounds: String = String::from_utf8(run_ledger_tool(ledger, &["bounds"])?)?;
+    let last_slot: u64 = bounds
+        .split_whitespace()
+        .last()
+        .and_then(|last| last.parse().ok())
+        .with_context(|| format!("reading the slot bounds of {}", ledger.display()))?;
+    let to_slot: u64 = to_slot.map_or(last_slot, |to_slot| to_slot.min(last_slot));
+
+    let mut updates: Vec<Update> = Vec::new();
+    for slot in from_slot..=to_slot {
+        let output: Vec<u8> =
+            match run_ledger_tool(ledger, &["slot", &slot.to_string(), "--output", "json"]) {
+                Ok(output) => output,
+                // Skipped slots have no block
+                Err(_) => continue,
+            };
+        let Some(block) = serde_json::Deserializer::from_slice(&output)
+            .into_iter::<Value>()
+            .next()
+            .transpose()
+            .with_context(|| format!("parsing block {slot} of {}", ledger.display()))?
+        else {
+            continue;
+        };
+
+        let block_time: Option<i64> = block.get("blockTime").and_then(Value::as_i64);
+        let transactions: &[Value] = block
+            .get("transactions")
+            .and_then(Value::as_array)
+            .map_or(&[], Vec::as_slice);
+        for (index, value) in transactions.iter().enumerate() {
+            let transaction: EncodedTransactionWithStatusMeta =
+                serde_json::from_value(value.clone())
+                    .with_context(|| format!("decoding transaction {index} of block {slot}"))?;
+            let transaction: Transaction =
+                decode_transaction(&EncodedConfirmedTransactionWithStatusMeta {
+                    slot,
+                    transaction,
+                    block_time,
+                })?;
+            if transaction
+                .instructions
+                .iter()
+                .any(|instruction| instruction.program_id == ID)
+            {
+                updates.push(Update::Transaction(transaction));
+            }
+        }
+    }
+    Ok(updates)
+}
+
+fn run_ledger_tool(ledger: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output: Output = Command::new(LEDGER_TOOL)
+        .arg("--ledger")
+        .arg(ledger)
+        .args(args)
+        .output()
+        .with_context(|| format!("running {LEDGER_TOOL}"))?;
+    if !output.status.success() {
+        bail!(
+            "{LEDGER_TOOL} {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Fetches the program's transactions newer than `until`, back to `from_slot`, oldest
+/// first.
+pub fn fetch_transactions(
+    rpc: &RpcClient,
+    from_slot: u64,
+    until: Option<Signature>,
+) -> Result<Vec<Update>> {
+    let mut signatures: Vec<Signature> = Vec::new();
+    let mut before: Option<Signature> = None;
+    'pages: loop {
+        let page = rpc.get_signatures_for_address_with_config(
+            &ID,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until,
+                limit: Some(SIGNATURES_PER_PAGE),
+                commitment: Some(rpc.commitment()),
+            },
+        )?;
+        for status in &page {
+            if status.slot < from_slot {
+                break 'pages;
+            }
+            signatures.push(Signature::from_str(&status.signature)?);
+        }
+        match signatures.last() {
+            Some(last) if page.len() == SIGNATURES_PER_PAGE => before = Some(*last),
+            _ => break,
+        }
+    }
+
+    let config: RpcTransactionConfig = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(rpc.commitment()),
+        max_supported_transaction_version: Some(0),
+    };
+    signatures
+        .iter()
+        .rev()
+        .map(|signature| {
+            let transaction: EncodedConfirmedTransactionWithStatusMeta = rpc
+                .get_transaction_with_config(signature, config)
+                .with_context(|| format!("fetching transaction {signature}"))?;
+            Ok(Update::Transaction(decode_transaction(&transaction)?))
+        })
+        .collect()
+}
+
+/// Fetches every account the program owns, as of the slot the request started at, followed
+/// by the snapshot they make up.
+pub fn fetch_accounts(rpc: &RpcClient) -> Result<Vec<Update>> {
+    let slot: u64 = rpc.get_slot()?;
+    let accounts = rpc.get_program_accounts_with_config(
+        &ID,
+        RpcProgramAccountsConfig {
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(rpc.commitment()),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+    let snapshot: Update = Update::Snapshot {
+        slot,
+        accounts: accounts.iter().map(|(pubkey, _)| *pubkey).collect(),
+    };
+    Ok(accounts
+        .into_iter()
+        .map(|(pubkey, account)| {
+            Update::Account(AccountUpdate {
+                slot,
+                pubkey,
+                owner: account.owner,
+                lamports: account.lamports,
+                data: account.data,
+            })
+        })
+        .chain([snapshot])
+        .collect())
+}
+
+fn decode_transaction(
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+) -> Result<Transaction> {
+    let slot: u64 = transaction.slot;
+    let Some(meta) = &transaction.transaction.meta else {
+        bail!("transaction in slot {slot} has no status, export it with its metadata");
+    };
+
+    let (signature, mut keys, instructions) = match &transaction.transaction.transaction {
+        EncodedTransaction::Json(ui_transaction) => {
+            let UiMessage::Raw(message) = &ui_transaction.message else {
+                bail!("jsonParsed transactions are not supported, export with json or base64");
+            };
+            let keys: Vec<Pubkey> = message
+                .account_keys
+                .iter()
+                .map(|key| Pubkey::from_str(key))
+                .collect::<Result<_, _>>()?;
+            let instructions: Vec<(u8, Vec<u8>, Vec<u8>)> = message
+                .instructions
+                .iter()
+                .map(|instruction| {
+                    Ok((
+                        instruction.program_id_index,
+                        instruction.accounts.clone(),
+                        bs58::decode(&instruction.data).into_vec()?,
+                    ))
+                })
+                .collect::<Result<_>>()?;
+            let signature: String = ui_transaction
+                .signatures
+                .first()
+                .cloned()
+                .unwrap_or_default();
+            (signature, keys, instructions)
+        }
+        encoded => {
+            let Some(versioned) = encoded.decode() else {
+                bail!("cannot decode transaction in slot {slot}");
+            };
+            let instructions: Vec<(u8, Vec<u8>, Vec<u8>)> = versioned
+                .message
+                .instructions()
+                .iter()
+                .map(|instruction| {
+                    (
+                        instruction.program_id_index,
+                        instruction.accounts.clone(),
+                        instruction.data.clone(),
+                    )
+                })
+                .collect();
+            let signature: String = versioned
+                .signatures
+                .first()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            (
+                signature,
+                versioned.message.static_account_keys().to_vec(),
+                instructions,
+            )
+        }
+    };
+
+    // Inner instructions come right after the instruction that invoked them
+    let inner_instructions: Option<&Vec<UiInnerInstructions>> =
+        meta.inner_instructions.as_ref().into();
+    let mut all_instructions: Vec<(u8, Vec<u8>, Vec<u8>)> = Vec::new();
+    for (index, instruction) in instructions.into_iter().enumerate() {
+        all_instructions.push(instruction);
+        for inner in inner_instructions
+            .into_iter()
+            .flatten()
+            .filter(|inner| inner.index as usize == index)
+        {
+            for instruction in &inner.instructions {
+                let UiInstruction::Compiled(instruction) = instruction else {
+                    bail!("jsonParsed transactions are not supported, export with json or base64");
+                };
+                all_instructions.push((
+                    instruction.program_id_index,
+                    instruction.accounts.clone(),
+                    bs58::decode(&instruction.data).into_vec()?,
+                ));
+            }
+        }
+    }
+
+    // Versioned transactions load the rest of their accounts from lookup tables
+    if let Some(UiLoadedAddresses { writable, readonly }) =
+        Option::<&UiLoadedAddresses>::from(meta.loaded_addresses.as_ref())
+    {
+        for key in writable.iter().chain(readonly) {
+            keys.push(Pubkey::from_str(key)?);
+        }
+    }
+
+    let key = |index: u8| -> Result<Pubkey> {
+        keys.get(index as usize)
+            .copied()
+            .with_context(|| format!("account index {index} out of range in {signature}"))
+    };
+    let instructions: Vec<Instruction> = all_instructions
+        .into_iter()
+        .map(|(program_id_index, accounts, data)| {
+            Ok(Instruction {
+                program_id: key(program_id_index)?,
+                accounts: accounts.into_iter().map(key).collect::<Result<_>>()?,
+                data,
+            })
+        })
+        .collect::<Result<_>>()?;
+    let logs: Option<&Vec<String>> = meta.log_messages.as_ref().into();
+
+    Ok(Transaction {
+        signature,
+        slot,
+        block_time: transaction.block_time,
+        failed: meta.err.is_some(),
+        instructions,
+        logs: logs.cloned().unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn invoked_instructions_follow_their_caller() {
+        let [payer, router, other]: [Pubkey; 3] = std::array::from_fn(|_| Pubkey::new_unique());
+        let keys: Vec<String> = [payer, router, ID, other]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let instruction = |program: u8, data: &[u8]| {
+            json!({
+                "programIdIndex": program,
+                "accounts": [0],
+                "data": bs58::encode(data).into_string(),
+            })
+        };
+        let transaction: EncodedConfirmedTransactionWithStatusMeta =
+            serde_json::from_value(json!({
+                "slot": 10,
+                "blockTime": 1_700_000_000,
+                "transaction": {
+                    "signatures": ["5ig"],
+                    "message": {
+                        "header": {
+                            "numRequiredSignatures": 1,
+                            "numReadonlySignedAccounts": 0,
+                            "numReadonlyUnsignedAccounts": 2,
+                        },
+                        "accountKeys": keys,
+                        "recentBlockhash": Pubkey::default().to_string(),
+                        "instructions": [instruction(1, &[1]), instruction(3, &[3])],
+                    },
+                },
+                "meta": {
+                    "err": null,
+                    "status": { "Ok": null },
+                    "fee": 5000,
+                    "preBalances": [],
+                    "postBalances": [],
+                    "innerInstructions": [{ "index": 0, "instructions": [instruction(2, &[2])] }],
+                    "logMessages": [],
+                },
+            }))
+            .unwrap();
+
+        let transaction: Transaction = decode_transaction(&transaction).unwrap();
+        let called: Vec<(Pubkey, Vec<u8>)> = transaction
+            .instructions
+            .into_iter()
+            .map(|instruction| (instruction.program_id, instruction.data))
+            .collect();
+        assert_eq!(called, [(router, vec![1]), (ID, vec![2]), (other, vec![3])]);
+    }
+}
@@ -0,0 +1,570 @@
+use std::{fs, path::Path, str::FromStr};
+
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use octo_program::{
+    instruction as ix, SharesPurchased, SharesRefunded, SharesTraded, SharesTransferred,
+    TenderSettled, ID,
+};
+use serde_json::Value;
+use solana_sdk::{bs58, pubkey::Pubkey};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, UiInnerInstructions,
+    UiInstruction, UiLoadedAddresses, UiMessage,
+};
+
+/// Position of the holder and the pool in the `ClaimRewards` and `ClaimClassRewards` accounts.
+const CLAIM_HOLDER: usize = 0;
+const CLAIM_POOL: usize = 2;
+
+/// Something that changed what an investor holds, paid or received in a pool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Activity {
+    Purchased {
+        pool: Pubkey,
+        buyer: Pubkey,
+        shares: u64,
+        cost: u64,
+    },
+    Traded {
+        pool: Pubkey,
+        seller: Pubkey,
+        buyer: Pubkey,
+        shares: u64,
+        price: u64,
+    },
+    Transferred {
+        pool: Pubkey,
+        from: Pubkey,
+        to: Pubkey,
+        shares: u64,
+    },
+    Redeemed {
+        pool: Pubkey,
+        holder: Pubkey,
+        shares: u64,
+        payout: u64,
+    },
+    RewardsClaimed {
+        pool: Pubkey,
+        holder: Pubkey,
+        amount: u64,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub slot: u64,
+    pub block_time: i64,
+    pub signature: String,
+    pub activity: Activity,
+}
+
+/// Reads `getTransaction` results from a JSON array, a single RPC response or one result
+/// per line, and returns the program activity they contain ordered by slot.
+pub fn load(paths: &[impl AsRef<Path>]) -> Result<Vec<Record>> {
+    let mut records: Vec<Record> = Vec::new();
+    for path in paths {
+        let path: &Path = path.as_ref();
+        let text: String =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        for (index, value) in parse_values(&text)
+            .with_context(|| format!("parsing {}", path.display()))?
+            .into_iter()
+            .enumerate()
+        {
+            let transaction: EncodedConfirmedTransactionWithStatusMeta =
+                serde_json::from_value(value).with_context(|| {
+                    format!("decoding transaction {index} of {}", path.display())
+                })?;
+            records.extend(decode(&transaction)?);
+        }
+    }
+
+    // Transactions of one slot keep the order they were exported in
+    records.sort_by_key(|record| record.slot);
+    Ok(records)
+}
+
+fn parse_values(text: &str) -> Result<Vec<Value>> {
+    let values: Vec<Value> = match serde_json::from_str(text) {
+        Ok(Value::Array(values)) => values,
+        Ok(value) => vec![value],
+        Err(_) => text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<serde_json::Result<_>>()?,
+    };
+
+    // Unwrap JSON-RPC responses and drop the ones for unknown signatures
+    Ok(values
+        .into_iter()
+        .filter_map(|value| match value.get("result") {
+            Some(result) => (!result.is_null()).then(|| result.clone()),
+            None => Some(value),
+        })
+        .collect())
+}
+
+fn decode(transaction: &EncodedConfirmedTransactionWithStatusMeta) -> Result<Vec<Record>> {
+    let slot: u64 = transaction.slot;
+    let Some(meta) = &transaction.transaction.meta else {
+        return Ok(Vec::new());
+    };
+    if meta.err.is_some() {
+        return Ok(Vec::new());
+    }
+    let Some(block_time) = transaction.block_time else {
+        bail!("transaction in slot {slot} has no block time");
+    };
+
+    let (signature, keys, instructions) = match &transaction.transaction.transaction {
+        EncodedTransaction::Json(ui_transaction) => {
+            let UiMessage::Raw(message) = &ui_transaction.message else {
+                bail!("jsonParsed transactions are not supported, export with json or base64");
+            };
+            let keys: Vec<Pubkey> = message
+                .account_keys
+                .iter()
+                .map(|key| Pubkey::from_str(key))
+                .collect::<Result<_, _>>()?;
+            let instructions: Vec<(u8, Vec<u8>, Vec<u8>)> = message
+                .instructions
+                .iter()
+                .map(|instruction| {
+                    let data: Vec<u8> = bs58::decode(&instruction.data).into_vec()?;
+                    Ok((
+                        instruction.program_id_index,
+                        instruction.accounts.clone(),
+                        data,
+                    ))
+                })
+                .collect::<Result<_>>()?;
+            let signature: String = ui_transaction
+                .signatures
+                .first()
+                .cloned()
+                .unwrap_or_default();
+            (signature, keys, instructions)
+        }
+        encoded => {
+            let Some(versioned) = encoded.decode() else {
+                bail!("cannot decode transaction in slot {slot}");
+            };
+            let instructions: Vec<(u8, Vec<u8>, Vec<u8>)> = versioned
+                .message
+                .instructions()
+                .iter()
+                .map(|instruction| {
+                    (
+                        instruction.program_id_index,
+                        instruction.accounts.clone(),
+                        instruction.data.clone(),
+                    )
+                })
+                .collect();
+            let signature: String = versioned
+                .signatures
+                .first()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            (
+                signature,
+                versioned.message.static_account_keys().to_vec(),
+                instructions,
+            )
+        }
+    };
+
+    // Follow each instruction with the ones it invoked, so claims made through CPI count
+    let inner_instructions: Option<&Vec<UiInnerInstructions>> =
+        meta.inner_instructions.as_ref().into();
+    let mut all_instructions: Vec<(u8, Vec<u8>, Vec<u8>)> = Vec::new();
+    for (index, instruction) in instructions.into_iter().enumerate() {
+        all_instructions.push(instruction);
+        for inner in inner_instructions
+            .into_iter()
+            .flatten()
+            .filter(|inner| inner.index as usize == index)
+        {
+            for instruction in &inner.instructions {
+                let UiInstruction::Compiled(instruction) = instruction else {
+                    bail!("jsonParsed transactions are not supported, export with json or base64");
+                };
+                all_instructions.push((
+                    instruction.program_id_index,
+                    instruction.accounts.clone(),
+                    bs58::decode(&instruction.data).into_vec()?,
+                ));
+            }
+        }
+    }
+
+    // Versioned transactions load the rest of their accounts from lookup tables
+    let mut keys: Vec<Pubkey> = keys;
+    if let Some(UiLoadedAddresses { writable, readonly }) =
+        Option::<&UiLoadedAddresses>::from(meta.loaded_addresses.as_ref())
+    {
+        for key in writable.iter().chain(readonly) {
+            keys.push(Pubkey::from_str(key)?);
+        }
+    }
+
+    let record = |activity: Activity| Record {
+        slot,
+        block_time,
+        signature: signature.clone(),
+        activity,
+    };
+    let mut records: Vec<Record> = Vec::new();
+
+    // Claimed rewards come straight from the instruction arguments
+    for (program_id_index, accounts, data) in &all_instructions {
+        if keys.get(*program_id_index as usize) != Some(&ID) || data.len() < 8 {
+            continue;
+        }
+        let account = |position: usize| -> Result<Pubkey> {
+            accounts
+                .get(position)
+                .and_then(|index| keys.get(*index as usize))
+                .copied()
+                .with_context(|| format!("missing account {position} in slot {slot}"))
+        };
+        let (discriminator, args) = data.split_at(8);
+        let amount: Option<u64> = if discriminator == ix::ClaimRewards::DISCRIMINATOR {
+            Some(ix::ClaimRewards::try_from_slice(args)?.rewards)
+        } else if discriminator == ix::ClaimClassRewards::DISCRIMINATOR {
+            Some(ix::ClaimClassRewards::try_from_slice(args)?.rewards)
+        } else {
+            None
+        };
+        if let Some(amount) = amount {
+            records.push(record(Activity::RewardsClaimed {
+                pool: account(CLAIM_POOL)?,
+                holder: account(CLAIM_HOLDER)?,
+                amount,
+            }));
+        }
+    }
+
+    // Everything priced by the program is read from its events
+    let logs: Option<&Vec<String>> = meta.log_messages.as_ref().into();
+    for data in get_program_events(logs.map_or(&[], Vec::as_slice)) {
+        if let Some(activity) = decode_event(&data)? {
+            records.push(record(activity));
+        }
+    }
+
+    Ok(records)
+}
+
+/// The events the program emitted itself. `Program data:` logs belong to the innermost
+/// program running, so the invoke and return logs are followed to skip the events of
+/// other programs, including the ones the program invokes.
+fn get_program_events(logs: &[String]) -> Vec<Vec<u8>> {
+    let program_id: String = ID.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut events: Vec<Vec<u8>> = Vec::new();
+    for log in logs {
+        let Some(message) = log.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(data) = message.strip_prefix("data: ") {
+            if stack.last() == Some(&program_id.as_str()) {
+                if let Ok(data) = STANDARD.decode(data) {
+                    events.push(data);
+                }
+            }
+        } else if let Some((program, status)) = message.split_once(' ') {
+            if status.starts_with("invoke [") {
+                stack.push(program);
+            } else if status == "success" || status.starts_with("failed") {
+                stack.pop();
+            }
+        }
+    }
+    events
+}
+
+fn decode_event(data: &[u8]) -> Result<Option<Activity>> {
+    if data.len() < 8 {
+        return Ok(None);
+    }
+    let (discriminator, mut event) = data.split_at(8);
+
+    let activity: Activity = if discriminator == SharesPurchased::DISCRIMINATOR {
+        let event: SharesPurchased = AnchorDeserialize::deserialize(&mut event)?;
+        Activity::Purchased {
+            pool: event.pool,
+            buyer: event.buyer,
+            shares: event.shares,
+            cost: event.cost,
+        }
+    } else if discriminator == SharesTraded::DISCRIMINATOR {
+        let event: SharesTraded = AnchorDeserialize::deserialize(&mut event)?;
+        Activity::Traded {
+            pool: event.pool,
+            seller: event.seller,
+            buyer: event.buyer,
+            shares: event.shares,
+            price: event
+                .shares
+                .checked_mul(event.price_per_share)
+                .context("trade price overflows")?,
+        }
+    } else if discriminator == SharesTransferred::DISCRIMINATOR {
+        let event: SharesTransferred = AnchorDeserialize::deserialize(&mut event)?;
+        Activity::Transferred {
+            pool: event.pool,
+            from: event.from,
+            to: event.to,
+            shares: event.shares,
+        }
+    } else if discriminator == TenderSettled::DISCRIMINATOR {
+        let event: TenderSettled = AnchorDeserialize::deserialize(&mut event)?;
+        Activity::Redeemed {
+            pool: event.pool,
+            holder: event.holder,
+            shares: event.shares,
+            payout: event.payout,
+        }
+    } else if discriminator == SharesRefunded::DISCRIMINATOR {
+        // A refund of a failed raise redeems the shares at whatever the pool paid back
+        let event: SharesRefunded = AnchorDeserialize::deserialize(&mut event)?;
+        Activity::Redeemed {
+            pool: event.pool,
+            holder: event.holder,
+            shares: event.shares,
+            payout: event.refund,
+        }
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(activity))
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{Event, InstructionData};
+    use serde_json::json;
+
+    use super::*;
+
+    const USDC: u64 = 1_000_000;
+
+    fn invoke(program: &Pubkey, depth: u8) -> String {
+        format!("Program {program} invoke [{depth}]")
+    }
+
+    fn success(program: &Pubkey) -> String {
+        format!("Program {program} success")
+    }
+
+    fn data(event: &impl Event) -> String {
+        format!("Program data: {}", STANDARD.encode(event.data()))
+    }
+
+    /// A `getTransaction` result in `json` encoding calling `program` with `accounts`.
+    fn transaction(
+        keys: &[Pubkey],
+        program: u8,
+        accounts: &[u8],
+        inner_instructions: Value,
+        logs: &[String],
+    ) -> EncodedConfirmedTransactionWithStatusMeta {
+        serde_json::from_value(json!({
+            "slot": 10,
+            "blockTime": 1_700_000_000,
+            "transaction": {
+                "signatures": ["5ig"],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 1,
+                    },
+                    "accountKeys": keys.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                    "recentBlockhash": Pubkey::default().to_string(),
+                    "instructions": [{
+                        "programIdIndex": program,
+                        "accounts": accounts,
+                        "data": bs58::encode([0; 8]).into_string(),
+                    }],
+                },
+            },
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": inner_instructions,
+                "logMessages": logs,
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn events_of_other_programs_are_skipped() {
+        let holder: Pubkey = Pubkey::new_unique();
+        let pool: Pubkey = Pubkey::new_unique();
+        let other: Pubkey = Pubkey::new_unique();
+        let purchase = |shares: u64| SharesPurchased {
+            pool,
+            buyer: holder,
+            shares,
+            cost: shares * USDC,
+        };
+
+        // Only the event logged while the program itself runs is its own
+        let logs: Vec<String> = vec![
+            invoke(&ID, 1),
+            invoke(&other, 2),
+            data(&purchase(1)),
+            success(&other),
+            data(&purchase(10)),
+            success(&ID),
+            invoke(&other, 1),
+            data(&purchase(100)),
+            success(&other),
+        ];
+        let records: Vec<Record> = decode(&transaction(
+            &[holder, ID, other],
+            1,
+            &[0],
+            json!([]),
+            &logs,
+        ))
+        .unwrap();
+
+        let activity: Vec<Activity> = records.into_iter().map(|record| record.activity).collect();
+        assert_eq!(
+            activity,
+            [Activity::Purchased {
+                pool,
+                buyer: holder,
+                shares: 10,
+                cost: 10 * USDC,
+            }]
+        );
+    }
+
+    #[test]
+    fn claims_made_through_cpi_are_decoded() {
+        let holder: Pubkey = Pubkey::new_unique();
+        let pool: Pubkey = Pubkey::new_unique();
+        let distribution: Pubkey = Pubkey::new_unique();
+        let router: Pubkey = Pubkey::new_unique();
+        let keys: [Pubkey; 5] = [holder, router, ID, pool, distribution];
+
+        let claim: Vec<u8> = ix::ClaimRewards { rewards: 3 * USDC }.data();
+        let inner_instructions: Value = json!([{
+            "index": 0,
+            "instructions": [{
+                "programIdIndex": 2,
+                "accounts": [0, 4, 3, 4],
+                "data": bs58::encode(claim).into_string(),
+                "stackHeight": 2,
+            }],
+        }]);
+        let logs: Vec<String> = vec![
+            invoke(&router, 1),
+            invoke(&ID, 2),
+            success(&ID),
+            success(&router),
+        ];
+        let records: Vec<Record> = decode(&transaction(
+            &keys,
+            1,
+            &[0, 2, 3, 4],
+            inner_instructions,
+            &logs,
+        ))
+        .unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].signature, "5ig");
+        assert_eq!(
+            records[0].activity,
+            Activity::RewardsClaimed {
+                pool,
+                holder,
+                amount: 3 * USDC,
+            }
+        );
+    }
+
+    #[test]
+    fn every_event_decodes_to_its_activity() {
+        let pool: Pubkey = Pubkey::new_unique();
+        let first: Pubkey = Pubkey::new_unique();
+        let second: Pubkey = Pubkey::new_unique();
+
+        let traded = SharesTraded {
+            pool,
+            seller: first,
+            buyer: second,
+            shares: 4,
+            price_per_share: 2 * USDC,
+        };
+        assert_eq!(
+            decode_event(&traded.data()).unwrap(),
+            Some(Activity::Traded {
+                pool,
+                seller: first,
+                buyer: second,
+                shares: 4,
+                price: 8 * USDC,
+            })
+        );
+        let transferred = SharesTransferred {
+            pool,
+            from: first,
+            to: second,
+            shares: 2,
+        };
+        assert_eq!(
+            decode_event(&transferred.data()).unwrap(),
+            Some(Activity::Transferred {
+                pool,
+                from: first,
+                to: second,
+                shares: 2,
+            })
+        );
+        let settled = TenderSettled {
+            pool,
+            holder: first,
+            shares: 5,
+            payout: 6 * USDC,
+        };
+        assert_eq!(
+            decode_event(&settled.data()).unwrap(),
+            Some(Activity::Redeemed {
+                pool,
+                holder: first,
+                shares: 5,
+                payout: 6 * USDC,
+            })
+        );
+        let refunded = SharesRefunded {
+            pool,
+            holder: second,
+            shares: 3,
+            refund: 2 * USDC,
+        };
+        assert_eq!(
+            decode_event(&refunded.data()).unwrap(),
+            Some(Activity::Redeemed {
+                pool,
+                holder: second,
+                shares: 3,
+                payout: 2 * USDC,
+            })
+        );
+        assert_eq!(decode_event(&[0; 16]).unwrap(), None);
+    }
+}
@@ -0,0 +1,131 @@
+//! Builds investor tax statements offline from exported octo-program transactions.
+//!
+//! Export the program's transactions with `getTransaction` (`json` or `base64` encoding)
+//! and pass the files in. Claimed rewards are read from the claim instructions, including
+//! those other programs make through CPI; purchases, trades, transfers and redemptions
+//! from the program's events, which are replayed through the same cost basis accounting
+//! as the on-chain `InvestorPosition`.
+
+mod history;
+mod statement;
+
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    history::Record,
+    statement::{Ledger, PoolStatement, CSV_HEADER},
+};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "blockride-statements",
+    about = "Build investor statements from octo-program transaction history"
+)]
+struct Cli {
+    /// `getTransaction` results as a JSON array, one per line, or JSON-RPC responses.
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Calendar year of the statements, in UTC.
+    #[arg(long)]
+    year: i32,
+
+    /// Only build the statement of this investor.
+    #[arg(long)]
+    investor: Option<Pubkey>,
+
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
+
+    /// Write one file per investor into this directory instead of printing.
+    #[arg(long)]
+    out_dir: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+enum Format {
+    #[default]
+    Csv,
+    Json,
+}
+
+fn main() -> Result<()> {
+    let cli: Cli = Cli::parse();
+
+    let records: Vec<Record> = history::load(&cli.inputs)?;
+    let ledger: Ledger = Ledger::replay(
+        &records,
+        statement::year_start(cli.year),
+        statement::year_start(cli.year + 1),
+    )?;
+
+    let statements: Vec<PoolStatement> = ledger.statements();
+    let mut investors: BTreeMap<Pubkey, Vec<&PoolStatement>> = BTreeMap::new();
+    for statement in &statements {
+        if cli
+            .investor
+            .is_none_or(|investor| investor == statement.investor)
+        {
+            investors
+                .entry(statement.investor)
+                .or_default()
+                .push(statement);
+        }
+    }
+
+    match &cli.out_dir {
+        Some(out_dir) => {
+            fs::create_dir_all(out_dir)
+                .with_context(|| format!("creating {}", out_dir.display()))?;
+            for (investor, statements) in &investors {
+                let (extension, contents): (&str, String) = match cli.format {
+                    Format::Csv => ("csv", csv(statements)),
+                    Format::Json => (
+                        "json",
+                        format!(
+                            "{:#}\n",
+                            statement::investor_json(investor, cli.year, statements)
+                        ),
+                    ),
+                };
+                let path: PathBuf = out_dir.join(format!("{investor}.{extension}"));
+                fs::write(&path, contents)
+                    .with_context(|| format!("writing {}", path.display()))?;
+            }
+            eprintln!(
+                "Wrote {} statements to {}",
+                investors.len(),
+                out_dir.display()
+            );
+        }
+        None => match cli.format {
+            Format::Csv => print!(
+                "{}",
+                csv(&investors.into_values().flatten().collect::<Vec<_>>())
+            ),
+            Format::Json => {
+                let statements: Vec<serde_json::Value> = investors
+                    .iter()
+                    .map(|(investor, statements)| {
+                        statement::investor_json(investor, cli.year, statements)
+                    })
+                    .collect();
+                println!("{:#}", serde_json::Value::Array(statements));
+            }
+        },
+    }
+    Ok(())
+}
+
+fn csv(statements: &[&PoolStatement]) -> String {
+    let mut csv: String = format!("{CSV_HEADER}\n");
+    for statement in statements {
+        csv.push_str(&statement.to_csv());
+        csv.push('\n');
+    }
+    csv
+}
@@ -0,0 +1,374 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+use octo_program::InvestorPosition;
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::history::{Activity, Record};
+
+pub const CSV_HEADER: &str =
+    "investor,pool,shares,cost_basis,income,principal_returned,realized_cost_basis,realized_gain";
+
+/// One investor's year in one pool. Amounts are in USDC base units.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolStatement {
+    pub investor: Pubkey,
+    pub pool: Pubkey,
+    /// Shares and their cost basis at the end of the period.
+    pub shares: u64,
+    pub cost_basis: u64,
+    /// Rewards claimed during the period.
+    pub income: u64,
+    /// Sale and redemption proceeds received during the period.
+    pub principal_returned: u64,
+    /// Cost basis of the shares sold or redeemed during the period.
+    pub realized_cost_basis: u64,
+}
+
+impl PoolStatement {
+    pub fn get_realized_gain(&self) -> i128 {
+        self.principal_returned as i128 - self.realized_cost_basis as i128
+    }
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{}",
+            self.investor,
+            self.pool,
+            self.shares,
+            format_usdc(self.cost_basis as i128),
+            format_usdc(self.income as i128),
+            format_usdc(self.principal_returned as i128),
+            format_usdc(self.realized_cost_basis as i128),
+            format_usdc(self.get_realized_gain()),
+        )
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "pool": self.pool.to_string(),
+            "shares": self.shares,
+            "cost_basis": format_usdc(self.cost_basis as i128),
+            "income": format_usdc(self.income as i128),
+            "principal_returned": format_usdc(self.principal_returned as i128),
+            "realized_cost_basis": format_usdc(self.realized_cost_basis as i128),
+            "realized_gain": format_usdc(self.get_realized_gain()),
+        })
+    }
+}
+
+/// Per-investor JSON statement with totals across pools.
+pub fn investor_json(investor: &Pubkey, year: i32, statements: &[&PoolStatement]) -> Value {
+    let total = |amount: fn(&PoolStatement) -> i128| -> String {
+        format_usdc(statements.iter().map(|statement| amount(statement)).sum())
+    };
+    json!({
+        "investor": investor.to_string(),
+        "year": year,
+        "pools": statements.iter().map(|statement| statement.to_json()).collect::<Vec<Value>>(),
+        "totals": {
+            "income": total(|statement| statement.income as i128),
+            "principal_returned": total(|statement| statement.principal_returned as i128),
+            "realized_gain": total(PoolStatement::get_realized_gain),
+        },
+    })
+}
+
+struct Holding {
+    position: InvestorPosition,
+    /// The position as the period opened, taken before its first change in the period.
+    opening: Option<InvestorPosition>,
+}
+
+/// Replays program activity through the same accounting as `InvestorPosition`.
+#[derive(Default)]
+pub struct Ledger {
+    holdings: BTreeMap<(Pubkey, Pubkey), Holding>,
+}
+
+impl Ledger {
+    /// Applies every record before `end`, splitting off what happened from `start` on.
+    pub fn replay(records: &[Record], start: i64, end: i64) -> Result<Self> {
+        let mut ledger: Ledger = Ledger::default();
+        for record in records.iter().filter(|record| record.block_time < end) {
+            ledger
+                .apply(record, record.block_time >= start)
+                .map_err(|err| anyhow!("{err} in transaction {}", record.signature))?;
+        }
+        Ok(ledger)
+    }
+
+    fn holding(&mut self, investor: Pubkey, pool: Pubkey, in_period: bool) -> &mut Holding {
+        let holding: &mut Holding =
+            self.holdings
+                .entry((investor, pool))
+                .or_insert_with(|| Holding {
+                    position: empty_position(pool, investor),
+                    opening: None,
+                });
+        if in_period && holding.opening.is_none() {
+            holding.opening = Some(holding.position.clone());
+        }
+        holding
+    }
+
+    fn apply(&mut self, record: &Record, in_period: bool) -> anchor_lang::Result<()> {
+        let now: u64 = record.block_time as u64;
+        match record.activity {
+            Activity::Purchased {
+                pool,
+                buyer,
+                shares,
+                cost,
+            } => {
+                let buyer: &mut InvestorPosition =
+                    &mut self.holding(buyer, pool, in_period).position;
+                buyer.add_shares(shares, cost, now)?;
+            }
+            Activity::Traded {
+                pool,
+                seller,
+                buyer,
+                shares,
+                price,
+            } => {
+                let seller: &mut InvestorPosition =
+                    &mut self.holding(seller, pool, in_period).position;
+                let cost_basis: u64 = seller.sub_shares(shares)?;
+                seller.add_realized(cost_basis, price)?;
+                let buyer: &mut InvestorPosition =
+                    &mut self.holding(buyer, pool, in_period).position;
+                buyer.add_shares(shares, price, now)?;
+            }
+            Activity::Transferred {
+                pool,
+                from,
+                to,
+                shares,
+            } => {
+                let from: &mut InvestorPosition = &mut self.holding(from, pool, in_period).position;
+                let cost_basis: u64 = from.sub_shares(shares)?;
+                let to: &mut InvestorPosition = &mut self.holding(to, pool, in_period).position;
+                to.add_shares(shares, cost_basis, now)?;
+            }
+            Activity::Redeemed {
+                pool,
+                holder,
+                shares,
+                payout,
+            } => {
+                let holder: &mut InvestorPosition =
+                    &mut self.holding(holder, pool, in_period).position;
+                let cost_basis: u64 = holder.sub_shares(shares)?;
+                holder.add_realized(cost_basis, payout)?;
+            }
+            Activity::RewardsClaimed {
+                pool,
+                holder,
+                amount,
+            } => {
+                let holder: &mut InvestorPosition =
+                    &mut self.holding(holder, pool, in_period).position;
+                holder.add_rewards_claimed(amount)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Statements of every position that was held at the end of the period or changed in it.
+    pub fn statements(&self) -> Vec<PoolStatement> {
+        self.holdings
+            .iter()
+            .map(|(&(investor, pool), holding)| {
+                let closing: &InvestorPosition = &holding.position;
+                let opening: &InvestorPosition = holding.opening.as_ref().unwrap_or(closing);
+                PoolStatement {
+                    investor,
+                    pool,
+                    shares: closing.shares,
+                    cost_basis: closing.cost_basis,
+                    income: closing.rewards_claimed - opening.rewards_claimed,
+                    principal_returned: closing.principal_returned - opening.principal_returned,
+                    realized_cost_basis: closing.realized_cost_basis - opening.realized_cost_basis,
+                }
+            })
+            .filter(|statement| {
+                statement.shares > 0
+                    || statement.income > 0
+                    || statement.principal_returned > 0
+                    || statement.realized_cost_basis > 0
+            })
+            .collect()
+    }
+}
+
+fn empty_position(pool: Pubkey, holder: Pubkey) -> InvestorPosition {
+    InvestorPosition {
+        version: InvestorPosition::VERSION,
+        pool,
+        holder,
+        shares: 0,
+        cost_basis: 0,
+        realized_cost_basis: 0,
+        principal_returned: 0,
+        rewards_claimed: 0,
+        opened_at: 0,
+        last_acquired_at: 0,
+        bump: 0,
+        reward_per_share_paid: 0,
+        rewards_owed: 0,
+        reserved: [0; 8],
+    }
+}
+
+/// Formats USDC base units with their six decimals.
+pub fn format_usdc(amount: i128) -> String {
+    let sign: &str = if amount < 0 { "-" } else { "" };
+    let amount: u128 = amount.unsigned_abs();
+    format!("{sign}{}.{:06}", amount / 1_000_000, amount % 1_000_000)
+}
+
+/// Unix timestamp of January 1st of `year`, UTC.
+pub fn year_start(year: i32) -> i64 {
+    // Days from the civil calendar, counting years from March so leap days come last
+    let year: i64 = year as i64 - 1;
+    let era: i64 = year.div_euclid(400);
+    let year_of_era: i64 = year - era * 400;
+    let day_of_era: i64 = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + 306;
+    (era * 146_097 + day_of_era - 719_468) * 24 * 60 * 60
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC: u64 = 1_000_000;
+
+    fn record(block_time: i64, activity: Activity) -> Record {
+        Record {
+            slot: block_time as u64,
+            block_time,
+            signature: String::new(),
+            activity,
+        }
+    }
+
+    #[test]
+    fn replay_splits_the_period_off_earlier_activity() {
+        let seller: Pubkey = Pubkey::new_unique();
+        let buyer: Pubkey = Pubkey::new_unique();
+        let pool: Pubkey = Pubkey::new_unique();
+        let (start, end): (i64, i64) = (year_start(2024), year_start(2025));
+
+        let records: Vec<Record> = vec![
+            record(
+                start - 1,
+                Activity::Purchased {
+                    pool,
+                    buyer: seller,
+                    shares: 10,
+                    cost: 10 * USDC,
+                },
+            ),
+            record(
+                start - 1,
+                Activity::RewardsClaimed {
+                    pool,
+                    holder: seller,
+                    amount: USDC,
+                },
+            ),
+            record(
+                start,
+                Activity::Traded {
+                    pool,
+                    seller,
+                    buyer,
+                    shares: 4,
+                    price: 6 * USDC,
+                },
+            ),
+            record(
+                end - 1,
+                Activity::RewardsClaimed {
+                    pool,
+                    holder: seller,
+                    amount: 2 * USDC,
+                },
+            ),
+            record(
+                end,
+                Activity::Redeemed {
+                    pool,
+                    holder: buyer,
+                    shares: 4,
+                    payout: 5 * USDC,
+                },
+            ),
+        ];
+        let statements: Vec<PoolStatement> =
+            Ledger::replay(&records, start, end).unwrap().statements();
+
+        let statement = |investor: Pubkey| -> &PoolStatement {
+            statements
+                .iter()
+                .find(|statement| statement.investor == investor)
+                .unwrap()
+        };
+        assert_eq!(
+            *statement(seller),
+            PoolStatement {
+                investor: seller,
+                pool,
+                shares: 6,
+                cost_basis: 6 * USDC,
+                income: 2 * USDC,
+                principal_returned: 6 * USDC,
+                realized_cost_basis: 4 * USDC,
+            }
+        );
+        assert_eq!(statement(seller).get_realized_gain(), 2 * USDC as i128);
+        // The redemption after the period is left out
+        assert_eq!(
+            *statement(buyer),
+            PoolStatement {
+                investor: buyer,
+                pool,
+                shares: 4,
+                cost_basis: 6 * USDC,
+                income: 0,
+                principal_returned: 0,
+                realized_cost_basis: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn shares_from_before_the_history_have_no_cost_basis() {
+        let holder: Pubkey = Pubkey::new_unique();
+        let pool: Pubkey = Pubkey::new_unique();
+        let records: Vec<Record> = vec![record(
+            0,
+            Activity::Redeemed {
+                pool,
+                holder,
+                shares: 1,
+                payout: USDC,
+            },
+        )];
+        let statements: Vec<PoolStatement> = Ledger::replay(&records, 0, 1).unwrap().statements();
+
+        assert_eq!(statements.len(), 1);
+        assert_eq!(statements[0].principal_returned, USDC);
+        assert_eq!(statements[0].realized_cost_basis, 0);
+    }
+
+    #[test]
+    fn amounts_keep_six_decimals() {
+        assert_eq!(format_usdc(1_500_000), "1.500000");
+        assert_eq!(format_usdc(-2), "-0.000002");
+        assert_eq!(year_start(1970), 0);
+        assert_eq!(year_start(2024), 1_704_067_200);
+    }
+}
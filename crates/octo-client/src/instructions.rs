@@ -0,0 +1,1217 @@
+use anchor_lang::{
+    prelude::Pubkey,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        system_program, sysvar,
+    },
+    InstructionData, ToAccountMetas,
+};
+use anchor_spl::{associated_token, token};
+use mpl_token_metadata::ID as MPL_TOKEN_METADATA_ID;
+use octo_program::{
+    accounts, instruction as ix, BuyerEligibility, PoolStatus, PoolTemplateTerms, ReceiptedExpense,
+    RedemptionSource, RevenueBucket, VehicleAssetDetails, VehicleStatus, CUSTOM_USDC_MINT, ID,
+};
+
+use crate::pda::*;
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn initialize_program(creator: Pubkey, grand_authority: Pubkey) -> Instruction {
+    build(
+        accounts::InitializeProgram {
+            creator,
+            project_pda: find_project_address().0,
+            system_program: system_program::ID,
+        },
+        ix::InitializeProgram { grand_authority },
+    )
+}
+
+pub fn update_program_grand_authority(
+    grand_authority: Pubkey,
+    new_grand_authority: Pubkey,
+) -> Instruction {
+    build(
+        accounts::UpdateProgramGrandAuthority {
+            grand_authority,
+            project_pda: find_project_address().0,
+            system_program: system_program::ID,
+        },
+        ix::UpdateProgramGrandAuthority {
+            new_grand_authority,
+        },
+    )
+}
+
+pub fn set_protocol_fee(grand_authority: Pubkey, protocol_fee_bps: u16) -> Instruction {
+    build(
+        accounts::SetProtocolFee {
+            grand_authority,
+            project_pda: find_project_address().0,
+        },
+        ix::SetProtocolFee { protocol_fee_bps },
+    )
+}
+
+pub fn add_pool_creator(grand_authority: Pubkey, creator: Pubkey, can_create: bool) -> Instruction {
+    build(
+        accounts::AddCreator {
+            grand_authority,
+            project_pda: find_project_address().0,
+            pool_creator_data: find_pool_creator_address(&creator).0,
+            system_program: system_program::ID,
+        },
+        ix::AddPoolCreator {
+            creator,
+            can_create,
+        },
+    )
+}
+
+pub fn update_pool_creator(
+    grand_authority: Pubkey,
+    creator: Pubkey,
+    can_create: bool,
+) -> Instruction {
+    build(
+        accounts::UpdatePoolCreator {
+            pool_creator_data: find_pool_creator_address(&creator).0,
+            creator: grand_authority,
+            project_pda: find_project_address().0,
+            system_program: system_program::ID,
+        },
+        ix::UpdatePoolCreator { can_create },
+    )
+}
+
+pub fn create_collection(
+    grand_authority: Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Instruction {
+    let project_pda: Pubkey = find_project_address().0;
+    let collection_mint: Pubkey = find_collection_mint_address().0;
+
+    build(
+        accounts::CreateCollection {
+            grand_authority,
+            project_pda,
+            collection_mint,
+            collection_token_account: associated_token::get_associated_token_address(
+                &project_pda,
+                &collection_mint,
+            ),
+            collection_metadata: find_metadata_address(&collection_mint).0,
+            collection_master_edition: find_master_edition_address(&collection_mint).0,
+            metadata_program: MPL_TOKEN_METADATA_ID,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::CreateCollection { name, symbol, uri },
+    )
+}
+
+/// `id` and `creator_index` are the registry slots the pool takes: the current
+/// `ProjectData::pools` and the creator's `CreatorPools::pools` (0 if it does not exist yet).
+pub fn create_pool(
+    creator: Pubkey,
+    args: ix::CreatePool,
+    id: u64,
+    creator_index: u64,
+) -> Instruction {
+    build(
+        create_pool_accounts(creator, &args.reference, id, creator_index),
+        args,
+    )
+}
+
+fn create_pool_accounts(
+    creator: Pubkey,
+    reference: &Pubkey,
+    id: u64,
+    creator_index: u64,
+) -> accounts::CreatePool {
+    let pool: Pubkey = find_pool_address(reference).0;
+    let mint: Pubkey = find_mint_address(&pool).0;
+    let collection_mint: Pubkey = find_collection_mint_address().0;
+
+    accounts::CreatePool {
+        creator,
+        pool,
+        mint,
+        creator_mint_account: get_shares_address(&pool, &creator),
+        usdc_mint: CUSTOM_USDC_MINT,
+        creator_usdc_account: get_usdc_address(&creator),
+        pool_usdc_account: get_usdc_address(&pool),
+        metadata: find_metadata_address(&mint).0,
+        project_pda: find_project_address().0,
+        creator_pools: find_creator_pools_address(&creator).0,
+        pool_entry: find_pool_entry_address(id).0,
+        creator_pool_entry: find_creator_pool_entry_address(&creator, creator_index).0,
+        creator_position: find_position_address(&pool, &creator).0,
+        collection_mint,
+        collection_metadata: find_metadata_address(&collection_mint).0,
+        collection_master_edition: find_master_edition_address(&collection_mint).0,
+        metadata_program: MPL_TOKEN_METADATA_ID,
+        sysvar_instructions: sysvar::instructions::ID,
+        rent: sysvar::rent::ID,
+        system_program: system_program::ID,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+    }
+}
+
+/// Registers a pool created before the registry; `id` and `creator_index` are as in
+/// [`create_pool`], for the pool's creator.
+pub fn register_pool(
+    payer: Pubkey,
+    pool: Pubkey,
+    creator: Pubkey,
+    id: u64,
+    creator_index: u64,
+) -> Instruction {
+    build(
+        accounts::RegisterPool {
+            payer,
+            pool,
+            project_pda: find_project_address().0,
+            creator_pools: find_creator_pools_address(&creator).0,
+            pool_entry: find_pool_entry_address(id).0,
+            creator_pool_entry: find_creator_pool_entry_address(&creator, creator_index).0,
+            system_program: system_program::ID,
+        },
+        ix::RegisterPool {},
+    )
+}
+
+pub fn create_pool_template(
+    creator: Pubkey,
+    reference: Pubkey,
+    terms: PoolTemplateTerms,
+) -> Instruction {
+    build(
+        accounts::CreatePoolTemplate {
+            creator,
+            template: find_template_address(&reference).0,
+            system_program: system_program::ID,
+        },
+        ix::CreatePoolTemplate { reference, terms },
+    )
+}
+
+pub fn update_pool_template(
+    creator: Pubkey,
+    template: Pubkey,
+    terms: PoolTemplateTerms,
+) -> Instruction {
+    build(
+        accounts::UpdatePoolTemplate { creator, template },
+        ix::UpdatePoolTemplate { terms },
+    )
+}
+
+pub fn set_template_fee_override(
+    grand_authority: Pubkey,
+    template: Pubkey,
+    protocol_fee_bps: Option<u16>,
+) -> Instruction {
+    build(
+        accounts::SetTemplateFeeOverride {
+            grand_authority,
+            project_pda: find_project_address().0,
+            template,
+        },
+        ix::SetTemplateFeeOverride { protocol_fee_bps },
+    )
+}
+
+/// The pool lands at `find_pool_address(&reference)`; `id` and `creator_index` are as in
+/// [`create_pool`].
+pub fn create_pool_from_template(
+    creator: Pubkey,
+    template: Pubkey,
+    reference: Pubkey,
+    shares: u64,
+    start_date: u64,
+    id: u64,
+    creator_index: u64,
+) -> Instruction {
+    build(
+        accounts::CreatePoolFromTemplate {
+            create_pool: create_pool_accounts(creator, &reference, id, creator_index),
+            template,
+        },
+        ix::CreatePoolFromTemplate {
+            reference,
+            shares,
+            start_date,
+        },
+    )
+}
+
+pub fn update_pool_metadata(
+    authority: Pubkey,
+    pool: Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Instruction {
+    build(
+        accounts::UpdatePoolMetadata {
+            authority,
+            pool,
+            metadata: find_metadata_address(&find_mint_address(&pool).0).0,
+            metadata_program: MPL_TOKEN_METADATA_ID,
+        },
+        ix::UpdatePoolMetadata { name, symbol, uri },
+    )
+}
+
+pub fn join_pool_collection(authority: Pubkey, pool: Pubkey) -> Instruction {
+    let collection_mint: Pubkey = find_collection_mint_address().0;
+
+    build(
+        accounts::JoinPoolCollection {
+            authority,
+            pool,
+            metadata: find_metadata_address(&find_mint_address(&pool).0).0,
+            project_pda: find_project_address().0,
+            collection_mint,
+            collection_metadata: find_metadata_address(&collection_mint).0,
+            collection_master_edition: find_master_edition_address(&collection_mint).0,
+            metadata_program: MPL_TOKEN_METADATA_ID,
+        },
+        ix::JoinPoolCollection {},
+    )
+}
+
+pub fn register_vehicle_asset(creator: Pubkey, details: VehicleAssetDetails) -> Instruction {
+    build(
+        accounts::RegisterVehicleAsset {
+            creator,
+            pool_creator_data: find_pool_creator_address(&creator).0,
+            vehicle_asset: find_vehicle_asset_address(&details.vin_hash).0,
+            system_program: system_program::ID,
+        },
+        ix::RegisterVehicleAsset { details },
+    )
+}
+
+pub fn update_vehicle_asset_status(
+    creator: Pubkey,
+    vin_hash: &[u8; 32],
+    status: VehicleStatus,
+) -> Instruction {
+    build(
+        accounts::UpdateVehicleAssetStatus {
+            creator,
+            vehicle_asset: find_vehicle_asset_address(vin_hash).0,
+        },
+        ix::UpdateVehicleAssetStatus { status },
+    )
+}
+
+pub fn link_vehicle_asset(creator: Pubkey, pool: Pubkey, vin_hash: &[u8; 32]) -> Instruction {
+    build(
+        accounts::LinkVehicleAsset {
+            creator,
+            pool,
+            vehicle_asset: find_vehicle_asset_address(vin_hash).0,
+        },
+        ix::LinkVehicleAsset {},
+    )
+}
+
+/// `vin_hash` must be linked to the pool, and `index` must be the pool's current
+/// `tranches` count.
+pub fn add_asset_tranche(
+    authority: Pubkey,
+    pool: Pubkey,
+    index: u16,
+    vin_hash: &[u8; 32],
+    principal_allocation: u64,
+) -> Instruction {
+    build(
+        accounts::AddAssetTranche {
+            authority,
+            pool,
+            vehicle_asset: find_vehicle_asset_address(vin_hash).0,
+            tranche: find_tranche_address(&pool, index).0,
+            system_program: system_program::ID,
+        },
+        ix::AddAssetTranche {
+            principal_allocation,
+        },
+    )
+}
+
+/// `locked` must match whether the pool was created with a lockup, and `approver` is
+/// the pool authority co-signing for `Approved` pools.
+pub fn buy_shares(
+    buyer: Pubkey,
+    approver: Option<Pubkey>,
+    pool: Pubkey,
+    shares: u64,
+    locked: bool,
+) -> Instruction {
+    build(
+        accounts::BuyShares {
+            buyer,
+            approver,
+            pool,
+            mint: find_mint_address(&pool).0,
+            buyer_mint_account: get_shares_address(&pool, &buyer),
+            usdc_mint: CUSTOM_USDC_MINT,
+            buyer_usdc_account: get_usdc_address(&buyer),
+            pool_usdc_account: get_usdc_address(&pool),
+            pool_escrow_account: locked.then(|| find_escrow_address(&pool).0),
+            buyer_lockup: locked.then(|| find_lockup_address(&pool, &buyer).0),
+            buyer_position: find_position_address(&pool, &buyer).0,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::BuyShares { shares },
+    )
+}
+
+pub fn release_shares(holder: Pubkey, pool: Pubkey) -> Instruction {
+    build(
+        accounts::ReleaseShares {
+            holder,
+            pool,
+            mint: find_mint_address(&pool).0,
+            pool_escrow_account: find_escrow_address(&pool).0,
+            holder_lockup: find_lockup_address(&pool, &holder).0,
+            holder_mint_account: get_shares_address(&pool, &holder),
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::ReleaseShares {},
+    )
+}
+
+pub fn list_shares(seller: Pubkey, pool: Pubkey, shares: u64, price_per_share: u64) -> Instruction {
+    let listing: Pubkey = find_listing_address(&pool, &seller).0;
+
+    build(
+        accounts::ListShares {
+            seller,
+            pool,
+            mint: find_mint_address(&pool).0,
+            listing,
+            seller_mint_account: get_shares_address(&pool, &seller),
+            listing_mint_account: get_shares_address(&pool, &listing),
+            seller_position: find_position_address(&pool, &seller).0,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::ListShares {
+            shares,
+            price_per_share,
+        },
+    )
+}
+
+pub fn cancel_listing(seller: Pubkey, pool: Pubkey) -> Instruction {
+    let listing: Pubkey = find_listing_address(&pool, &seller).0;
+
+    build(
+        accounts::CancelListing {
+            seller,
+            pool,
+            mint: find_mint_address(&pool).0,
+            listing,
+            seller_mint_account: get_shares_address(&pool, &seller),
+            listing_mint_account: get_shares_address(&pool, &listing),
+            token_program: token::ID,
+        },
+        ix::CancelListing {},
+    )
+}
+
+pub fn fill_listing(
+    buyer: Pubkey,
+    approver: Option<Pubkey>,
+    pool: Pubkey,
+    seller: Pubkey,
+    shares: u64,
+) -> Instruction {
+    let listing: Pubkey = find_listing_address(&pool, &seller).0;
+
+    build(
+        accounts::FillListing {
+            buyer,
+            approver,
+            pool,
+            mint: find_mint_address(&pool).0,
+            listing,
+            listing_mint_account: get_shares_address(&pool, &listing),
+            buyer_mint_account: get_shares_address(&pool, &buyer),
+            usdc_mint: CUSTOM_USDC_MINT,
+            buyer_usdc_account: get_usdc_address(&buyer),
+            seller_usdc_account: get_usdc_address(&seller),
+            seller_position: find_position_address(&pool, &seller).0,
+            buyer_position: find_position_address(&pool, &buyer).0,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::FillListing { shares },
+    )
+}
+
+pub fn transfer_shares(
+    holder: Pubkey,
+    recipient: Pubkey,
+    approver: Option<Pubkey>,
+    pool: Pubkey,
+    shares: u64,
+) -> Instruction {
+    build(
+        accounts::TransferShares {
+            holder,
+            recipient,
+            approver,
+            pool,
+            mint: find_mint_address(&pool).0,
+            holder_mint_account: get_shares_address(&pool, &holder),
+            recipient_mint_account: get_shares_address(&pool, &recipient),
+            holder_position: find_position_address(&pool, &holder).0,
+            recipient_position: find_position_address(&pool, &recipient).0,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::TransferShares { shares },
+    )
+}
+
+/// `tranche` is the index of the repaying tranche, required for fleet pools.
+pub fn create_distribution(
+    authority: Pubkey,
+    distribution_authority: Pubkey,
+    pool: Pubkey,
+    distribution_id: u8,
+) -> Instruction {
+    let distribution: Pubkey = find_distribution_address(&pool, distribution_id).0;
+
+    build(
+        accounts::CreateDistribution {
+            authority,
+            distribution_authority,
+            pool,
+            distribution,
+            usdc_mint: CUSTOM_USDC_MINT,
+            distribution_usdc_account: get_usdc_address(&distribution),
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::CreateDistribution {},
+    )
+}
+
+/// `signer` is the current distribution authority or the pool authority.
+pub fn set_distribution_authority(
+    signer: Pubkey,
+    new_authority: Pubkey,
+    pool: Pubkey,
+    distribution_id: u8,
+) -> Instruction {
+    build(
+        accounts::SetDistributionAuthority {
+            signer,
+            new_authority,
+            pool,
+            distribution: find_distribution_address(&pool, distribution_id).0,
+        },
+        ix::SetDistributionAuthority {},
+    )
+}
+
+pub fn close_distribution(authority: Pubkey, pool: Pubkey, distribution_id: u8) -> Instruction {
+    let distribution: Pubkey = find_distribution_address(&pool, distribution_id).0;
+
+    build(
+        accounts::CloseDistribution {
+            authority,
+            pool,
+            distribution,
+            usdc_mint: CUSTOM_USDC_MINT,
+            distribution_usdc_account: get_usdc_address(&distribution),
+            token_program: token::ID,
+        },
+        ix::CloseDistribution {},
+    )
+}
+
+pub fn distribute(
+    signer: Pubkey,
+    distribution_authority: Pubkey,
+    pool: Pubkey,
+    distribution_id: u8,
+    amount: u64,
+    tranche: Option<u16>,
+) -> Instruction {
+    let distribution: Pubkey = find_distribution_address(&pool, distribution_id).0;
+
+    build(
+        accounts::Distribute {
+            signer,
+            distribution_authority,
+            pool,
+            signer_usdc_account: get_usdc_address(&signer),
+            distribution,
+            tranche: tranche.map(|index| find_tranche_address(&pool, index).0),
+            reserve: find_reserve_address(&pool).0,
+            usdc_mint: CUSTOM_USDC_MINT,
+            distribution_usdc_account: get_usdc_address(&distribution),
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::Distribute { amount },
+    )
+}
+
+/// `class_id` must be the pool's current `share_classes` count.
+pub fn create_share_class(
+    authority: Pubkey,
+    pool: Pubkey,
+    class_id: u8,
+    shares: u64,
+    apy: u8,
+    eligibility: BuyerEligibility,
+) -> Instruction {
+    build(
+        accounts::CreateShareClass {
+            authority,
+            pool,
+            share_class: find_share_class_address(&pool, class_id).0,
+            mint: find_class_mint_address(&pool, class_id).0,
+            system_program: system_program::ID,
+            token_program: token::ID,
+        },
+        ix::CreateShareClass {
+            shares,
+            apy,
+            eligibility,
+        },
+    )
+}
+
+/// `approver` is the pool authority co-signing for `Approved` classes.
+pub fn buy_class_shares(
+    buyer: Pubkey,
+    approver: Option<Pubkey>,
+    pool: Pubkey,
+    class_id: u8,
+    shares: u64,
+) -> Instruction {
+    build(
+        accounts::BuyClassShares {
+            buyer,
+            approver,
+            pool,
+            share_class: find_share_class_address(&pool, class_id).0,
+            mint: find_class_mint_address(&pool, class_id).0,
+            buyer_mint_account: get_class_shares_address(&pool, class_id, &buyer),
+            usdc_mint: CUSTOM_USDC_MINT,
+            buyer_usdc_account: get_usdc_address(&buyer),
+            pool_usdc_account: get_usdc_address(&pool),
+            buyer_position: find_position_address(&pool, &buyer).0,
+            class_position: find_class_position_address(&pool, class_id, &buyer).0,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::BuyClassShares { shares },
+    )
+}
+
+/// `share_classes` must be the pool's `share_classes` count.
+pub fn allocate_class_rewards(pool: Pubkey, share_classes: u8) -> Instruction {
+    let mut instruction: Instruction = build(
+        accounts::AllocateClassRewards {
+            pool,
+            distribution: find_distribution_address(&pool, 0).0,
+        },
+        ix::AllocateClassRewards {},
+    );
+    instruction.accounts.extend(
+        (0..share_classes)
+            .map(|class_id| AccountMeta::new(find_share_class_address(&pool, class_id).0, false)),
+    );
+    instruction
+}
+
+pub fn claim_class_rewards(
+    holder: Pubkey,
+    authority: Pubkey,
+    pool: Pubkey,
+    class_id: u8,
+    rewards: u64,
+) -> Instruction {
+    let distribution: Pubkey = find_distribution_address(&pool, 0).0;
+
+    build(
+        accounts::ClaimClassRewards {
+            holder,
+            authority,
+            pool,
+            distribution,
+            share_class: find_share_class_address(&pool, class_id).0,
+            usdc_mint: CUSTOM_USDC_MINT,
+            distribution_usdc_account: get_usdc_address(&distribution),
+            holder_usdc_account: get_usdc_address(&holder),
+            holder_position: find_position_address(&pool, &holder).0,
+            class_position: find_class_position_address(&pool, class_id, &holder).0,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::ClaimClassRewards { rewards },
+    )
+}
+
+pub fn claim_rewards(
+    holder: Pubkey,
+    authority: Pubkey,
+    pool: Pubkey,
+    distribution_id: u8,
+    rewards: u64,
+) -> Instruction {
+    let distribution: Pubkey = find_distribution_address(&pool, distribution_id).0;
+
+    build(
+        accounts::ClaimRewards {
+            holder,
+            authority,
+            pool,
+            distribution,
+            mint: find_mint_address(&pool).0,
+            holder_mint_account: get_shares_address(&pool, &holder),
+            usdc_mint: CUSTOM_USDC_MINT,
+            distribution_usdc_account: get_usdc_address(&distribution),
+            holder_usdc_account: get_usdc_address(&holder),
+            holder_position: find_position_address(&pool, &holder).0,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::ClaimRewards { rewards },
+    )
+}
+
+pub fn configure_revenue(
+    authority: Pubkey,
+    pool: Pubkey,
+    operator: Pubkey,
+    driver_bps: u16,
+    maintenance_bps: u16,
+) -> Instruction {
+    build(
+        accounts::ConfigureRevenue {
+            authority,
+            pool,
+            project_pda: find_project_address().0,
+            revenue_config: find_revenue_config_address(&pool).0,
+            usdc_mint: CUSTOM_USDC_MINT,
+            driver_bucket: find_revenue_bucket_address(&pool, RevenueBucket::Driver).0,
+            maintenance_bucket: find_revenue_bucket_address(&pool, RevenueBucket::Maintenance).0,
+            protocol_bucket: find_revenue_bucket_address(&pool, RevenueBucket::Protocol).0,
+            investor_bucket: find_revenue_bucket_address(&pool, RevenueBucket::Investors).0,
+            system_program: system_program::ID,
+            token_program: token::ID,
+        },
+        ix::ConfigureRevenue {
+            operator,
+            driver_bps,
+            maintenance_bps,
+        },
+    )
+}
+
+/// `reserve` says whether the pool has configured its reserve, which then takes the
+/// maintenance share.
+pub fn report_revenue(operator: Pubkey, pool: Pubkey, amount: u64, reserve: bool) -> Instruction {
+    build(
+        accounts::ReportRevenue {
+            operator,
+            pool,
+            project_pda: find_project_address().0,
+            revenue_config: find_revenue_config_address(&pool).0,
+            usdc_mint: CUSTOM_USDC_MINT,
+            operator_usdc_account: get_usdc_address(&operator),
+            driver_bucket: find_revenue_bucket_address(&pool, RevenueBucket::Driver).0,
+            maintenance_bucket: find_revenue_bucket_address(&pool, RevenueBucket::Maintenance).0,
+            reserve: reserve.then(|| find_reserve_address(&pool).0),
+            protocol_bucket: find_revenue_bucket_address(&pool, RevenueBucket::Protocol).0,
+            investor_bucket: find_revenue_bucket_address(&pool, RevenueBucket::Investors).0,
+            token_program: token::ID,
+        },
+        ix::ReportRevenue { amount },
+    )
+}
+
+pub fn withdraw_revenue(
+    signer: Pubkey,
+    pool: Pubkey,
+    bucket: RevenueBucket,
+    to_usdc_account: Pubkey,
+    amount: u64,
+) -> Instruction {
+    build(
+        accounts::WithdrawRevenue {
+            signer,
+            pool,
+            project_pda: find_project_address().0,
+            revenue_config: find_revenue_config_address(&pool).0,
+            usdc_mint: CUSTOM_USDC_MINT,
+            bucket_account: find_revenue_bucket_address(&pool, bucket).0,
+            to_usdc_account,
+            token_program: token::ID,
+        },
+        ix::WithdrawRevenue { bucket, amount },
+    )
+}
+
+/// `signer` is the revenue operator or the pool authority. `tranche` is the index of the
+/// repaying tranche, required for fleet pools.
+pub fn distribute_revenue(signer: Pubkey, pool: Pubkey, tranche: Option<u16>) -> Instruction {
+    let distribution: Pubkey = find_distribution_address(&pool, 0).0;
+
+    build(
+        accounts::DistributeRevenue {
+            signer,
+            pool,
+            revenue_config: find_revenue_config_address(&pool).0,
+            distribution,
+            tranche: tranche.map(|index| find_tranche_address(&pool, index).0),
+            reserve: find_reserve_address(&pool).0,
+            usdc_mint: CUSTOM_USDC_MINT,
+            investor_bucket: find_revenue_bucket_address(&pool, RevenueBucket::Investors).0,
+            distribution_usdc_account: get_usdc_address(&distribution),
+            token_program: token::ID,
+        },
+        ix::DistributeRevenue {},
+    )
+}
+
+pub fn configure_reserve(
+    authority: Pubkey,
+    pool: Pubkey,
+    principal_bps: u16,
+    target: u64,
+) -> Instruction {
+    build(
+        accounts::ConfigureReserve {
+            authority,
+            pool,
+            reserve: find_reserve_address(&pool).0,
+            usdc_mint: CUSTOM_USDC_MINT,
+            reserve_vault: find_reserve_vault_address(&pool).0,
+            system_program: system_program::ID,
+            token_program: token::ID,
+        },
+        ix::ConfigureReserve {
+            principal_bps,
+            target,
+        },
+    )
+}
+
+/// `index` must be the reserve's current `expenses` count.
+pub fn spend_reserve(
+    authority: Pubkey,
+    pool: Pubkey,
+    index: u64,
+    to_usdc_account: Pubkey,
+    expense: ReceiptedExpense,
+) -> Instruction {
+    let reserve: Pubkey = find_reserve_address(&pool).0;
+
+    build(
+        accounts::SpendReserve {
+            authority,
+            pool,
+            reserve,
+            expense: find_reserve_expense_address(&reserve, index).0,
+            usdc_mint: CUSTOM_USDC_MINT,
+            reserve_vault: find_reserve_vault_address(&pool).0,
+            to_usdc_account,
+            system_program: system_program::ID,
+            token_program: token::ID,
+        },
+        ix::SpendReserve {
+            amount: expense.amount,
+            category: expense.category,
+            receipt_hash: expense.receipt_hash,
+        },
+    )
+}
+
+pub fn set_pool_valuator(authority: Pubkey, pool: Pubkey, valuator: Pubkey) -> Instruction {
+    build(
+        accounts::SetPoolValuator { authority, pool },
+        ix::SetPoolValuator { valuator },
+    )
+}
+
+pub fn update_nav(valuator: Pubkey, pool: Pubkey, nav_per_share: u64) -> Instruction {
+    build(
+        accounts::UpdateNav { valuator, pool },
+        ix::UpdateNav { nav_per_share },
+    )
+}
+
+pub fn get_nav(pool: Pubkey) -> Instruction {
+    build(accounts::GetNav { pool }, ix::GetNav {})
+}
+
+/// `distributing` includes the pool's distribution account, which only exists once the
+/// pool is active or settled.
+pub fn get_pool_summary(pool: Pubkey, distributing: bool) -> Instruction {
+    build(
+        accounts::GetPoolSummary {
+            pool,
+            distribution: distributing.then(|| find_distribution_address(&pool, 0).0),
+        },
+        ix::GetPoolSummary {},
+    )
+}
+
+pub fn get_tranche_summary(pool: Pubkey, index: u16) -> Instruction {
+    build(
+        accounts::GetTrancheSummary {
+            tranche: find_tranche_address(&pool, index).0,
+        },
+        ix::GetTrancheSummary {},
+    )
+}
+
+pub fn get_claimable_rewards(pool: Pubkey, distribution_id: u8, holder: Pubkey) -> Instruction {
+    build(
+        accounts::GetClaimableRewards {
+            pool,
+            distribution: find_distribution_address(&pool, distribution_id).0,
+            holder_position: find_position_address(&pool, &holder).0,
+        },
+        ix::GetClaimableRewards { holder },
+    )
+}
+
+pub fn get_claimable_class_rewards(pool: Pubkey, class_id: u8, holder: Pubkey) -> Instruction {
+    build(
+        accounts::GetClaimableClassRewards {
+            share_class: find_share_class_address(&pool, class_id).0,
+            class_position: find_class_position_address(&pool, class_id, &holder).0,
+        },
+        ix::GetClaimableClassRewards { holder },
+    )
+}
+
+pub fn get_redeemable_principal(pool: Pubkey, holder: Pubkey) -> Instruction {
+    build(
+        accounts::GetRedeemablePrincipal {
+            pool,
+            mint: find_mint_address(&pool).0,
+            holder_mint_account: get_shares_address(&pool, &holder),
+            holder_lockup: find_lockup_address(&pool, &holder).0,
+            holder_listing: find_listing_address(&pool, &holder).0,
+            token_program: token::ID,
+        },
+        ix::GetRedeemablePrincipal { holder },
+    )
+}
+
+pub fn quote_buy(pool: Pubkey, shares: u64) -> Instruction {
+    build(accounts::QuoteBuy { pool }, ix::QuoteBuy { shares })
+}
+
+/// `tranche` is the index of the financed tranche, required for fleet pools. `reserve`
+/// says whether the pool has a reserve, which then takes its share of the principal.
+pub fn withdraw_from_pool(
+    authority: Pubkey,
+    pool: Pubkey,
+    to_usdc_account: Pubkey,
+    shares: u64,
+    tranche: Option<u16>,
+    reserve: bool,
+) -> Instruction {
+    build(
+        accounts::WithdrawFromPool {
+            authority,
+            pool,
+            usdc_mint: CUSTOM_USDC_MINT,
+            pool_usdc_account: get_usdc_address(&pool),
+            to_usdc_account,
+            reserve: reserve.then(|| find_reserve_address(&pool).0),
+            reserve_vault: reserve.then(|| find_reserve_vault_address(&pool).0),
+            tranche: tranche.map(|index| find_tranche_address(&pool, index).0),
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::WithdrawFromPool { shares },
+    )
+}
+
+pub fn transition_pool(authority: Pubkey, pool: Pubkey, status: PoolStatus) -> Instruction {
+    build(
+        accounts::TransitionPool { authority, pool },
+        ix::TransitionPool { status },
+    )
+}
+
+pub fn activate_pool(
+    authority: Pubkey,
+    distribution_authority: Pubkey,
+    pool: Pubkey,
+) -> Instruction {
+    let distribution: Pubkey = find_distribution_address(&pool, 0).0;
+
+    build(
+        accounts::ActivatePool {
+            authority,
+            pool,
+            distribution,
+            distribution_authority,
+            usdc_mint: CUSTOM_USDC_MINT,
+            distribution_usdc_account: get_usdc_address(&distribution),
+            token_program: token::ID,
+            system_program: system_program::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::ActivatePool {},
+    )
+}
+
+pub fn close_pool(authority: Pubkey, distribution_authority: Pubkey, pool: Pubkey) -> Instruction {
+    let distribution: Pubkey = find_distribution_address(&pool, 0).0;
+
+    build(
+        accounts::ClosePool {
+            authority,
+            pool,
+            distribution,
+            distribution_authority,
+            usdc_mint: CUSTOM_USDC_MINT,
+            distribution_usdc_account: get_usdc_address(&distribution),
+            token_program: token::ID,
+            system_program: system_program::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::ClosePool {},
+    )
+}
+
+pub fn refund_shares(holder: Pubkey, pool: Pubkey, shares: u64) -> Instruction {
+    build(
+        accounts::RefundShares {
+            holder,
+            pool,
+            mint: find_mint_address(&pool).0,
+            holder_mint_account: get_shares_address(&pool, &holder),
+            usdc_mint: CUSTOM_USDC_MINT,
+            pool_usdc_account: get_usdc_address(&pool),
+            holder_usdc_account: get_usdc_address(&holder),
+            holder_position: find_position_address(&pool, &holder).0,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::RefundShares { shares },
+    )
+}
+
+pub fn refund_class_shares(holder: Pubkey, pool: Pubkey, class_id: u8, shares: u64) -> Instruction {
+    build(
+        accounts::RefundClassShares {
+            holder,
+            pool,
+            share_class: find_share_class_address(&pool, class_id).0,
+            mint: find_class_mint_address(&pool, class_id).0,
+            holder_mint_account: get_class_shares_address(&pool, class_id, &holder),
+            usdc_mint: CUSTOM_USDC_MINT,
+            pool_usdc_account: get_usdc_address(&pool),
+            holder_usdc_account: get_usdc_address(&holder),
+            holder_position: find_position_address(&pool, &holder).0,
+            class_position: find_class_position_address(&pool, class_id, &holder).0,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::RefundClassShares { shares },
+    )
+}
+
+pub fn open_redemption_window(
+    authority: Pubkey,
+    pool: Pubkey,
+    close_date: u64,
+    liquidity: u64,
+    source: RedemptionSource,
+) -> Instruction {
+    let redemption_window: Pubkey = find_redemption_window_address(&pool).0;
+    let distribution: Option<Pubkey> =
+        (source == RedemptionSource::Distribution).then(|| find_distribution_address(&pool, 0).0);
+
+    build(
+        accounts::OpenRedemptionWindow {
+            authority,
+            pool,
+            redemption_window,
+            usdc_mint: CUSTOM_USDC_MINT,
+            pool_usdc_account: get_usdc_address(&pool),
+            redemption_usdc_account: get_usdc_address(&redemption_window),
+            distribution,
+            distribution_usdc_account: distribution.as_ref().map(get_usdc_address),
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::OpenRedemptionWindow {
+            close_date,
+            liquidity,
+            source,
+        },
+    )
+}
+
+pub fn tender_shares(holder: Pubkey, pool: Pubkey, shares: u64) -> Instruction {
+    let redemption_window: Pubkey = find_redemption_window_address(&pool).0;
+
+    build(
+        accounts::TenderShares {
+            holder,
+            pool,
+            mint: find_mint_address(&pool).0,
+            redemption_window,
+            tender: find_tender_address(&redemption_window, &holder).0,
+            holder_mint_account: get_shares_address(&pool, &holder),
+            redemption_mint_account: get_shares_address(&pool, &redemption_window),
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::TenderShares { shares },
+    )
+}
+
+pub fn settle_tender(signer: Pubkey, pool: Pubkey, holder: Pubkey) -> Instruction {
+    let redemption_window: Pubkey = find_redemption_window_address(&pool).0;
+
+    build(
+        accounts::SettleTender {
+            signer,
+            holder,
+            pool,
+            mint: find_mint_address(&pool).0,
+            redemption_window,
+            tender: find_tender_address(&redemption_window, &holder).0,
+            redemption_mint_account: get_shares_address(&pool, &redemption_window),
+            holder_mint_account: get_shares_address(&pool, &holder),
+            usdc_mint: CUSTOM_USDC_MINT,
+            redemption_usdc_account: get_usdc_address(&redemption_window),
+            holder_usdc_account: get_usdc_address(&holder),
+            holder_position: find_position_address(&pool, &holder).0,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        ix::SettleTender {},
+    )
+}
+
+/// `source` must match the source the window was opened with.
+pub fn close_redemption_window(
+    authority: Pubkey,
+    pool: Pubkey,
+    source: RedemptionSource,
+) -> Instruction {
+    let redemption_window: Pubkey = find_redemption_window_address(&pool).0;
+    let distribution: Option<Pubkey> =
+        (source == RedemptionSource::Distribution).then(|| find_distribution_address(&pool, 0).0);
+
+    build(
+        accounts::CloseRedemptionWindow {
+            authority,
+            pool,
+            redemption_window,
+            usdc_mint: CUSTOM_USDC_MINT,
+            redemption_usdc_account: get_usdc_address(&redemption_window),
+            pool_usdc_account: get_usdc_address(&pool),
+            distribution,
+            distribution_usdc_account: distribution.as_ref().map(get_usdc_address),
+            token_program: token::ID,
+        },
+        ix::CloseRedemptionWindow {},
+    )
+}
+
+pub fn close_pool_accounts(authority: Pubkey, pool: Pubkey) -> Instruction {
+    let distribution: Pubkey = find_distribution_address(&pool, 0).0;
+
+    build(
+        accounts::ClosePoolAccounts {
+            authority,
+            pool,
+            distribution,
+            mint: find_mint_address(&pool).0,
+            usdc_mint: CUSTOM_USDC_MINT,
+            pool_usdc_account: get_usdc_address(&pool),
+            distribution_usdc_account: get_usdc_address(&distribution),
+            token_program: token::ID,
+        },
+        ix::ClosePoolAccounts {},
+    )
+}
+
+pub fn migrate_project(payer: Pubkey) -> Instruction {
+    build(
+        accounts::MigrateProject {
+            payer,
+            project_pda: find_project_address().0,
+            system_program: system_program::ID,
+        },
+        ix::MigrateProject {},
+    )
+}
+
+pub fn migrate_pool(payer: Pubkey, pool: Pubkey) -> Instruction {
+    build(
+        accounts::MigratePool {
+            payer,
+            pool,
+            system_program: system_program::ID,
+        },
+        ix::MigratePool {},
+    )
+}
+
+/// The pool must be migrated first.
+pub fn migrate_distribution(payer: Pubkey, pool: Pubkey) -> Instruction {
+    build(
+        accounts::MigrateDistribution {
+            payer,
+            pool,
+            distribution: find_distribution_address(&pool, 0).0,
+            system_program: system_program::ID,
+        },
+        ix::MigrateDistribution {},
+    )
+}
@@ -0,0 +1,10 @@
+//! Off-chain client for the octo-program.
+//!
+//! Builds every program instruction from typed arguments, derives the program's PDAs
+//! and decodes its accounts, without linking the on-chain entrypoint.
+
+pub mod instructions;
+pub mod pda;
+pub mod state;
+
+pub use octo_program::{CUSTOM_USDC_MINT, ID, PROJECT_PDA};
@@ -0,0 +1,173 @@
+use anchor_lang::{prelude::Pubkey, solana_program::hash::hash};
+use anchor_spl::associated_token::get_associated_token_address;
+use mpl_token_metadata::ID as MPL_TOKEN_METADATA_ID;
+use octo_program::{RevenueBucket, COLLECTION_SEED, CUSTOM_USDC_MINT, ID, PROJECT_PDA};
+
+pub fn find_project_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROJECT_PDA], &ID)
+}
+
+pub fn find_pool_creator_address(creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROJECT_PDA, creator.as_ref()], &ID)
+}
+
+pub fn find_pool_address(reference: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"pool", reference.as_ref()], &ID)
+}
+
+/// The registry entry of the pool with sequential id `id`.
+pub fn find_pool_entry_address(id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"pool_id", &id.to_le_bytes()], &ID)
+}
+
+/// The count of pools registered by `creator`.
+pub fn find_creator_pools_address(creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"creator_pools", creator.as_ref()], &ID)
+}
+
+/// The registry entry of the `index`th pool registered by `creator`.
+pub fn find_creator_pool_entry_address(creator: &Pubkey, index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"creator_pool", creator.as_ref(), &index.to_le_bytes()],
+        &ID,
+    )
+}
+
+pub fn find_template_address(reference: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"template", reference.as_ref()], &ID)
+}
+
+pub fn find_mint_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"mint", pool.as_ref()], &ID)
+}
+
+/// Share class `class_id` of `pool`; class 0 is the most senior.
+pub fn find_share_class_address(pool: &Pubkey, class_id: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"share_class", pool.as_ref(), &[class_id]], &ID)
+}
+
+/// What `holder` has claimed from share class `class_id` of `pool`.
+pub fn find_class_position_address(pool: &Pubkey, class_id: u8, holder: &Pubkey) -> (Pubkey, u8) {
+    let share_class: Pubkey = find_share_class_address(pool, class_id).0;
+    Pubkey::find_program_address(
+        &[b"class_position", share_class.as_ref(), holder.as_ref()],
+        &ID,
+    )
+}
+
+/// The mint of share class `class_id` of `pool`.
+pub fn find_class_mint_address(pool: &Pubkey, class_id: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"mint", pool.as_ref(), &[class_id]], &ID)
+}
+
+/// The associated token account of `owner` for share class `class_id` of `pool`.
+pub fn get_class_shares_address(pool: &Pubkey, class_id: u8, owner: &Pubkey) -> Pubkey {
+    get_associated_token_address(owner, &find_class_mint_address(pool, class_id).0)
+}
+
+/// The position of `holder` in `pool`.
+pub fn find_position_address(pool: &Pubkey, holder: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"position", pool.as_ref(), holder.as_ref()], &ID)
+}
+
+/// Distribution `id` of `pool`; the primary distribution, id 0, has no id in its seeds.
+pub fn find_distribution_address(pool: &Pubkey, id: u8) -> (Pubkey, u8) {
+    match id {
+        0 => Pubkey::find_program_address(&[b"distribution", pool.as_ref()], &ID),
+        _ => Pubkey::find_program_address(&[b"distribution", pool.as_ref(), &[id]], &ID),
+    }
+}
+
+pub fn find_metadata_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"metadata", MPL_TOKEN_METADATA_ID.as_ref(), mint.as_ref()],
+        &MPL_TOKEN_METADATA_ID,
+    )
+}
+
+pub fn find_master_edition_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            MPL_TOKEN_METADATA_ID.as_ref(),
+            mint.as_ref(),
+            b"edition",
+        ],
+        &MPL_TOKEN_METADATA_ID,
+    )
+}
+
+/// The mint of the BlockRide collection every pool mint joins.
+pub fn find_collection_mint_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[COLLECTION_SEED], &ID)
+}
+
+pub fn find_vehicle_asset_address(vin_hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vehicle", vin_hash.as_ref()], &ID)
+}
+
+/// The SHA-256 of a VIN, trimmed and upper-cased so every spelling hashes alike.
+pub fn hash_vin(vin: &str) -> [u8; 32] {
+    hash(vin.trim().to_ascii_uppercase().as_bytes()).to_bytes()
+}
+
+pub fn find_escrow_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow", pool.as_ref()], &ID)
+}
+
+pub fn find_lockup_address(pool: &Pubkey, holder: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"lockup", pool.as_ref(), holder.as_ref()], &ID)
+}
+
+pub fn find_listing_address(pool: &Pubkey, seller: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"listing", pool.as_ref(), seller.as_ref()], &ID)
+}
+
+pub fn find_redemption_window_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"redemption", pool.as_ref()], &ID)
+}
+
+pub fn find_tender_address(redemption_window: &Pubkey, holder: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"tender", redemption_window.as_ref(), holder.as_ref()],
+        &ID,
+    )
+}
+
+pub fn find_revenue_config_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"revenue", pool.as_ref()], &ID)
+}
+
+/// The pool-owned USDC account collecting `bucket`'s share of reported revenue.
+pub fn find_revenue_bucket_address(pool: &Pubkey, bucket: RevenueBucket) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[bucket.seed(), pool.as_ref()], &ID)
+}
+
+pub fn find_reserve_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"reserve", pool.as_ref()], &ID)
+}
+
+/// The pool-owned USDC account holding the reserve, which is the maintenance bucket.
+pub fn find_reserve_vault_address(pool: &Pubkey) -> (Pubkey, u8) {
+    find_revenue_bucket_address(pool, RevenueBucket::Maintenance)
+}
+
+/// The record of the `index`th expense paid out of `reserve`.
+pub fn find_reserve_expense_address(reserve: &Pubkey, index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"expense", reserve.as_ref(), &index.to_le_bytes()], &ID)
+}
+
+/// The `index`th asset tranche of a fleet pool.
+pub fn find_tranche_address(pool: &Pubkey, index: u16) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"tranche", pool.as_ref(), &index.to_le_bytes()], &ID)
+}
+
+/// The USDC associated token account of `owner`.
+pub fn get_usdc_address(owner: &Pubkey) -> Pubkey {
+    get_associated_token_address(owner, &CUSTOM_USDC_MINT)
+}
+
+/// The associated token account of `owner` for the share mint of `pool`.
+pub fn get_shares_address(pool: &Pubkey, owner: &Pubkey) -> Pubkey {
+    get_associated_token_address(owner, &find_mint_address(pool).0)
+}
@@ -0,0 +1,21 @@
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Result};
+
+pub use octo_program::{
+    AssetTranche, BuyQuote, BuyerEligibility, ClassPosition, CreatorPools, Distribution,
+    ExpenseCategory, InvestorPosition, LegacyDistribution, LegacyPool, LegacyProjectData, Listing,
+    LockLot, LockupConfig, NavQuote, Pool, PoolCreatorData, PoolRegistryEntry, PoolReserve,
+    PoolStatus, PoolSummary, PoolTemplate, PoolTemplateTerms, ProjectData, ReceiptedExpense,
+    RedemptionSource, RedemptionTender, RedemptionWindow, ReserveExpense, RevenueBucket,
+    RevenueConfig, RevenueSplit, ShareClass, ShareLockup, TrancheSummary, VehicleAsset,
+    VehicleAssetDetails, VehicleStatus,
+};
+
+/// Decodes a program account, checking its discriminator.
+pub fn decode_account<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+/// Decodes the return data of a view instruction such as `get_pool_summary`.
+pub fn decode_return_data<T: AnchorDeserialize>(data: &[u8]) -> Result<T> {
+    Ok(T::try_from_slice(data)?)
+}
@@ -0,0 +1,42 @@
+//! Drives `tests/common/scenario.rs` with arbitrary instruction sequences and unbounded
+//! amounts, panicking as soon as an invariant breaks or the program aborts.
+//!
+//! Run from `programs/octo-program` with `cargo +nightly fuzz run instruction_sequence`.
+
+#![no_main]
+
+#[path = "../../tests/common/mod.rs"]
+mod common;
+
+use std::cell::RefCell;
+
+use common::{
+    scenario::{Action, Scenario},
+    TestEnv,
+};
+use libfuzzer_sys::fuzz_target;
+use tokio::runtime::Runtime;
+
+thread_local! {
+    static RUNTIME: Runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    // Starting a bank per input is slow and never fully freed, so inputs share one
+    static ENV: RefCell<Option<TestEnv>> = const { RefCell::new(None) };
+}
+
+fuzz_target!(|input: (bool, Vec<Action>)| {
+    let (locked, actions) = input;
+    RUNTIME.with(|runtime| {
+        runtime.block_on(async {
+            let env: TestEnv = match ENV.take() {
+                Some(env) => env,
+                None => TestEnv::start().await,
+            };
+            let mut scenario: Scenario = Scenario::new(env, locked).await;
+            scenario.run(&actions).await;
+            ENV.set(Some(scenario.into_env()));
+        })
+    });
+});
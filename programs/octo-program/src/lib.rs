@@ -1,14 +1,20 @@
 use solana_program::{pubkey, pubkey::Pubkey};
 use std::str::FromStr;
 use {
-    anchor_lang::prelude::*,
+    anchor_lang::{prelude::*, system_program, Discriminator},
     anchor_spl::{associated_token, mint, token},
     mpl_token_metadata::{
+        accounts::Metadata,
         instructions::{
-            CreateMetadataAccountV3Cpi, CreateMetadataAccountV3CpiAccounts,
-            CreateMetadataAccountV3InstructionArgs,
+            CreateMasterEditionV3Cpi, CreateMasterEditionV3CpiAccounts,
+            CreateMasterEditionV3InstructionArgs, CreateMetadataAccountV3Cpi,
+            CreateMetadataAccountV3CpiAccounts, CreateMetadataAccountV3InstructionArgs,
+            SetAndVerifySizedCollectionItemCpi, SetAndVerifySizedCollectionItemCpiAccounts,
+            UpdateMetadataAccountV2Cpi, UpdateMetadataAccountV2CpiAccounts,
+            UpdateMetadataAccountV2InstructionArgs, VerifyCollectionV1Cpi,
+            VerifyCollectionV1CpiAccounts, VerifyCreatorV1Cpi, VerifyCreatorV1CpiAccounts,
         },
-        types::DataV2,
+        types::{Collection, CollectionDetails, Creator, DataV2},
         ID as MPL_TOKEN_METADATA_ID,
     },
 };
@@ -25,6 +31,7 @@ pub mod octo_program {
         grand_authority: Pubkey,
     ) -> Result<()> {
         let program_data: &mut Box<Account<'_, ProjectData>> = &mut ctx.accounts.project_pda;
+        program_data.version = ProjectData::VERSION;
         program_data.grand_authority = grand_authority;
         Ok(())
     }
@@ -38,6 +45,18 @@ pub mod octo_program {
         Ok(())
     }
 
+    pub fn set_protocol_fee(ctx: Context<SetProtocolFee>, protocol_fee_bps: u16) -> Result<()> {
+        // Validate the input
+        require_gte!(
+            RevenueConfig::MAX_BPS,
+            protocol_fee_bps,
+            ErrorCode::InvalidRevenueSplit
+        );
+
+        ctx.accounts.project_pda.protocol_fee_bps = protocol_fee_bps;
+        Ok(())
+    }
+
     pub fn add_pool_creator(
         ctx: Context<AddCreator>,
         creator: Pubkey,
@@ -57,109 +76,58 @@ pub mod octo_program {
         Ok(())
     }
 
-    pub fn create_pool(
-        ctx: Context<CreatePool>,
-        reference: Pubkey,
-        authority: Pubkey,
-        // seed: u64,
-        shares: u64,
-        deposit: u64,
+    pub fn create_collection(
+        ctx: Context<CreateCollection>,
         name: String,
         symbol: String,
         uri: String,
-        start_date: u64,
-        maturity_date: u64,
-        apy: u8,
     ) -> Result<()> {
-        // Get the pool account
-        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
-
-        // Initialize the pool account
-        pool.init(
-            [
-                *ctx.accounts.creator.key,
-                authority,
-                reference,
-                // *ctx.accounts.reference.key,
-                ctx.accounts.mint.key(),
-            ],
-            [shares, start_date, maturity_date],
-            ctx.bumps.pool,
-            apy,
-        )?;
-
-        // Get the shares minted from the deposit
-        let minted: u64 = pool.get_shares_from_deposit(deposit);
-        // let clock = Clock::get()?;
-        // Validate the input
-        // require_gt!(
-        //     start_date,
-        //     clock.unix_timestamp,
-        //     ErrorCode::StartDatePassed
-        // );
-        // require_gt!(
-        //     maturity_date,
-        //     clock.unix_timestamp,
-        //     ErrorCode::MaturityDatePassed
-        // );
-        // require_gt!(maturity_date, start_date, ErrorCode::StartDatePassed);
-        // if !ctx.accounts.pool_creator_data.can_create || ctx.accounts.pool_creator_data.creator != ctx.accounts.creator.key() {
-        //  return Err(ErrorCode::CreatorNotAuthorized.into());
-        // }
-        require_gte!(shares, 1_u64, ErrorCode::MinimumShares);
-        require_gte!(deposit, Pool::MIN_DEPOSIT, ErrorCode::MinimumDeposit);
-        // require_eq!(seed % shares, 0, ErrorCode::InvalidSeedSharesRatio);
-        require!(pool.is_valid_deposit(deposit), ErrorCode::MinimumDeposit);
-        require_gte!(
-            pool.shares,
-            pool.minted + minted,
-            ErrorCode::ExceedsAvailableShares
-        );
-
         // Signer seeds
-        let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
-        let signers_seeds: &[&[&[u8]]; 1] = &[&pool_seeds[..]];
+        let project_seeds: &[&[u8]; 2] = &[PROJECT_PDA, &[ctx.bumps.project_pda]];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&project_seeds[..]];
 
-        // Send the USDC to the pool account
-        token::transfer(
-            CpiContext::new(
+        // Mint the single collection token
+        token::mint_to(
+            CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.creator_usdc_account.to_account_info(),
-                    to: ctx.accounts.pool_usdc_account.to_account_info(),
-                    authority: ctx.accounts.creator.to_account_info(),
+                token::MintTo {
+                    authority: ctx.accounts.project_pda.to_account_info(),
+                    mint: ctx.accounts.collection_mint.to_account_info(),
+                    to: ctx.accounts.collection_token_account.to_account_info(),
                 },
+                signers_seeds,
             ),
-            deposit,
+            1,
         )?;
 
-        // Create the metadata account
+        // Create the collection metadata account
         let metadata_program: &AccountInfo<'_> = &ctx.accounts.metadata_program.to_account_info();
-        let metadata: &AccountInfo<'_> = &ctx.accounts.metadata.to_account_info();
-        let mint: &AccountInfo<'_> = &ctx.accounts.mint.to_account_info();
-        let mint_authority: &AccountInfo<'_> = &pool.to_account_info();
-        let payer: &AccountInfo<'_> = &ctx.accounts.creator.to_account_info();
+        let metadata: &AccountInfo<'_> = &ctx.accounts.collection_metadata.to_account_info();
+        let edition: &AccountInfo<'_> = &ctx.accounts.collection_master_edition.to_account_info();
+        let mint: &AccountInfo<'_> = &ctx.accounts.collection_mint.to_account_info();
+        let authority: &AccountInfo<'_> = &ctx.accounts.project_pda.to_account_info();
+        let payer: &AccountInfo<'_> = &ctx.accounts.grand_authority.to_account_info();
         let system_program: &AccountInfo<'_> = &ctx.accounts.system_program.to_account_info();
+        let token_program: &AccountInfo<'_> = &ctx.accounts.token_program.to_account_info();
         let rent: &AccountInfo<'_> = &ctx.accounts.rent.to_account_info();
-        let update_authority: &AccountInfo<'_> = &pool.to_account_info();
 
-        let metadata_v3_cpi: CreateMetadataAccountV3Cpi<'_, '_> = CreateMetadataAccountV3Cpi::new(
+        CreateMetadataAccountV3Cpi::new(
             metadata_program,
             CreateMetadataAccountV3CpiAccounts {
                 metadata,
                 mint,
-                mint_authority,
+                mint_authority: authority,
                 payer,
                 system_program,
                 rent: Some(rent),
-                update_authority: (update_authority, true),
+                update_authority: (authority, true),
             },
             CreateMetadataAccountV3InstructionArgs {
-                collection_details: None,
+                collection_details: Some(CollectionDetails::V1 { size: 0 }),
                 data: DataV2 {
-                    name: name.to_string(),
-                    symbol: symbol.to_string(),
-                    uri: uri.to_string(),
+                    name,
+                    symbol,
+                    uri,
                     seller_fee_basis_points: 0,
                     creators: None,
                     collection: None,
@@ -167,25 +135,337 @@ pub mod octo_program {
                 },
                 is_mutable: true,
             },
-        );
-        metadata_v3_cpi.invoke_signed(signers_seeds)?;
+        )
+        .invoke_signed(signers_seeds)?;
 
-        // Mint tokens to the creator token account
-        token::mint_to(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                token::MintTo {
-                    authority: pool.to_account_info(),
-                    mint: ctx.accounts.mint.to_account_info(),
-                    to: ctx.accounts.creator_mint_account.to_account_info(),
-                },
-                signers_seeds,
+        // Fix the collection supply with a master edition
+        CreateMasterEditionV3Cpi::new(
+            metadata_program,
+            CreateMasterEditionV3CpiAccounts {
+                edition,
+                mint,
+                update_authority: authority,
+                mint_authority: authority,
+                payer,
+                metadata,
+                token_program,
+                system_program,
+                rent: Some(rent),
+            },
+            CreateMasterEditionV3InstructionArgs {
+                max_supply: Some(0),
+            },
+        )
+        .invoke_signed(signers_seeds)?;
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_pool(
+        ctx: Context<CreatePool>,
+        reference: Pubkey,
+        authority: Pubkey,
+        // seed: u64,
+        shares: u64,
+        deposit: u64,
+        name: String,
+        symbol: String,
+        uri: String,
+        start_date: u64,
+        maturity_date: u64,
+        apy: u8,
+        lockup: Option<LockupConfig>,
+    ) -> Result<()> {
+        issue_pool(
+            ctx,
+            reference,
+            authority,
+            shares,
+            deposit,
+            Pool::PAR,
+            name,
+            symbol,
+            uri,
+            start_date,
+            maturity_date,
+            apy,
+            lockup,
+        )
+    }
+
+    /// Gives a pool created before the registry existed its id and creator list entry.
+    pub fn register_pool(ctx: Context<RegisterPool>) -> Result<()> {
+        add_to_registry(
+            &mut ctx.accounts.project_pda,
+            &mut ctx.accounts.creator_pools,
+            [
+                &mut ctx.accounts.pool_entry,
+                &mut ctx.accounts.creator_pool_entry,
+            ],
+            &mut ctx.accounts.pool,
+            [
+                ctx.bumps.creator_pools,
+                ctx.bumps.pool_entry,
+                ctx.bumps.creator_pool_entry,
+            ],
+        )
+    }
+
+    pub fn create_pool_template(
+        ctx: Context<CreatePoolTemplate>,
+        reference: Pubkey,
+        terms: PoolTemplateTerms,
+    ) -> Result<()> {
+        // Validate the input
+        require!(terms.is_valid(), ErrorCode::InvalidTemplateTerms);
+
+        ctx.accounts.template.set(
+            ctx.accounts.creator.key(),
+            reference,
+            terms,
+            ctx.bumps.template,
+        )
+    }
+
+    pub fn update_pool_template(
+        ctx: Context<UpdatePoolTemplate>,
+        terms: PoolTemplateTerms,
+    ) -> Result<()> {
+        // Validate the input
+        require!(terms.is_valid(), ErrorCode::InvalidTemplateTerms);
+
+        ctx.accounts.template.set_terms(terms);
+        Ok(())
+    }
+
+    /// Gives every pool later created from the template a negotiated protocol fee.
+    pub fn set_template_fee_override(
+        ctx: Context<SetTemplateFeeOverride>,
+        protocol_fee_bps: Option<u16>,
+    ) -> Result<()> {
+        // Validate the input
+        if let Some(protocol_fee_bps) = protocol_fee_bps {
+            require_gte!(
+                RevenueConfig::MAX_BPS,
+                protocol_fee_bps,
+                ErrorCode::InvalidRevenueSplit
+            );
+        }
+
+        ctx.accounts.template.protocol_fee_bps = protocol_fee_bps;
+        Ok(())
+    }
+
+    /// Launches the next pool of a series on the template's terms. The pool is named and
+    /// its metadata URI filled in with its number within the series, and its shares are
+    /// issued at the template's share price. The creator seeds it with the fewest shares
+    /// worth the minimum deposit.
+    pub fn create_pool_from_template<'info>(
+        mut ctx: Context<'_, '_, '_, 'info, CreatePoolFromTemplate<'info>>,
+        reference: Pubkey,
+        shares: u64,
+        start_date: u64,
+    ) -> Result<()> {
+        // Get accounts
+        let template: &mut Account<'_, PoolTemplate> = &mut ctx.accounts.template;
+        template.add_pools(1)?;
+        let number: u32 = template.pools;
+        let maturity_date: u64 = start_date
+            .checked_add(template.term)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // Seed the pool with the fewest shares worth the minimum deposit
+        let deposit: u64 = Pool::MIN_DEPOSIT
+            .div_ceil(template.share_price)
+            .checked_mul(template.share_price)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        issue_pool(
+            Context::new(
+                ctx.program_id,
+                &mut ctx.accounts.create_pool,
+                ctx.remaining_accounts,
+                std::mem::take(&mut ctx.bumps.create_pool),
             ),
-            minted,
+            reference,
+            template.authority,
+            shares,
+            deposit,
+            template.share_price,
+            template.get_pool_name(number),
+            template.symbol.clone(),
+            template.get_pool_uri(number),
+            start_date,
+            maturity_date,
+            template.apy,
+            template.lockup,
         )?;
 
-        // Update the pool account
-        pool.add_minted(minted);
+        // Carry the series rules over to the pool
+        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.create_pool.pool;
+        pool.eligibility = template.eligibility;
+        pool.protocol_fee_bps = template.protocol_fee_bps;
+
+        emit!(PoolCreatedFromTemplate {
+            template: template.key(),
+            pool: pool.key(),
+            number,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_pool_metadata(
+        ctx: Context<UpdatePoolMetadata>,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        // Get accounts
+        let pool: &Account<'_, Pool> = &ctx.accounts.pool;
+        let metadata: &AccountInfo<'_> = &ctx.accounts.metadata.to_account_info();
+
+        // Keep the creators and collection as they are
+        let current: Metadata = Metadata::try_from(metadata)
+            .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?;
+
+        // Signer seeds
+        let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&pool_seeds[..]];
+
+        UpdateMetadataAccountV2Cpi::new(
+            &ctx.accounts.metadata_program.to_account_info(),
+            UpdateMetadataAccountV2CpiAccounts {
+                metadata,
+                update_authority: &pool.to_account_info(),
+            },
+            UpdateMetadataAccountV2InstructionArgs {
+                data: Some(DataV2 {
+                    name,
+                    symbol,
+                    uri,
+                    seller_fee_basis_points: current.seller_fee_basis_points,
+                    creators: current.creators,
+                    collection: current.collection,
+                    uses: current.uses,
+                }),
+                new_update_authority: None,
+                primary_sale_happened: None,
+                is_mutable: None,
+            },
+        )
+        .invoke_signed(signers_seeds)?;
+
+        Ok(())
+    }
+
+    /// Adds a pool created before the BlockRide collection existed to it.
+    pub fn join_pool_collection(ctx: Context<JoinPoolCollection>) -> Result<()> {
+        // Get accounts
+        let pool: &Account<'_, Pool> = &ctx.accounts.pool;
+
+        // Signer seeds
+        let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
+        let project_seeds: &[&[u8]; 2] = &[PROJECT_PDA, &[ctx.bumps.project_pda]];
+        let signers_seeds: &[&[&[u8]]; 2] = &[&pool_seeds[..], &project_seeds[..]];
+
+        SetAndVerifySizedCollectionItemCpi::new(
+            &ctx.accounts.metadata_program.to_account_info(),
+            SetAndVerifySizedCollectionItemCpiAccounts {
+                metadata: &ctx.accounts.metadata.to_account_info(),
+                collection_authority: &ctx.accounts.project_pda.to_account_info(),
+                payer: &ctx.accounts.authority.to_account_info(),
+                update_authority: &pool.to_account_info(),
+                collection_mint: &ctx.accounts.collection_mint.to_account_info(),
+                collection: &ctx.accounts.collection_metadata.to_account_info(),
+                collection_master_edition_account: &ctx
+                    .accounts
+                    .collection_master_edition
+                    .to_account_info(),
+                collection_authority_record: None,
+            },
+        )
+        .invoke_signed(signers_seeds)?;
+
+        Ok(())
+    }
+
+    pub fn register_vehicle_asset(
+        ctx: Context<RegisterVehicleAsset>,
+        details: VehicleAssetDetails,
+    ) -> Result<()> {
+        // Get accounts
+        let vehicle_asset: &mut Account<'_, VehicleAsset> = &mut ctx.accounts.vehicle_asset;
+        let now: u64 = Clock::get()?.unix_timestamp as u64;
+
+        // Validate the input
+        require!(details.is_valid(), ErrorCode::VehicleDetailsTooLong);
+
+        vehicle_asset.set(
+            ctx.accounts.creator.key(),
+            details,
+            now,
+            ctx.bumps.vehicle_asset,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn update_vehicle_asset_status(
+        ctx: Context<UpdateVehicleAssetStatus>,
+        status: VehicleStatus,
+    ) -> Result<()> {
+        // Get accounts
+        let vehicle_asset: &mut Account<'_, VehicleAsset> = &mut ctx.accounts.vehicle_asset;
+
+        // Validations
+        require!(
+            vehicle_asset.status.can_transition_to(status),
+            ErrorCode::InvalidVehicleStatus
+        );
+
+        vehicle_asset.status = status;
+        Ok(())
+    }
+
+    /// Records that `pool` financed the vehicle. A pool may finance several vehicles.
+    pub fn link_vehicle_asset(ctx: Context<LinkVehicleAsset>) -> Result<()> {
+        // Get accounts
+        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+        let vehicle_asset: &mut Account<'_, VehicleAsset> = &mut ctx.accounts.vehicle_asset;
+
+        vehicle_asset.pool = Some(pool.key());
+        pool.add_assets(1)?;
+
+        Ok(())
+    }
+
+    /// Turns the pool into a fleet pool, or extends one, with a tranche financing one of
+    /// its linked vehicles.
+    pub fn add_asset_tranche(
+        ctx: Context<AddAssetTranche>,
+        principal_allocation: u64,
+    ) -> Result<()> {
+        // Get accounts
+        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+        let tranche: &mut Account<'_, AssetTranche> = &mut ctx.accounts.tranche;
+        let vehicle_asset: &mut Account<'_, VehicleAsset> = &mut ctx.accounts.vehicle_asset;
+
+        // Validations
+        require_gt!(principal_allocation, 0, ErrorCode::InvalidTrancheAllocation);
+        pool.add_allocated(principal_allocation)?;
+        require_gte!(pool.raised, pool.allocated, ErrorCode::ExceedsPoolPrincipal);
+
+        tranche.set(
+            pool.key(),
+            pool.tranches,
+            vehicle_asset.key(),
+            principal_allocation,
+            ctx.bumps.tranche,
+        )?;
+        vehicle_asset.tranche = Some(pool.tranches);
+        pool.add_tranches(1)?;
 
         Ok(())
     }
@@ -194,15 +474,27 @@ pub mod octo_program {
         // Get the pool account
         let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
 
-        // Get deposit from shares
-        let deposit: u64 = pool.get_deposit_from_shares(shares);
+        // Get deposit from shares, priced at NAV once the pool has launched
+        let now: u64 = Clock::get()?.unix_timestamp as u64;
+        let deposit: u64 = shares
+            .checked_mul(pool.get_share_price(now)?)
+            .ok_or(ErrorCode::MathOverflow)?;
 
         // Validate the input
         // require!(!pool.investment_period_ended(), ErrorCode::StartDatePassed);
-        require!(pool.is_valid_deposit(deposit), ErrorCode::MinimumDeposit);
+        require_eq!(pool.share_classes, 0, ErrorCode::ShareClassRequired);
+        require!(
+            pool.eligibility.is_eligible(
+                ctx.accounts.buyer.key(),
+                pool.creator,
+                ctx.accounts.approver.is_some()
+            ),
+            ErrorCode::BuyerNotEligible
+        );
+        require_gte!(shares, 1_u64, ErrorCode::MinimumShares);
         require_gte!(
-            pool.shares,
-            pool.minted + shares,
+            pool.get_available_shares()?,
+            shares,
             ErrorCode::ExceedsAvailableShares
         );
 
@@ -223,14 +515,30 @@ pub mod octo_program {
             deposit,
         )?;
 
-        // Mint tokens to the creator token account
+        // Locked pools mint into the escrow and record the buyer's lockup
+        let to: AccountInfo<'_> = match pool.lockup {
+            Some(lockup) => {
+                let (Some(pool_escrow_account), Some(buyer_lockup)) = (
+                    &ctx.accounts.pool_escrow_account,
+                    &mut ctx.accounts.buyer_lockup,
+                ) else {
+                    return err!(ErrorCode::LockupAccountsMissing);
+                };
+                buyer_lockup.set(pool.key(), ctx.accounts.buyer.key(), ctx.bumps.buyer_lockup)?;
+                buyer_lockup.add_locked(&lockup, shares, pool.start_date.max(now), now)?;
+                pool_escrow_account.to_account_info()
+            }
+            None => ctx.accounts.buyer_mint_account.to_account_info(),
+        };
+
+        // Mint tokens to the buyer token account
         token::mint_to(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 token::MintTo {
                     authority: pool.to_account_info(),
                     mint: ctx.accounts.mint.to_account_info(),
-                    to: ctx.accounts.buyer_mint_account.to_account_info(),
+                    to,
                 },
                 signers_seeds,
             ),
@@ -238,853 +546,6793 @@ pub mod octo_program {
         )?;
 
         // Update the pool account
-        pool.add_minted(shares);
+        pool.add_minted(shares)?;
+        pool.add_raised(deposit)?;
+
+        // Record the purchase on the buyer's position
+        let buyer_position: &mut Account<'_, InvestorPosition> = &mut ctx.accounts.buyer_position;
+        buyer_position.set(
+            pool.key(),
+            ctx.accounts.buyer.key(),
+            ctx.bumps.buyer_position,
+        )?;
+        buyer_position.settle_rewards(pool.reward_per_share)?;
+        buyer_position.add_shares(shares, deposit, now)?;
+        emit!(SharesPurchased {
+            pool: pool.key(),
+            buyer: ctx.accounts.buyer.key(),
+            shares,
+            cost: deposit,
+        });
 
         Ok(())
     }
 
-    pub fn distribute(ctx: Context<Distribute>, amount: u64) -> Result<()> {
+    pub fn release_shares(ctx: Context<ReleaseShares>) -> Result<()> {
         // Get accounts
-        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
-        let distribution: &mut Account<'_, Distribution> = &mut ctx.accounts.distribution;
+        let pool: &Account<'_, Pool> = &ctx.accounts.pool;
+        let holder_lockup: &mut Account<'_, ShareLockup> = &mut ctx.accounts.holder_lockup;
+
+        // Get the shares unlocked by the schedule so far
+        let lockup: LockupConfig = pool.lockup.ok_or(ErrorCode::PoolNotLocked)?;
+        let now: u64 = Clock::get()?.unix_timestamp as u64;
+        let vested: u64 = match pool.status {
+            // A failed raise unlocks everything so it can be refunded
+            PoolStatus::Refunding => holder_lockup.locked,
+            _ => holder_lockup.get_vested_shares(&lockup, now),
+        };
+        let releasable: u64 = holder_lockup.get_releasable(vested);
 
-        // Initialize the distribution account
-        // distribution.set(
-        //     pool.key(),
-        //     ctx.accounts.distribution_authority.key(),
-        //     ctx.bumps.distribution,
-        // )?;
+        // Validations
+        require_gt!(releasable, 0, ErrorCode::NoSharesToRelease);
 
-        // Check if the distribution has enough USDC
-        // require!(
-        //     pool.investment_period_ended(),
-        //     ErrorCode::StartDateNotPassed
-        // );
-        // require_gte!(
-        //     ctx.accounts.distribution_usdc_account.amount,
-        //     (distribution.rewards - distribution.claimed) + amount,
-        //     ErrorCode::InsufficientDistributionUSDCBalance
-        // );
+        // Signer seeds
+        let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&pool_seeds[..]];
 
-        // Send the USDC to the distribution USDC account
+        // Transfer the unlocked shares to the holder
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.pool_escrow_account.to_account_info(),
+                    to: ctx.accounts.holder_mint_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                signers_seeds,
+            ),
+            releasable,
+        )?;
+
+        // Update the lockup account
+        holder_lockup.add_released(releasable)?;
+
+        Ok(())
+    }
+
+    pub fn list_shares(ctx: Context<ListShares>, shares: u64, price_per_share: u64) -> Result<()> {
+        // Get accounts
+        let listing: &mut Account<'_, Listing> = &mut ctx.accounts.listing;
+
+        // Validate the input
+        require_gte!(shares, 1_u64, ErrorCode::MinimumShares);
+        require_gt!(price_per_share, 0_u64, ErrorCode::InvalidListingPrice);
+        if listing.shares > 0 {
+            require_eq!(
+                listing.price_per_share,
+                price_per_share,
+                ErrorCode::ListingPriceChanged
+            );
+        }
+
+        // Move the shares into the listing escrow
         token::transfer(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
                 token::Transfer {
-                    from: ctx.accounts.signer_usdc_account.to_account_info(),
-                    to: ctx.accounts.distribution_usdc_account.to_account_info(),
-                    authority: ctx.accounts.signer.to_account_info(),
+                    from: ctx.accounts.seller_mint_account.to_account_info(),
+                    to: ctx.accounts.listing_mint_account.to_account_info(),
+                    authority: ctx.accounts.seller.to_account_info(),
                 },
             ),
-            amount,
+            shares,
         )?;
 
-        // Add rewards to the distribution
-        distribution.add_rewards(amount);
+        // Update the listing account
+        listing.set(
+            ctx.accounts.pool.key(),
+            ctx.accounts.seller.key(),
+            price_per_share,
+            ctx.bumps.listing,
+        )?;
+        listing.add_shares(shares)?;
+
+        // Open the seller's position so a fill never charges the buyer for it
+        let seller_position: &mut Account<'_, InvestorPosition> = &mut ctx.accounts.seller_position;
+        seller_position.set(
+            ctx.accounts.pool.key(),
+            ctx.accounts.seller.key(),
+            ctx.bumps.seller_position,
+        )?;
 
         Ok(())
     }
 
-    pub fn claim_rewards(ctx: Context<ClaimRewards>, rewards: u64) -> Result<()> {
+    pub fn cancel_listing(ctx: Context<CancelListing>) -> Result<()> {
         // Get accounts
-        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
-        let distribution: &mut Account<'_, Distribution> = &mut ctx.accounts.distribution;
+        let listing: &Account<'_, Listing> = &ctx.accounts.listing;
 
-        // Check if the distribution has enough USDC
+        // Signer seeds
+        let listing_seeds: &[&[u8]; 4] = &[
+            b"listing",
+            listing.pool.as_ref(),
+            listing.seller.as_ref(),
+            &[listing.bump],
+        ];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&listing_seeds[..]];
 
-        require_gte!(
-            ctx.accounts.distribution_usdc_account.amount,
-            distribution.rewards - distribution.claimed,
-            ErrorCode::InsufficientDistributionUSDCBalance
-        );
-        require_gte!(
-            ctx.accounts.distribution_usdc_account.amount,
-            rewards,
-            ErrorCode::InsufficientDistributionUSDCBalance
-        );
-        // require!(
-        //     pool.investment_period_ended(),
-        //     ErrorCode::StartDateNotPassed
-        // );
-        // require!(!pool.maturity_date_passed(), ErrorCode::MaturityDatePassed);
-        // Signer seeds
-        let distribution_seeds: &[&[u8]; 3] = &[
-            b"distribution",
-            distribution.pool.as_ref(),
-            &[distribution.bump],
-        ];
-        let signers_seeds: &[&[&[u8]]; 1] = &[&distribution_seeds[..]];
-
-        // Transfer the USDC to the holder account
+        // Return the unsold shares to the seller
         token::transfer(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 token::Transfer {
-                    from: ctx.accounts.distribution_usdc_account.to_account_info(),
-                    to: ctx.accounts.holder_usdc_account.to_account_info(),
-                    authority: distribution.to_account_info(),
+                    from: ctx.accounts.listing_mint_account.to_account_info(),
+                    to: ctx.accounts.seller_mint_account.to_account_info(),
+                    authority: listing.to_account_info(),
                 },
                 signers_seeds,
             ),
-            rewards,
+            ctx.accounts.listing_mint_account.amount,
         )?;
 
-        // Update the distribution account
-        distribution.add_claimed(rewards);
+        // Close the listing escrow
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::CloseAccount {
+                account: ctx.accounts.listing_mint_account.to_account_info(),
+                destination: ctx.accounts.seller.to_account_info(),
+                authority: listing.to_account_info(),
+            },
+            signers_seeds,
+        ))?;
 
         Ok(())
     }
 
-    pub fn withdraw_from_pool(ctx: Context<WithdrawFromPool>, shares: u64) -> Result<()> {
+    pub fn fill_listing(ctx: Context<FillListing>, shares: u64) -> Result<()> {
         // Get accounts
-        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+        let listing: &mut Account<'_, Listing> = &mut ctx.accounts.listing;
 
-        let pool_usdc_balance: u64 = ctx.accounts.pool_usdc_account.amount;
-        let amount: u64 = pool.get_deposit_from_shares(shares);
+        // Get the price of the shares
+        let price: u64 = listing.get_price(shares)?;
 
-        // Validations
-        // require_eq!(pool.shares, pool.minted, ErrorCode::SeedRoundsNotCompleted);
-        // require!(
-        //     pool.investment_period_ended(),
-        //     ErrorCode::StartDateNotPassed
-        // );
-        require_gte!(
-            pool_usdc_balance,
-            amount,
-            ErrorCode::InsufficientPoolUSDCBalance
+        // Validate the input
+        let pool: &Account<'_, Pool> = &ctx.accounts.pool;
+        require!(
+            pool.eligibility.is_eligible(
+                ctx.accounts.buyer.key(),
+                pool.creator,
+                ctx.accounts.approver.is_some()
+            ),
+            ErrorCode::BuyerNotEligible
         );
+        require_gte!(shares, 1_u64, ErrorCode::MinimumShares);
+        require_gte!(listing.shares, shares, ErrorCode::ExceedsListedShares);
 
         // Signer seeds
-        let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
-        let signers_seeds: &[&[&[u8]]; 1] = &[&pool_seeds[..]];
+        let listing_seeds: &[&[u8]; 4] = &[
+            b"listing",
+            listing.pool.as_ref(),
+            listing.seller.as_ref(),
+            &[listing.bump],
+        ];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&listing_seeds[..]];
 
-        // Transfer the USDC
+        // Pay the seller
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.buyer_usdc_account.to_account_info(),
+                    to: ctx.accounts.seller_usdc_account.to_account_info(),
+                    authority: ctx.accounts.buyer.to_account_info(),
+                },
+            ),
+            price,
+        )?;
+
+        // Deliver the shares to the buyer
         token::transfer(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 token::Transfer {
-                    from: ctx.accounts.pool_usdc_account.to_account_info(),
-                    to: ctx.accounts.to_usdc_account.to_account_info(),
-                    authority: pool.to_account_info(),
+                    from: ctx.accounts.listing_mint_account.to_account_info(),
+                    to: ctx.accounts.buyer_mint_account.to_account_info(),
+                    authority: listing.to_account_info(),
                 },
                 signers_seeds,
             ),
-            amount,
+            shares,
+        )?;
+
+        // Update the listing account
+        listing.sub_shares(shares)?;
+
+        // The seller realizes the sale and the buyer takes the shares at the price paid,
+        // along with the rewards still owed on them
+        let reward_per_share: u128 = ctx.accounts.pool.reward_per_share;
+        let seller_position: &mut Account<'_, InvestorPosition> = &mut ctx.accounts.seller_position;
+        seller_position.settle_rewards(reward_per_share)?;
+        let rewards_owed: u64 = seller_position.take_rewards_owed(shares)?;
+        let cost_basis: u64 = seller_position.sub_shares(shares)?;
+        seller_position.add_realized(cost_basis, price)?;
+        let buyer_position: &mut Account<'_, InvestorPosition> = &mut ctx.accounts.buyer_position;
+        buyer_position.set(
+            listing.pool,
+            ctx.accounts.buyer.key(),
+            ctx.bumps.buyer_position,
         )?;
+        buyer_position.settle_rewards(reward_per_share)?;
+        buyer_position.add_shares(shares, price, Clock::get()?.unix_timestamp as u64)?;
+        buyer_position.add_rewards_owed(rewards_owed)?;
+
+        emit!(SharesTraded {
+            pool: listing.pool,
+            seller: listing.seller,
+            buyer: ctx.accounts.buyer.key(),
+            shares,
+            price_per_share: listing.price_per_share,
+        });
 
         Ok(())
     }
 
-    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+    /// Moves shares to another holder. The cost basis and rewards owed move with them, so a
+    /// transfer is neither a sale for the sender nor a purchase for the recipient.
+    pub fn transfer_shares(ctx: Context<TransferShares>, shares: u64) -> Result<()> {
+        // Get accounts
+        let pool: &Account<'_, Pool> = &ctx.accounts.pool;
+
+        // Validate the input
+        require!(
+            pool.eligibility.is_eligible(
+                ctx.accounts.recipient.key(),
+                pool.creator,
+                ctx.accounts.approver.is_some()
+            ),
+            ErrorCode::BuyerNotEligible
+        );
+        require_gte!(shares, 1_u64, ErrorCode::MinimumShares);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.holder_mint_account.to_account_info(),
+                    to: ctx.accounts.recipient_mint_account.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
+
+        // Update both positions
+        let holder_position: &mut Account<'_, InvestorPosition> = &mut ctx.accounts.holder_position;
+        holder_position.set(
+            pool.key(),
+            ctx.accounts.holder.key(),
+            ctx.bumps.holder_position,
+        )?;
+        holder_position.settle_rewards(pool.reward_per_share)?;
+        let rewards_owed: u64 = holder_position.take_rewards_owed(shares)?;
+        let cost_basis: u64 = holder_position.sub_shares(shares)?;
+        let recipient_position: &mut Account<'_, InvestorPosition> =
+            &mut ctx.accounts.recipient_position;
+        recipient_position.set(
+            pool.key(),
+            ctx.accounts.recipient.key(),
+            ctx.bumps.recipient_position,
+        )?;
+        recipient_position.settle_rewards(pool.reward_per_share)?;
+        recipient_position.add_shares(shares, cost_basis, Clock::get()?.unix_timestamp as u64)?;
+        recipient_position.add_rewards_owed(rewards_owed)?;
+        emit!(SharesTransferred {
+            pool: pool.key(),
+            from: ctx.accounts.holder.key(),
+            to: ctx.accounts.recipient.key(),
+            shares,
+        });
+
+        Ok(())
+    }
+
+    /// Opens another distribution next to the pool's primary one, e.g. to pay principal or
+    /// recoveries apart from interest. Ids follow on from the pool's last distribution.
+    pub fn create_distribution(ctx: Context<CreateDistribution>) -> Result<()> {
+        // Get accounts
         let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
         let distribution: &mut Account<'_, Distribution> = &mut ctx.accounts.distribution;
 
-        // Initialize the distribution account
+        pool.add_distribution()?;
         distribution.set(
             pool.key(),
             ctx.accounts.distribution_authority.key(),
+            pool.distributions,
             ctx.bumps.distribution,
         )?;
-        pool.closed = true;
 
         Ok(())
     }
 
-    // pub fn claim_deposit(ctx: Context<ClaimDeposit>) -> Result<()> {
-    //     // Get the pool account
-    //     let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+    /// Hands a distribution over to a new authority, which co-signs so that claims can't be
+    /// left without a signer. The pool authority may rotate a lost authority.
+    pub fn set_distribution_authority(ctx: Context<SetDistributionAuthority>) -> Result<()> {
+        ctx.accounts
+            .distribution
+            .set_authority(ctx.accounts.new_authority.key());
 
-    //     // Signer seeds
-    //     let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
-    //     let signers_seeds: &[&[&[u8]]; 1] = &[&pool_seeds[..]];
+        Ok(())
+    }
 
-    //     // Transfer the USDC to the user account
-    //     token::transfer(
-    //         CpiContext::new_with_signer(
-    //             ctx.accounts.token_program.to_account_info(),
-    //             token::Transfer {
-    //                 from: ctx.accounts.pool_usdc_account.to_account_info(),
-    //                 to: ctx.accounts.holder_usdc_account.to_account_info(),
-    //                 authority: pool.to_account_info(),
-    //             },
-    //             signers_seeds,
-    //         ),
-    //         pool.get_deposit_from_shares(ctx.accounts.holder_mint_account.amount),
-    //     )?;
+    /// Closes a fully claimed distribution other than the primary one, which closes with
+    /// the pool's accounts.
+    pub fn close_distribution(ctx: Context<CloseDistribution>) -> Result<()> {
+        // Get accounts
+        let distribution: &Account<'_, Distribution> = &ctx.accounts.distribution;
 
-    //     // Burn tokens from the holder token account
-    //     token::burn(
-    //         CpiContext::new(
-    //             ctx.accounts.token_program.to_account_info(),
-    //             token::Burn {
-    //                 authority: ctx.accounts.holder.to_account_info(),
-    //                 mint: ctx.accounts.mint.to_account_info(),
-    //                 from: ctx.accounts.holder_mint_account.to_account_info(),
-    //             },
-    //         ),
-    //         ctx.accounts.holder_mint_account.amount,
-    //     )?;
+        // Signer seeds, closable distributions always have an id
+        let distribution_seeds: &[&[u8]; 4] = &[
+            b"distribution",
+            distribution.pool.as_ref(),
+            &[distribution.id],
+            &[distribution.bump],
+        ];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&distribution_seeds[..]];
 
-    //     Ok(())
-    // }
+        // Return the rent of the emptied USDC account to the authority
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::CloseAccount {
+                account: ctx.accounts.distribution_usdc_account.to_account_info(),
+                destination: ctx.accounts.authority.to_account_info(),
+                authority: distribution.to_account_info(),
+            },
+            signers_seeds,
+        ))?;
+
+        ctx.accounts.pool.sub_open_distributions(1)?;
 
-    pub fn close_pool_accounts(_ctx: Context<ClosePoolAccounts>) -> Result<()> {
         Ok(())
     }
-}
 
-#[constant]
-pub const PROJECT_PDA: &[u8] = b"BLOCKRIDE_SYSTEM";
-#[derive(Accounts)]
-pub struct InitializeProgram<'info> {
-    #[account(mut)]
-    pub creator: Signer<'info>,
+    pub fn distribute(ctx: Context<Distribute>, amount: u64) -> Result<()> {
+        // Get accounts
+        let distribution: &mut Account<'_, Distribution> = &mut ctx.accounts.distribution;
 
-    #[account(init,
-     payer = creator,
-     seeds=[PROJECT_PDA],bump,
-      space = 8 + std::mem::size_of::<ProjectData>())]
-    pub project_pda: Box<Account<'info, ProjectData>>,
-    pub system_program: Program<'info, System>,
-}
+        // Initialize the distribution account
+        // distribution.set(
+        //     pool.key(),
+        //     ctx.accounts.distribution_authority.key(),
+        //     ctx.bumps.distribution,
+        // )?;
 
-#[account]
-pub struct ProjectData {
-    pub grand_authority: Pubkey,
-}
+        // Check if the distribution has enough USDC
+        // require!(
+        //     pool.investment_period_ended(),
+        //     ErrorCode::StartDateNotPassed
+        // );
+        // require_gte!(
+        //     ctx.accounts.distribution_usdc_account.amount,
+        //     (distribution.rewards - distribution.claimed) + amount,
+        //     ErrorCode::InsufficientDistributionUSDCBalance
+        // );
 
-#[derive(Accounts)]
-pub struct UpdateProgramGrandAuthority<'info> {
-    #[account(mut,address=project_pda.grand_authority @ ErrorCode::SignerNotAuthorized)]
-    pub grand_authority: Signer<'info>,
+        // Validations
+        require_reserve_funded(&ctx.accounts.reserve)?;
+        if ctx.accounts.pool.is_fleet() {
+            let tranche: &mut Account<'_, AssetTranche> = ctx
+                .accounts
+                .tranche
+                .as_mut()
+                .ok_or(ErrorCode::TrancheRequired)?;
+            tranche.add_repaid(amount)?;
+        }
 
-    #[account(mut,
-     seeds=[PROJECT_PDA],bump)]
-    pub project_pda: Box<Account<'info, ProjectData>>,
-    pub system_program: Program<'info, System>,
-}
+        // Send the USDC to the distribution USDC account
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.signer_usdc_account.to_account_info(),
+                    to: ctx.accounts.distribution_usdc_account.to_account_info(),
+                    authority: ctx.accounts.signer.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
 
-#[derive(Accounts)]
-#[instruction(creator: Pubkey)]
-pub struct AddCreator<'info> {
-    #[account(mut,address = project_pda.grand_authority @ ErrorCode::SignerNotAuthorized)]
-    pub grand_authority: Signer<'info>,
+        // Add rewards to the distribution
+        distribution.add_rewards(amount)?;
+        ctx.accounts.pool.add_rewards(amount)?;
 
-    #[account(mut,
-     seeds=[PROJECT_PDA],bump)]
-    pub project_pda: Box<Account<'info, ProjectData>>,
+        Ok(())
+    }
 
-    #[account(init,
-     payer = grand_authority,
-     seeds=[PROJECT_PDA,creator.as_ref()],bump,
-      space = 8 + std::mem::size_of::<PoolCreatorData>())]
-    pub pool_creator_data: Box<Account<'info, PoolCreatorData>>,
-    pub system_program: Program<'info, System>,
-}
+    pub fn claim_rewards(ctx: Context<ClaimRewards>, rewards: u64) -> Result<()> {
+        // Get accounts
+        let pool: &Account<'_, Pool> = &ctx.accounts.pool;
+        let distribution: &mut Account<'_, Distribution> = &mut ctx.accounts.distribution;
+        let holder_position: &mut Account<'_, InvestorPosition> = &mut ctx.accounts.holder_position;
 
-#[account]
-pub struct PoolCreatorData {
-    pub creator: Pubkey,
-    pub can_create: bool,
-}
+        // Pools with share classes pay their holders through the classes
+        require_eq!(pool.share_classes, 0, ErrorCode::ShareClassRequired);
 
-#[derive(Accounts)]
-// #[instruction(poolcreator: Pubkey)]
-pub struct UpdatePoolCreator<'info> {
-    #[account(mut,
-     seeds=[PROJECT_PDA,pool_creator_data.creator.as_ref()],bump)]
-    pub pool_creator_data: Box<Account<'info, PoolCreatorData>>,
+        // The holder claims no more than their shares have earned
+        holder_position.set(
+            distribution.pool,
+            ctx.accounts.holder.key(),
+            ctx.bumps.holder_position,
+        )?;
+        holder_position.settle_rewards(pool.reward_per_share)?;
+        holder_position.claim_rewards(rewards)?;
 
-    #[account(mut, address = project_pda.grand_authority @ ErrorCode::SignerNotAuthorized)]
-    pub creator: Signer<'info>,
-    #[account(mut,
-     seeds=[PROJECT_PDA],bump)]
-    pub project_pda: Box<Account<'info, ProjectData>>,
-    pub system_program: Program<'info, System>,
-}
+        // Check if the distribution has enough USDC
+        require_gte!(
+            ctx.accounts.distribution_usdc_account.amount,
+            distribution.get_unclaimed_rewards()?,
+            ErrorCode::InsufficientDistributionUSDCBalance
+        );
+        require_gte!(
+            ctx.accounts.distribution_usdc_account.amount,
+            rewards,
+            ErrorCode::InsufficientDistributionUSDCBalance
+        );
+        // require!(
+        //     pool.investment_period_ended(),
+        //     ErrorCode::StartDateNotPassed
+        // );
+        // require!(!pool.maturity_date_passed(), ErrorCode::MaturityDatePassed);
+        // Signer seeds, only distributions after the primary one have an id in them
+        let distribution_id: &[u8] = &[distribution.id];
+        let distribution_bump: &[u8] = &[distribution.bump];
+        let distribution_seeds: Vec<&[u8]> = match distribution.id {
+            0 => vec![
+                b"distribution",
+                distribution.pool.as_ref(),
+                distribution_bump,
+            ],
+            _ => vec![
+                b"distribution",
+                distribution.pool.as_ref(),
+                distribution_id,
+                distribution_bump,
+            ],
+        };
+        let signers_seeds: &[&[&[u8]]; 1] = &[&distribution_seeds[..]];
 
-#[derive(Accounts)]
-#[instruction(reference:Pubkey)]
-pub struct CreatePool<'info> {
-    // #[account(mut,
-    //  seeds=[PROJECT_PDA,pool_creator_data.creator.as_ref()],bump)]
-    // pub pool_creator_data: Account<'info, PoolCreatorData>,
-    #[account(mut, 
-    //     address = pool_creator_data.creator @ ErrorCode::CreatorNotAuthorized,
-    // constraint = pool_creator_data.can_create == true @ ErrorCode::CreatorNotAuthorized
- )]
+        // Transfer the USDC to the holder account
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.distribution_usdc_account.to_account_info(),
+                    to: ctx.accounts.holder_usdc_account.to_account_info(),
+                    authority: distribution.to_account_info(),
+                },
+                signers_seeds,
+            ),
+            rewards,
+        )?;
+
+        // Update the distribution account
+        distribution.add_claimed(rewards)?;
+
+        Ok(())
+    }
+
+    /// Adds the pool's next share class. Class 0 is the most senior; each later class is
+    /// paid after the ones before it and the last class takes any residual.
+    pub fn create_share_class(
+        ctx: Context<CreateShareClass>,
+        shares: u64,
+        apy: u8,
+        eligibility: BuyerEligibility,
+    ) -> Result<()> {
+        // Get accounts
+        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+        let share_class: &mut Account<'_, ShareClass> = &mut ctx.accounts.share_class;
+
+        // Validations
+        require_gte!(shares, 1_u64, ErrorCode::MinimumShares);
+        pool.add_class_shares(shares)?;
+        require_gte!(
+            pool.get_available_shares()?,
+            pool.class_shares,
+            ErrorCode::ExceedsAvailableShares
+        );
+
+        share_class.set(
+            pool.key(),
+            pool.share_classes,
+            ctx.accounts.mint.key(),
+            shares,
+            apy,
+            eligibility,
+            ctx.bumps.share_class,
+        )?;
+        pool.add_share_classes(1)?;
+
+        Ok(())
+    }
+
+    pub fn buy_class_shares(ctx: Context<BuyClassShares>, shares: u64) -> Result<()> {
+        // Get accounts
+        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+        let share_class: &mut Account<'_, ShareClass> = &mut ctx.accounts.share_class;
+
+        // Get deposit from shares, priced at NAV once the pool has launched
+        let now: u64 = Clock::get()?.unix_timestamp as u64;
+        let deposit: u64 = shares
+            .checked_mul(pool.get_share_price(now)?)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // Validate the input
+        require!(
+            share_class.eligibility.is_eligible(
+                ctx.accounts.buyer.key(),
+                pool.creator,
+                ctx.accounts.approver.is_some()
+            ),
+            ErrorCode::BuyerNotEligible
+        );
+        require_gte!(shares, 1_u64, ErrorCode::MinimumShares);
+        require_gte!(
+            share_class.get_available_shares()?,
+            shares,
+            ErrorCode::ExceedsAvailableShares
+        );
+
+        // Signer seeds
+        let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&pool_seeds[..]];
+
+        // Send the USDC to the pool account
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.buyer_usdc_account.to_account_info(),
+                    to: ctx.accounts.pool_usdc_account.to_account_info(),
+                    authority: ctx.accounts.buyer.to_account_info(),
+                },
+            ),
+            deposit,
+        )?;
+
+        // Mint class tokens to the buyer token account, which stays frozen so that class
+        // rewards follow the class position
+        if ctx.accounts.buyer_mint_account.is_frozen() {
+            token::thaw_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::ThawAccount {
+                    account: ctx.accounts.buyer_mint_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                signers_seeds,
+            ))?;
+        }
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    authority: pool.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.buyer_mint_account.to_account_info(),
+                },
+                signers_seeds,
+            ),
+            shares,
+        )?;
+        token::freeze_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::FreezeAccount {
+                account: ctx.accounts.buyer_mint_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: pool.to_account_info(),
+            },
+            signers_seeds,
+        ))?;
+
+        // Update the class and pool accounts
+        share_class.add_minted(shares)?;
+        pool.add_minted(shares)?;
+        pool.add_raised(deposit)?;
+
+        // Record the purchase on the buyer's class position
+        let class_position: &mut Account<'_, ClassPosition> = &mut ctx.accounts.class_position;
+        class_position.set(
+            share_class.key(),
+            ctx.accounts.buyer.key(),
+            ctx.bumps.class_position,
+        );
+        class_position.settle_rewards(share_class.reward_per_share)?;
+        class_position.add_shares(shares)?;
+
+        // Record the purchase on the buyer's position
+        let buyer_position: &mut Account<'_, InvestorPosition> = &mut ctx.accounts.buyer_position;
+        buyer_position.set(
+            pool.key(),
+            ctx.accounts.buyer.key(),
+            ctx.bumps.buyer_position,
+        )?;
+        buyer_position.add_shares(shares, deposit, now)?;
+        emit!(SharesPurchased {
+            pool: pool.key(),
+            buyer: ctx.accounts.buyer.key(),
+            shares,
+            cost: deposit,
+        });
+
+        Ok(())
+    }
+
+    /// Runs newly distributed rewards down the class waterfall. Every share class of the
+    /// pool must be passed as a remaining account, in class order.
+    pub fn allocate_class_rewards<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AllocateClassRewards<'info>>,
+    ) -> Result<()> {
+        // Get accounts
+        let pool: &Account<'_, Pool> = &ctx.accounts.pool;
+        let distribution: &mut Account<'_, Distribution> = &mut ctx.accounts.distribution;
+        let unallocated: u64 = distribution.get_unallocated_rewards()?;
+
+        // Validations
+        require_gt!(pool.share_classes, 0, ErrorCode::InvalidShareClass);
+        require_eq!(
+            ctx.remaining_accounts.len(),
+            pool.share_classes as usize,
+            ErrorCode::InvalidShareClass
+        );
+
+        // Senior classes are owed their principal and coupon first
+        let mut remaining: u64 = unallocated;
+        for (class_id, info) in ctx.remaining_accounts.iter().enumerate() {
+            let mut share_class: Account<'info, ShareClass> = Account::try_from(info)?;
+            require_keys_eq!(share_class.pool, pool.key(), ErrorCode::InvalidShareClass);
+            require_eq!(
+                share_class.class_id as usize,
+                class_id,
+                ErrorCode::InvalidShareClass
+            );
+
+            let junior: bool = class_id + 1 == ctx.remaining_accounts.len();
+            let allocation: u64 = match junior {
+                true => remaining,
+                false => remaining.min(share_class.get_outstanding(pool)?),
+            };
+            share_class.add_rewards(allocation)?;
+            share_class.exit(&crate::ID)?;
+            remaining = remaining
+                .checked_sub(allocation)
+                .ok_or(ErrorCode::MathUnderflow)?;
+        }
+
+        distribution.add_allocated(unallocated)?;
+
+        Ok(())
+    }
+
+    pub fn claim_class_rewards(ctx: Context<ClaimClassRewards>, rewards: u64) -> Result<()> {
+        // Get accounts
+        let distribution: &mut Account<'_, Distribution> = &mut ctx.accounts.distribution;
+        let share_class: &mut Account<'_, ShareClass> = &mut ctx.accounts.share_class;
+
+        let class_position: &mut Account<'_, ClassPosition> = &mut ctx.accounts.class_position;
+
+        // Validations
+        require_gte!(
+            share_class.get_unclaimed_rewards()?,
+            rewards,
+            ErrorCode::ExceedsClassRewards
+        );
+        class_position.settle_rewards(share_class.reward_per_share)?;
+        class_position.claim_rewards(rewards)?;
+        require_gte!(
+            ctx.accounts.distribution_usdc_account.amount,
+            rewards,
+            ErrorCode::InsufficientDistributionUSDCBalance
+        );
+
+        // Signer seeds
+        let distribution_seeds: &[&[u8]; 3] = &[
+            b"distribution",
+            distribution.pool.as_ref(),
+            &[distribution.bump],
+        ];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&distribution_seeds[..]];
+
+        // Transfer the USDC to the holder account
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.distribution_usdc_account.to_account_info(),
+                    to: ctx.accounts.holder_usdc_account.to_account_info(),
+                    authority: distribution.to_account_info(),
+                },
+                signers_seeds,
+            ),
+            rewards,
+        )?;
+
+        // Update the class, distribution and position accounts
+        share_class.add_claimed(rewards)?;
+        distribution.add_claimed(rewards)?;
+        let holder_position: &mut Account<'_, InvestorPosition> = &mut ctx.accounts.holder_position;
+        holder_position.set(
+            distribution.pool,
+            ctx.accounts.holder.key(),
+            ctx.bumps.holder_position,
+        )?;
+        holder_position.add_rewards_claimed(rewards)?;
+
+        Ok(())
+    }
+
+    pub fn configure_revenue(
+        ctx: Context<ConfigureRevenue>,
+        operator: Pubkey,
+        driver_bps: u16,
+        maintenance_bps: u16,
+    ) -> Result<()> {
+        // Get accounts
+        let revenue_config: &mut Account<'_, RevenueConfig> = &mut ctx.accounts.revenue_config;
+
+        revenue_config.set(
+            ctx.accounts.pool.key(),
+            operator,
+            driver_bps,
+            maintenance_bps,
+            ctx.bumps.revenue_config,
+        )?;
+
+        // Validate the input
+        require!(
+            revenue_config.is_valid_split(
+                ctx.accounts
+                    .pool
+                    .get_protocol_fee_bps(ctx.accounts.project_pda.protocol_fee_bps)
+            ),
+            ErrorCode::InvalidRevenueSplit
+        );
+
+        Ok(())
+    }
+
+    pub fn report_revenue(ctx: Context<ReportRevenue>, amount: u64) -> Result<()> {
+        // Get accounts
+        let revenue_config: &mut Account<'_, RevenueConfig> = &mut ctx.accounts.revenue_config;
+        let protocol_fee_bps: u16 = ctx
+            .accounts
+            .pool
+            .get_protocol_fee_bps(ctx.accounts.project_pda.protocol_fee_bps);
+
+        // Validations
+        require_gt!(amount, 0, ErrorCode::InvalidRevenueAmount);
+        require!(
+            revenue_config.is_valid_split(protocol_fee_bps),
+            ErrorCode::InvalidRevenueSplit
+        );
+        require!(
+            ctx.accounts.reserve.is_some() || !ctx.accounts.pool.reserve_configured,
+            ErrorCode::ReserveRequired
+        );
+
+        // Split the revenue down the waterfall
+        let split: RevenueSplit = revenue_config.split(amount, protocol_fee_bps)?;
+        for (bucket, share) in [
+            (&ctx.accounts.driver_bucket, split.driver),
+            (&ctx.accounts.maintenance_bucket, split.maintenance),
+            (&ctx.accounts.protocol_bucket, split.protocol),
+            (&ctx.accounts.investor_bucket, split.investors),
+        ] {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.operator_usdc_account.to_account_info(),
+                        to: bucket.to_account_info(),
+                        authority: ctx.accounts.operator.to_account_info(),
+                    },
+                ),
+                share,
+            )?;
+        }
+
+        // Update the revenue config and the reserve
+        revenue_config.add_reported(amount)?;
+        if let Some(reserve) = ctx.accounts.reserve.as_mut() {
+            reserve.add_funded(split.maintenance)?;
+        }
+
+        emit!(RevenueReported {
+            pool: ctx.accounts.pool.key(),
+            operator: ctx.accounts.operator.key(),
+            gross: amount,
+            driver: split.driver,
+            maintenance: split.maintenance,
+            protocol: split.protocol,
+            investors: split.investors,
+        });
+
+        Ok(())
+    }
+
+    /// Pays out of the driver or protocol bucket. The investor bucket only leaves through
+    /// `distribute_revenue`, and the maintenance bucket, which holds the pool reserve,
+    /// through `spend_reserve`.
+    pub fn withdraw_revenue(
+        ctx: Context<WithdrawRevenue>,
+        bucket: RevenueBucket,
+        amount: u64,
+    ) -> Result<()> {
+        // Get accounts
+        let pool: &Account<'_, Pool> = &ctx.accounts.pool;
+
+        // Validations
+        let owner: Pubkey = match bucket {
+            RevenueBucket::Driver => ctx.accounts.revenue_config.operator,
+            RevenueBucket::Protocol => ctx.accounts.project_pda.grand_authority,
+            RevenueBucket::Maintenance | RevenueBucket::Investors => {
+                return err!(ErrorCode::InvalidRevenueBucket)
+            }
+        };
+        require_keys_eq!(
+            ctx.accounts.signer.key(),
+            owner,
+            ErrorCode::SignerNotAuthorized
+        );
+
+        // Signer seeds
+        let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&pool_seeds[..]];
+
+        // Transfer the USDC
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.bucket_account.to_account_info(),
+                    to: ctx.accounts.to_usdc_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                signers_seeds,
+            ),
+            amount,
+        )?;
+
+        Ok(())
+    }
+
+    /// Moves everything in the investor bucket into the pool's distribution.
+    pub fn distribute_revenue(ctx: Context<DistributeRevenue>) -> Result<()> {
+        // Get accounts
+        let pool: &Account<'_, Pool> = &ctx.accounts.pool;
+        let distribution: &mut Account<'_, Distribution> = &mut ctx.accounts.distribution;
+        let amount: u64 = ctx.accounts.investor_bucket.amount;
+
+        // Validations
+        require!(
+            ctx.accounts.signer.key() == ctx.accounts.revenue_config.operator
+                || ctx.accounts.signer.key() == pool.authority,
+            ErrorCode::SignerNotAuthorized
+        );
+        require_gt!(amount, 0, ErrorCode::InvalidRevenueAmount);
+        require_reserve_funded(&ctx.accounts.reserve)?;
+        if pool.is_fleet() {
+            let tranche: &mut Account<'_, AssetTranche> = ctx
+                .accounts
+                .tranche
+                .as_mut()
+                .ok_or(ErrorCode::TrancheRequired)?;
+            tranche.add_repaid(amount)?;
+        }
+
+        // Signer seeds
+        let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&pool_seeds[..]];
+
+        // Send the USDC to the distribution USDC account
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.investor_bucket.to_account_info(),
+                    to: ctx.accounts.distribution_usdc_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                signers_seeds,
+            ),
+            amount,
+        )?;
+
+        // Add rewards to the distribution
+        distribution.add_rewards(amount)?;
+        ctx.accounts.pool.add_rewards(amount)?;
+
+        Ok(())
+    }
+
+    pub fn configure_reserve(
+        ctx: Context<ConfigureReserve>,
+        principal_bps: u16,
+        target: u64,
+    ) -> Result<()> {
+        // Validate the input
+        require_gte!(
+            RevenueConfig::MAX_BPS,
+            principal_bps,
+            ErrorCode::InvalidReserveShare
+        );
+
+        // Revenue reported before the reserve was configured is already in its vault
+        let reserve: &mut Account<'_, PoolReserve> = &mut ctx.accounts.reserve;
+        if reserve.version == 0 {
+            reserve.add_funded(ctx.accounts.reserve_vault.amount)?;
+        }
+        reserve.configure(
+            ctx.accounts.pool.key(),
+            principal_bps,
+            target,
+            ctx.bumps.reserve,
+        )?;
+        ctx.accounts.pool.reserve_configured = true;
+
+        Ok(())
+    }
+
+    /// Pays a vehicle expense out of the reserve, keeping a record of it and its receipt.
+    pub fn spend_reserve(
+        ctx: Context<SpendReserve>,
+        amount: u64,
+        category: ExpenseCategory,
+        receipt_hash: [u8; 32],
+    ) -> Result<()> {
+        // Get accounts
+        let pool: &Account<'_, Pool> = &ctx.accounts.pool;
+        let reserve: &mut Account<'_, PoolReserve> = &mut ctx.accounts.reserve;
+        let now: u64 = Clock::get()?.unix_timestamp as u64;
+
+        // Validations
+        require_gte!(
+            reserve.get_balance()?,
+            amount,
+            ErrorCode::InsufficientReserveBalance
+        );
+
+        // Signer seeds
+        let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&pool_seeds[..]];
+
+        // Transfer the USDC
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.reserve_vault.to_account_info(),
+                    to: ctx.accounts.to_usdc_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                signers_seeds,
+            ),
+            amount,
+        )?;
+
+        // Record the expense
+        ctx.accounts.expense.set(
+            reserve.key(),
+            reserve.expenses,
+            ReceiptedExpense {
+                category,
+                amount,
+                recipient: ctx.accounts.to_usdc_account.key(),
+                receipt_hash,
+            },
+            now,
+            ctx.bumps.expense,
+        )?;
+        reserve.add_spent(amount)?;
+
+        Ok(())
+    }
+
+    pub fn set_pool_valuator(ctx: Context<SetPoolValuator>, valuator: Pubkey) -> Result<()> {
+        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+        pool.valuator = valuator;
+        Ok(())
+    }
+
+    pub fn update_nav(ctx: Context<UpdateNav>, nav_per_share: u64) -> Result<()> {
+        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+        let now: u64 = Clock::get()?.unix_timestamp as u64;
+        pool.update_nav(nav_per_share, now)
+    }
+
+    pub fn get_nav(ctx: Context<GetNav>) -> Result<NavQuote> {
+        let pool: &Account<'_, Pool> = &ctx.accounts.pool;
+        let now: u64 = Clock::get()?.unix_timestamp as u64;
+
+        Ok(NavQuote {
+            nav_per_share: pool.nav_per_share,
+            nav_updated_at: pool.nav_updated_at,
+            stale: pool.is_nav_stale(now),
+        })
+    }
+
+    pub fn get_pool_summary(ctx: Context<GetPoolSummary>) -> Result<PoolSummary> {
+        let pool: &Account<'_, Pool> = &ctx.accounts.pool;
+        let now: u64 = Clock::get()?.unix_timestamp as u64;
+        let (rewards, claimed): (u64, u64) = match &ctx.accounts.distribution {
+            Some(distribution) => (distribution.rewards, distribution.claimed),
+            None => (0, 0),
+        };
+
+        Ok(PoolSummary {
+            authority: pool.authority,
+            mint: pool.mint,
+            shares: pool.shares,
+            minted: pool.minted,
+            available: pool.get_available_shares()?,
+            status: pool.status,
+            start_date: pool.start_date,
+            maturity_date: pool.maturity_date,
+            apy: pool.apy,
+            nav_per_share: pool.nav_per_share,
+            nav_stale: pool.is_nav_stale(now),
+            rewards,
+            claimed,
+            tranches: pool.tranches,
+        })
+    }
+
+    pub fn get_tranche_summary(ctx: Context<GetTrancheSummary>) -> Result<TrancheSummary> {
+        let tranche: &Account<'_, AssetTranche> = &ctx.accounts.tranche;
+
+        Ok(TrancheSummary {
+            index: tranche.index,
+            vehicle_asset: tranche.vehicle_asset,
+            principal_allocation: tranche.principal_allocation,
+            deployed: tranche.deployed,
+            repaid: tranche.repaid,
+            undeployed: tranche.get_undeployed()?,
+        })
+    }
+
+    // The holder is only used to derive the holder's position
+    #[allow(unused_variables)]
+    pub fn get_claimable_rewards(ctx: Context<GetClaimableRewards>, holder: Pubkey) -> Result<u64> {
+        let pool: &Account<'_, Pool> = &ctx.accounts.pool;
+        let distribution: &Account<'_, Distribution> = &ctx.accounts.distribution;
+
+        let pending: u64 = match load_if_created::<InvestorPosition>(&ctx.accounts.holder_position)?
+        {
+            Some(position) => position.get_pending_rewards(pool.reward_per_share)?,
+            None => 0,
+        };
+        distribution.get_claimable_rewards(pending)
+    }
+
+    // The holder is only used to derive the holder's class accounts
+    #[allow(unused_variables)]
+    pub fn get_claimable_class_rewards(
+        ctx: Context<GetClaimableClassRewards>,
+        holder: Pubkey,
+    ) -> Result<u64> {
+        let share_class: &Account<'_, ShareClass> = &ctx.accounts.share_class;
+
+        let pending: u64 = match load_if_created::<ClassPosition>(&ctx.accounts.class_position)? {
+            Some(class_position) => {
+                class_position.get_pending_rewards(share_class.reward_per_share)?
+            }
+            None => 0,
+        };
+        Ok(pending.min(share_class.get_unclaimed_rewards()?))
+    }
+
+    // The holder is only used to derive the holder's share account
+    #[allow(unused_variables)]
+    pub fn get_redeemable_principal(
+        ctx: Context<GetRedeemablePrincipal>,
+        holder: Pubkey,
+    ) -> Result<u64> {
+        let pool: &Account<'_, Pool> = &ctx.accounts.pool;
+
+        let shares: u64 = get_held_shares(
+            &ctx.accounts.holder_mint_account,
+            &ctx.accounts.holder_lockup,
+            &ctx.accounts.holder_listing,
+        )?;
+        pool.get_shares_value(shares, Clock::get()?.unix_timestamp as u64)
+    }
+
+    pub fn quote_buy(ctx: Context<QuoteBuy>, shares: u64) -> Result<BuyQuote> {
+        let pool: &Account<'_, Pool> = &ctx.accounts.pool;
+        let now: u64 = Clock::get()?.unix_timestamp as u64;
+        let price_per_share: u64 = pool.get_share_price(now)?;
+
+        Ok(BuyQuote {
+            shares,
+            price_per_share,
+            deposit: shares
+                .checked_mul(price_per_share)
+                .ok_or(ErrorCode::MathOverflow)?,
+            available: pool.get_available_shares()?,
+        })
+    }
+
+    pub fn withdraw_from_pool(ctx: Context<WithdrawFromPool>, shares: u64) -> Result<()> {
+        // Get accounts
+        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+
+        // Principal is withdrawn as deposited, whatever the shares are marked at
+        let pool_usdc_balance: u64 = ctx.accounts.pool_usdc_account.amount;
+        let amount: u64 = pool.get_principal_from_shares(shares)?;
+        let reserved: u64 = match &ctx.accounts.reserve {
+            Some(reserve) => get_bps_share(amount, reserve.principal_bps)?,
+            None => 0,
+        };
+
+        // Validations
+        require_gt!(pool.assets, 0, ErrorCode::NoVehicleAsset);
+        require!(
+            ctx.accounts.reserve.is_some() || !pool.reserve_configured,
+            ErrorCode::ReserveRequired
+        );
+        if pool.is_fleet() {
+            let tranche: &mut Account<'_, AssetTranche> = ctx
+                .accounts
+                .tranche
+                .as_mut()
+                .ok_or(ErrorCode::TrancheRequired)?;
+            tranche.add_deployed(amount)?;
+        }
+        // require_eq!(pool.shares, pool.minted, ErrorCode::SeedRoundsNotCompleted);
+        // require!(
+        //     pool.investment_period_ended(),
+        //     ErrorCode::StartDateNotPassed
+        // );
+        require_gte!(
+            pool_usdc_balance,
+            amount,
+            ErrorCode::InsufficientPoolUSDCBalance
+        );
+
+        // Signer seeds
+        let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&pool_seeds[..]];
+
+        // Set the reserve share of the principal aside, if the pool has a reserve
+        if let Some(reserve) = ctx.accounts.reserve.as_mut() {
+            let reserve_vault: &Account<'_, token::TokenAccount> = ctx
+                .accounts
+                .reserve_vault
+                .as_ref()
+                .ok_or(ErrorCode::ReserveVaultRequired)?;
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.pool_usdc_account.to_account_info(),
+                        to: reserve_vault.to_account_info(),
+                        authority: pool.to_account_info(),
+                    },
+                    signers_seeds,
+                ),
+                reserved,
+            )?;
+            reserve.add_funded(reserved)?;
+        }
+
+        // Transfer the USDC
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.pool_usdc_account.to_account_info(),
+                    to: ctx.accounts.to_usdc_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                signers_seeds,
+            ),
+            amount
+                .checked_sub(reserved)
+                .ok_or(ErrorCode::MathUnderflow)?,
+        )?;
+        emit!(PrincipalWithdrawn {
+            pool: pool.key(),
+            to: ctx.accounts.to_usdc_account.key(),
+            shares,
+            amount,
+            reserved,
+        });
+
+        Ok(())
+    }
+
+    /// Moves the pool along its lifecycle, as far as `PoolStatus::can_transition_to` allows.
+    pub fn transition_pool(ctx: Context<TransitionPool>, status: PoolStatus) -> Result<()> {
+        // Activation and settlement open the primary distribution, so they have their own
+        // instructions
+        require!(
+            !matches!(status, PoolStatus::Active | PoolStatus::Settled),
+            ErrorCode::InvalidPoolState
+        );
+
+        let now: u64 = Clock::get()?.unix_timestamp as u64;
+
+        ctx.accounts.pool.transition(status, now)
+    }
+
+    /// Starts the pool's operating life by opening its primary distribution, which takes
+    /// payouts from then on while shares keep trading.
+    pub fn activate_pool(ctx: Context<ActivatePool>) -> Result<()> {
+        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+        let distribution: &mut Account<'_, Distribution> = &mut ctx.accounts.distribution;
+
+        pool.transition(PoolStatus::Active, Clock::get()?.unix_timestamp as u64)?;
+
+        // Initialize the distribution account
+        distribution.set(
+            pool.key(),
+            ctx.accounts.distribution_authority.key(),
+            0,
+            ctx.bumps.distribution,
+        )?;
+
+        Ok(())
+    }
+
+    /// Final settlement. Pools that were never activated open their primary distribution
+    /// here, with `distribution_authority` as its authority.
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+        let distribution: &mut Account<'_, Distribution> = &mut ctx.accounts.distribution;
+
+        // Initialize the distribution account
+        if pool.status == PoolStatus::Fundraising {
+            distribution.set(
+                pool.key(),
+                ctx.accounts.distribution_authority.key(),
+                0,
+                ctx.bumps.distribution,
+            )?;
+        }
+        pool.transition(PoolStatus::Settled, Clock::get()?.unix_timestamp as u64)?;
+
+        Ok(())
+    }
+
+    /// Pays a holder of a refunding pool its pro-rata share of the pool's USDC for `shares`,
+    /// which are burned.
+    pub fn refund_shares(ctx: Context<RefundShares>, shares: u64) -> Result<()> {
+        // Get accounts
+        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+        let pool_usdc_balance: u64 = ctx.accounts.pool_usdc_account.amount;
+
+        // Validate the input
+        require_gte!(shares, 1_u64, ErrorCode::MinimumShares);
+
+        // The holder takes its share of whatever USDC the pool still holds
+        let refund: u64 = pool.get_refund(pool_usdc_balance, shares)?;
+
+        // Signer seeds
+        let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&pool_seeds[..]];
+
+        // Refund the USDC
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.pool_usdc_account.to_account_info(),
+                    to: ctx.accounts.holder_usdc_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                signers_seeds,
+            ),
+            refund,
+        )?;
+
+        // Burn the refunded shares
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    authority: ctx.accounts.holder.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.holder_mint_account.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
+
+        // Update the pool and position accounts
+        pool.sub_minted(shares)?;
+        let holder_position: &mut Account<'_, InvestorPosition> = &mut ctx.accounts.holder_position;
+        holder_position.set(
+            pool.key(),
+            ctx.accounts.holder.key(),
+            ctx.bumps.holder_position,
+        )?;
+        holder_position.settle_rewards(pool.reward_per_share)?;
+        let cost_basis: u64 = holder_position.sub_shares(shares)?;
+        holder_position.add_realized(cost_basis, refund)?;
+        emit!(SharesRefunded {
+            pool: pool.key(),
+            holder: ctx.accounts.holder.key(),
+            shares,
+            refund,
+        });
+
+        Ok(())
+    }
+
+    /// Pays a class holder of a refunding pool its pro-rata share of the pool's USDC for
+    /// `shares`, which are thawed and burned.
+    pub fn refund_class_shares(ctx: Context<RefundClassShares>, shares: u64) -> Result<()> {
+        // Get accounts
+        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+        let share_class: &mut Account<'_, ShareClass> = &mut ctx.accounts.share_class;
+        let pool_usdc_balance: u64 = ctx.accounts.pool_usdc_account.amount;
+
+        // Validate the input
+        require_gte!(shares, 1_u64, ErrorCode::MinimumShares);
+
+        // Class holders share whatever USDC the pool still holds with every other holder
+        let refund: u64 = pool.get_refund(pool_usdc_balance, shares)?;
+
+        // Signer seeds
+        let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&pool_seeds[..]];
+
+        // Refund the USDC
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.pool_usdc_account.to_account_info(),
+                    to: ctx.accounts.holder_usdc_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                signers_seeds,
+            ),
+            refund,
+        )?;
+
+        // Thaw and burn the refunded class shares
+        token::thaw_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::ThawAccount {
+                account: ctx.accounts.holder_mint_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: pool.to_account_info(),
+            },
+            signers_seeds,
+        ))?;
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    authority: ctx.accounts.holder.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.holder_mint_account.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
+        token::freeze_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::FreezeAccount {
+                account: ctx.accounts.holder_mint_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: pool.to_account_info(),
+            },
+            signers_seeds,
+        ))?;
+
+        // Update the class, pool and position accounts
+        share_class.sub_minted(shares)?;
+        pool.sub_minted(shares)?;
+        let class_position: &mut Account<'_, ClassPosition> = &mut ctx.accounts.class_position;
+        class_position.settle_rewards(share_class.reward_per_share)?;
+        class_position.sub_shares(shares)?;
+        let holder_position: &mut Account<'_, InvestorPosition> = &mut ctx.accounts.holder_position;
+        holder_position.settle_rewards(pool.reward_per_share)?;
+        let cost_basis: u64 = holder_position.sub_shares(shares)?;
+        holder_position.add_realized(cost_basis, refund)?;
+        emit!(SharesRefunded {
+            pool: pool.key(),
+            holder: ctx.accounts.holder.key(),
+            shares,
+            refund,
+        });
+
+        Ok(())
+    }
+
+    // pub fn claim_deposit(ctx: Context<ClaimDeposit>) -> Result<()> {
+    //     // Get the pool account
+    //     let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+
+    //     // Signer seeds
+    //     let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
+    //     let signers_seeds: &[&[&[u8]]; 1] = &[&pool_seeds[..]];
+
+    //     // Transfer the USDC to the user account
+    //     token::transfer(
+    //         CpiContext::new_with_signer(
+    //             ctx.accounts.token_program.to_account_info(),
+    //             token::Transfer {
+    //                 from: ctx.accounts.pool_usdc_account.to_account_info(),
+    //                 to: ctx.accounts.holder_usdc_account.to_account_info(),
+    //                 authority: pool.to_account_info(),
+    //             },
+    //             signers_seeds,
+    //         ),
+    //         pool.get_deposit_from_shares(ctx.accounts.holder_mint_account.amount),
+    //     )?;
+
+    //     // Burn tokens from the holder token account
+    //     token::burn(
+    //         CpiContext::new(
+    //             ctx.accounts.token_program.to_account_info(),
+    //             token::Burn {
+    //                 authority: ctx.accounts.holder.to_account_info(),
+    //                 mint: ctx.accounts.mint.to_account_info(),
+    //                 from: ctx.accounts.holder_mint_account.to_account_info(),
+    //             },
+    //         ),
+    //         ctx.accounts.holder_mint_account.amount,
+    //     )?;
+
+    //     Ok(())
+    // }
+
+    pub fn open_redemption_window(
+        ctx: Context<OpenRedemptionWindow>,
+        close_date: u64,
+        liquidity: u64,
+        source: RedemptionSource,
+    ) -> Result<()> {
+        // Get accounts
+        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+        let redemption_window: &mut Account<'_, RedemptionWindow> =
+            &mut ctx.accounts.redemption_window;
+
+        // Validate the input
+        let now: u64 = Clock::get()?.unix_timestamp as u64;
+        require!(!redemption_window.open, ErrorCode::RedemptionWindowOpen);
+        require_gt!(close_date, now, ErrorCode::InvalidRedemptionCloseDate);
+        require_gt!(liquidity, 0_u64, ErrorCode::InvalidRedemptionLiquidity);
+
+        // Fund the redemption window from the chosen source
+        match source {
+            RedemptionSource::Pool => {
+                let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
+                let signers_seeds: &[&[&[u8]]; 1] = &[&pool_seeds[..]];
+
+                require_gte!(
+                    ctx.accounts.pool_usdc_account.amount,
+                    liquidity,
+                    ErrorCode::InsufficientPoolUSDCBalance
+                );
+
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        token::Transfer {
+                            from: ctx.accounts.pool_usdc_account.to_account_info(),
+                            to: ctx.accounts.redemption_usdc_account.to_account_info(),
+                            authority: pool.to_account_info(),
+                        },
+                        signers_seeds,
+                    ),
+                    liquidity,
+                )?;
+            }
+            RedemptionSource::Distribution => {
+                let (Some(distribution), Some(distribution_usdc_account)) = (
+                    &mut ctx.accounts.distribution,
+                    &ctx.accounts.distribution_usdc_account,
+                ) else {
+                    return err!(ErrorCode::DistributionAccountsMissing);
+                };
+
+                let distribution_seeds: &[&[u8]; 3] = &[
+                    b"distribution",
+                    distribution.pool.as_ref(),
+                    &[distribution.bump],
+                ];
+                let signers_seeds: &[&[&[u8]]; 1] = &[&distribution_seeds[..]];
+
+                require_gte!(
+                    distribution.get_unclaimed_rewards()?,
+                    liquidity,
+                    ErrorCode::InsufficientDistributionUSDCBalance
+                );
+
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        token::Transfer {
+                            from: distribution_usdc_account.to_account_info(),
+                            to: ctx.accounts.redemption_usdc_account.to_account_info(),
+                            authority: distribution.to_account_info(),
+                        },
+                        signers_seeds,
+                    ),
+                    liquidity,
+                )?;
+
+                // The liquidity is no longer owed to holders as rewards
+                distribution.sub_rewards(liquidity)?;
+                pool.sub_rewards(liquidity)?;
+            }
+        }
+
+        // Open the next redemption round at the current share value
+        redemption_window.open(
+            pool.key(),
+            source,
+            pool.get_share_price(now)?,
+            close_date,
+            liquidity,
+            ctx.bumps.redemption_window,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn tender_shares(ctx: Context<TenderShares>, shares: u64) -> Result<()> {
+        // Get accounts
+        let redemption_window: &mut Account<'_, RedemptionWindow> =
+            &mut ctx.accounts.redemption_window;
+        let tender: &mut Account<'_, RedemptionTender> = &mut ctx.accounts.tender;
+
+        // Validate the input
+        let now: u64 = Clock::get()?.unix_timestamp as u64;
+        require_gte!(shares, 1_u64, ErrorCode::MinimumShares);
+        require_gt!(
+            redemption_window.close_date,
+            now,
+            ErrorCode::RedemptionWindowEnded
+        );
+
+        // Move the shares into the redemption escrow
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.holder_mint_account.to_account_info(),
+                    to: ctx.accounts.redemption_mint_account.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
+
+        // Update the tender and window accounts
+        tender.set(
+            redemption_window.key(),
+            ctx.accounts.holder.key(),
+            redemption_window.round,
+            ctx.bumps.tender,
+        )?;
+        tender.add_shares(shares)?;
+        redemption_window.add_tendered(shares)?;
+
+        Ok(())
+    }
+
+    pub fn settle_tender(ctx: Context<SettleTender>) -> Result<()> {
+        // Get accounts
+        let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+        let redemption_window: &mut Account<'_, RedemptionWindow> =
+            &mut ctx.accounts.redemption_window;
+        let tender: &Account<'_, RedemptionTender> = &ctx.accounts.tender;
+
+        // Validations
+        let now: u64 = Clock::get()?.unix_timestamp as u64;
+        require_gte!(
+            now,
+            redemption_window.close_date,
+            ErrorCode::RedemptionWindowNotEnded
+        );
+
+        // Fill the tender pro-rata against the window liquidity
+        let filled: u64 = redemption_window.get_filled_shares(tender.shares);
+        let unfilled: u64 = tender
+            .shares
+            .checked_sub(filled)
+            .ok_or(ErrorCode::MathUnderflow)?;
+        let payout: u64 = filled
+            .checked_mul(redemption_window.price_per_share)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // Signer seeds
+        let redemption_seeds: &[&[u8]; 3] = &[
+            b"redemption",
+            redemption_window.pool.as_ref(),
+            &[redemption_window.bump],
+        ];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&redemption_seeds[..]];
+
+        // Pay the holder for the filled shares
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.redemption_usdc_account.to_account_info(),
+                    to: ctx.accounts.holder_usdc_account.to_account_info(),
+                    authority: redemption_window.to_account_info(),
+                },
+                signers_seeds,
+            ),
+            payout,
+        )?;
+
+        // Burn the filled shares
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    authority: redemption_window.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.redemption_mint_account.to_account_info(),
+                },
+                signers_seeds,
+            ),
+            filled,
+        )?;
+
+        // Return the unfilled shares to the holder
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.redemption_mint_account.to_account_info(),
+                    to: ctx.accounts.holder_mint_account.to_account_info(),
+                    authority: redemption_window.to_account_info(),
+                },
+                signers_seeds,
+            ),
+            unfilled,
+        )?;
+
+        // Update the pool, window and position accounts
+        pool.sub_minted(filled)?;
+        redemption_window.sub_unsettled(tender.shares)?;
+        let holder_position: &mut Account<'_, InvestorPosition> = &mut ctx.accounts.holder_position;
+        holder_position.set(pool.key(), tender.holder, ctx.bumps.holder_position)?;
+        holder_position.settle_rewards(pool.reward_per_share)?;
+        let cost_basis: u64 = holder_position.sub_shares(filled)?;
+        holder_position.add_realized(cost_basis, payout)?;
+        emit!(TenderSettled {
+            pool: pool.key(),
+            holder: tender.holder,
+            shares: filled,
+            payout,
+        });
+
+        Ok(())
+    }
+
+    pub fn close_redemption_window(ctx: Context<CloseRedemptionWindow>) -> Result<()> {
+        // Get accounts
+        let redemption_window: &mut Account<'_, RedemptionWindow> =
+            &mut ctx.accounts.redemption_window;
+        let leftover: u64 = ctx.accounts.redemption_usdc_account.amount;
+
+        // Validations
+        let now: u64 = Clock::get()?.unix_timestamp as u64;
+        require_gte!(
+            now,
+            redemption_window.close_date,
+            ErrorCode::RedemptionWindowNotEnded
+        );
+        require_eq!(
+            redemption_window.unsettled,
+            0_u64,
+            ErrorCode::UnsettledRedemptionTenders
+        );
+
+        // Signer seeds
+        let redemption_seeds: &[&[u8]; 3] = &[
+            b"redemption",
+            redemption_window.pool.as_ref(),
+            &[redemption_window.bump],
+        ];
+        let signers_seeds: &[&[&[u8]]; 1] = &[&redemption_seeds[..]];
+
+        // Return the unused liquidity to its source
+        let to: AccountInfo<'_> = match redemption_window.source {
+            RedemptionSource::Pool => ctx.accounts.pool_usdc_account.to_account_info(),
+            RedemptionSource::Distribution => {
+                let (Some(distribution), Some(distribution_usdc_account)) = (
+                    &mut ctx.accounts.distribution,
+                    &ctx.accounts.distribution_usdc_account,
+                ) else {
+                    return err!(ErrorCode::DistributionAccountsMissing);
+                };
+                distribution.add_rewards(leftover)?;
+                ctx.accounts.pool.add_rewards(leftover)?;
+                distribution_usdc_account.to_account_info()
+            }
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.redemption_usdc_account.to_account_info(),
+                    to,
+                    authority: redemption_window.to_account_info(),
+                },
+                signers_seeds,
+            ),
+            leftover,
+        )?;
+
+        // Close the round
+        redemption_window.open = false;
+
+        Ok(())
+    }
+
+    pub fn close_pool_accounts(_ctx: Context<ClosePoolAccounts>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn migrate_project(ctx: Context<MigrateProject>) -> Result<()> {
+        let accounts: &MigrateProject<'_> = ctx.accounts;
+        migrate_account(
+            &accounts.project_pda,
+            &accounts.payer,
+            &accounts.system_program,
+            8 + ProjectData::INIT_SPACE,
+            |data| Ok(ProjectData::migrate(LegacyProjectData::deserialize(data)?)),
+        )
+    }
+
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        let accounts: &MigratePool<'_> = ctx.accounts;
+        migrate_account(
+            &accounts.pool,
+            &accounts.payer,
+            &accounts.system_program,
+            8 + Pool::INIT_SPACE,
+            |data| Pool::migrate(LegacyPool::deserialize(data)?),
+        )
+    }
+
+    /// The pool must be migrated first.
+    pub fn migrate_distribution(ctx: Context<MigrateDistribution>) -> Result<()> {
+        let accounts: &mut MigrateDistribution<'_> = ctx.accounts;
+        let mut rewards: u64 = 0;
+        migrate_account(
+            &accounts.distribution,
+            &accounts.payer,
+            &accounts.system_program,
+            8 + Distribution::INIT_SPACE,
+            |data| {
+                let distribution: Distribution =
+                    Distribution::migrate(LegacyDistribution::deserialize(data)?);
+                rewards = distribution.rewards;
+                Ok(distribution)
+            },
+        )?;
+
+        // Legacy pools had a single distribution, so it holds all of the pool's rewards
+        accounts.pool.add_rewards(rewards)
+    }
+}
+
+#[constant]
+pub const PROJECT_PDA: &[u8] = b"BLOCKRIDE_SYSTEM";
+#[constant]
+pub const COLLECTION_SEED: &[u8] = b"collection";
+#[derive(Accounts)]
+pub struct InitializeProgram<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(init,
+     payer = creator,
+     seeds=[PROJECT_PDA],bump,
+      space = 8 + ProjectData::INIT_SPACE)]
+    pub project_pda: Box<Account<'info, ProjectData>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ProjectData {
+    pub version: u8,
+    pub grand_authority: Pubkey,
+    pub protocol_fee_bps: u16,
+    /// Pools registered so far, which is also the id of the next one.
+    pub pools: u64,
+    pub reserved: [u8; 22],
+}
+
+#[derive(Accounts)]
+pub struct UpdateProgramGrandAuthority<'info> {
+    #[account(mut,address=project_pda.grand_authority @ ErrorCode::SignerNotAuthorized)]
+    pub grand_authority: Signer<'info>,
+
+    #[account(mut,
+     seeds=[PROJECT_PDA],bump)]
+    pub project_pda: Box<Account<'info, ProjectData>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetProtocolFee<'info> {
+    #[account(address = project_pda.grand_authority @ ErrorCode::SignerNotAuthorized)]
+    pub grand_authority: Signer<'info>,
+
+    #[account(mut,
+     seeds=[PROJECT_PDA],bump)]
+    pub project_pda: Box<Account<'info, ProjectData>>,
+}
+
+#[derive(Accounts)]
+#[instruction(creator: Pubkey)]
+pub struct AddCreator<'info> {
+    #[account(mut,address = project_pda.grand_authority @ ErrorCode::SignerNotAuthorized)]
+    pub grand_authority: Signer<'info>,
+
+    #[account(mut,
+     seeds=[PROJECT_PDA],bump)]
+    pub project_pda: Box<Account<'info, ProjectData>>,
+
+    #[account(init,
+     payer = grand_authority,
+     seeds=[PROJECT_PDA,creator.as_ref()],bump,
+      space = 8 + PoolCreatorData::INIT_SPACE)]
+    pub pool_creator_data: Box<Account<'info, PoolCreatorData>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PoolCreatorData {
+    pub creator: Pubkey,
+    pub can_create: bool,
+}
+
+#[derive(Accounts)]
+// #[instruction(poolcreator: Pubkey)]
+pub struct UpdatePoolCreator<'info> {
+    #[account(mut,
+     seeds=[PROJECT_PDA,pool_creator_data.creator.as_ref()],bump)]
+    pub pool_creator_data: Box<Account<'info, PoolCreatorData>>,
+
+    #[account(mut, address = project_pda.grand_authority @ ErrorCode::SignerNotAuthorized)]
+    pub creator: Signer<'info>,
+    #[account(mut,
+     seeds=[PROJECT_PDA],bump)]
+    pub project_pda: Box<Account<'info, ProjectData>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateCollection<'info> {
+    #[account(mut, address = project_pda.grand_authority @ ErrorCode::SignerNotAuthorized)]
+    pub grand_authority: Signer<'info>,
+
+    #[account(seeds = [PROJECT_PDA], bump)]
+    pub project_pda: Box<Account<'info, ProjectData>>,
+
+    #[account(
+        init,
+        payer = grand_authority,
+        mint::decimals = 0,
+        mint::authority = project_pda,
+        mint::freeze_authority = project_pda,
+        token::token_program = token_program,
+        seeds = [COLLECTION_SEED],
+        bump,
+    )]
+    pub collection_mint: Box<Account<'info, token::Mint>>,
+
+    #[account(
+        init,
+        payer = grand_authority,
+        associated_token::mint = collection_mint,
+        associated_token::authority = project_pda,
+        associated_token::token_program = token_program,
+    )]
+    pub collection_token_account: Box<Account<'info, token::TokenAccount>>,
+
+    /// CHECKS: The collection metadata account.
+    #[account(
+        mut,
+        seeds = [b"metadata", metadata_program.key().as_ref(), collection_mint.key().as_ref()],
+        bump,
+        seeds::program = metadata_program,
+    )]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// CHECKS: The collection master edition account.
+    #[account(
+        mut,
+        seeds = [b"metadata", metadata_program.key().as_ref(), collection_mint.key().as_ref(), b"edition"],
+        bump,
+        seeds::program = metadata_program,
+    )]
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    /// CHECKS: The metadata program.
+    #[account(address = MPL_TOKEN_METADATA_ID)]
+    pub metadata_program: UncheckedAccount<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+#[instruction(reference:Pubkey)]
+pub struct CreatePool<'info> {
+    // #[account(mut,
+    //  seeds=[PROJECT_PDA,pool_creator_data.creator.as_ref()],bump)]
+    // pub pool_creator_data: Account<'info, PoolCreatorData>,
+    #[account(mut,
+    //     address = pool_creator_data.creator @ ErrorCode::CreatorNotAuthorized,
+    // constraint = pool_creator_data.can_create == true @ ErrorCode::CreatorNotAuthorized
+ )]
+    pub creator: Signer<'info>,
+
+    // An ephemeral account that is used as a seed for the Pool PDA.
+    // Must be a signer to prevent front-running attack by someone else but the original creator.
+    // pub reference: Signer<'info>,
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Pool::INIT_SPACE,
+        // seeds = [b"pool", reference.as_ref()],
+        seeds = [b"pool", reference.as_ref()],
+        bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        init,
+        payer = creator,
+        mint::decimals = 0,
+        mint::authority = pool,
+        mint::freeze_authority = pool,
+        token::token_program = token_program,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub mint: Account<'info, token::Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = creator,
+        associated_token::mint = mint,
+        associated_token::authority = creator,
+        associated_token::token_program = token_program,
+    )]
+    pub creator_mint_account: Account<'info, token::TokenAccount>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = creator,
+    )]
+    pub creator_usdc_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = creator,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_usdc_account: Account<'info, token::TokenAccount>,
+
+    /// CHECKS: The metadata account.
+    #[account(
+        mut,
+        seeds = [b"metadata", metadata_program.key().as_ref(), mint.key().as_ref()],
+        bump,
+        seeds::program = metadata_program,
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    #[account(mut, seeds = [PROJECT_PDA], bump)]
+    pub project_pda: Box<Account<'info, ProjectData>>,
+
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + CreatorPools::INIT_SPACE,
+        seeds = [b"creator_pools", creator.key().as_ref()],
+        bump,
+    )]
+    pub creator_pools: Box<Account<'info, CreatorPools>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + PoolRegistryEntry::INIT_SPACE,
+        seeds = [b"pool_id".as_ref(), &project_pda.pools.to_le_bytes()],
+        bump,
+    )]
+    pub pool_entry: Box<Account<'info, PoolRegistryEntry>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + PoolRegistryEntry::INIT_SPACE,
+        seeds = [b"creator_pool", creator.key().as_ref(), &creator_pools.pools.to_le_bytes()],
+        bump,
+    )]
+    pub creator_pool_entry: Box<Account<'info, PoolRegistryEntry>>,
+
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + InvestorPosition::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), creator.key().as_ref()],
+        bump,
+    )]
+    pub creator_position: Box<Account<'info, InvestorPosition>>,
+
+    #[account(seeds = [COLLECTION_SEED], bump)]
+    pub collection_mint: Box<Account<'info, token::Mint>>,
+
+    /// CHECKS: The collection metadata account.
+    #[account(
+        mut,
+        seeds = [b"metadata", metadata_program.key().as_ref(), collection_mint.key().as_ref()],
+        bump,
+        seeds::program = metadata_program,
+    )]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// CHECKS: The collection master edition account.
+    #[account(
+        seeds = [b"metadata", metadata_program.key().as_ref(), collection_mint.key().as_ref(), b"edition"],
+        bump,
+        seeds::program = metadata_program,
+    )]
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    /// CHECKS: The metadata program.
+    #[account(address = MPL_TOKEN_METADATA_ID)]
+    pub metadata_program: UncheckedAccount<'info>,
+
+    /// CHECKS: The instructions sysvar.
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterPool<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.id.is_none() @ ErrorCode::PoolAlreadyRegistered,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(mut, seeds = [PROJECT_PDA], bump)]
+    pub project_pda: Box<Account<'info, ProjectData>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CreatorPools::INIT_SPACE,
+        seeds = [b"creator_pools", pool.creator.as_ref()],
+        bump,
+    )]
+    pub creator_pools: Box<Account<'info, CreatorPools>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PoolRegistryEntry::INIT_SPACE,
+        seeds = [b"pool_id".as_ref(), &project_pda.pools.to_le_bytes()],
+        bump,
+    )]
+    pub pool_entry: Box<Account<'info, PoolRegistryEntry>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PoolRegistryEntry::INIT_SPACE,
+        seeds = [b"creator_pool", pool.creator.as_ref(), &creator_pools.pools.to_le_bytes()],
+        bump,
+    )]
+    pub creator_pool_entry: Box<Account<'info, PoolRegistryEntry>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(reference: Pubkey)]
+pub struct CreatePoolTemplate<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + PoolTemplate::INIT_SPACE,
+        seeds = [b"template", reference.as_ref()],
+        bump,
+    )]
+    pub template: Box<Account<'info, PoolTemplate>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePoolTemplate<'info> {
+    #[account(address = template.creator @ ErrorCode::SignerNotAuthorized)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"template", template.reference.as_ref()],
+        bump = template.bump,
+    )]
+    pub template: Box<Account<'info, PoolTemplate>>,
+}
+
+#[derive(Accounts)]
+pub struct SetTemplateFeeOverride<'info> {
+    #[account(address = project_pda.grand_authority @ ErrorCode::SignerNotAuthorized)]
+    pub grand_authority: Signer<'info>,
+
+    #[account(seeds = [PROJECT_PDA], bump)]
+    pub project_pda: Box<Account<'info, ProjectData>>,
+
+    #[account(
+        mut,
+        seeds = [b"template", template.reference.as_ref()],
+        bump = template.bump,
+    )]
+    pub template: Box<Account<'info, PoolTemplate>>,
+}
+
+#[derive(Accounts)]
+pub struct CreatePoolFromTemplate<'info> {
+    pub create_pool: CreatePool<'info>,
+
+    #[account(
+        mut,
+        constraint = template.creator == create_pool.creator.key() @ ErrorCode::SignerNotAuthorized,
+        seeds = [b"template", template.reference.as_ref()],
+        bump = template.bump,
+    )]
+    pub template: Box<Account<'info, PoolTemplate>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePoolMetadata<'info> {
+    #[account(address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        constraint = pool.status.is_any(PoolStatus::OPEN) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECKS: The metadata account of the pool mint.
+    #[account(
+        mut,
+        seeds = [b"metadata", metadata_program.key().as_ref(), pool.mint.as_ref()],
+        bump,
+        seeds::program = metadata_program,
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// CHECKS: The metadata program.
+    #[account(address = MPL_TOKEN_METADATA_ID)]
+    pub metadata_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct JoinPoolCollection<'info> {
+    #[account(mut, address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECKS: The metadata account of the pool mint.
+    #[account(
+        mut,
+        seeds = [b"metadata", metadata_program.key().as_ref(), pool.mint.as_ref()],
+        bump,
+        seeds::program = metadata_program,
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    #[account(seeds = [PROJECT_PDA], bump)]
+    pub project_pda: Box<Account<'info, ProjectData>>,
+
+    #[account(seeds = [COLLECTION_SEED], bump)]
+    pub collection_mint: Box<Account<'info, token::Mint>>,
+
+    /// CHECKS: The collection metadata account.
+    #[account(
+        mut,
+        seeds = [b"metadata", metadata_program.key().as_ref(), collection_mint.key().as_ref()],
+        bump,
+        seeds::program = metadata_program,
+    )]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// CHECKS: The collection master edition account.
+    #[account(
+        seeds = [b"metadata", metadata_program.key().as_ref(), collection_mint.key().as_ref(), b"edition"],
+        bump,
+        seeds::program = metadata_program,
+    )]
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    /// CHECKS: The metadata program.
+    #[account(address = MPL_TOKEN_METADATA_ID)]
+    pub metadata_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(details: VehicleAssetDetails)]
+pub struct RegisterVehicleAsset<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        seeds = [PROJECT_PDA, creator.key().as_ref()],
+        bump,
+        constraint = pool_creator_data.can_create @ ErrorCode::CreatorNotAuthorized,
+    )]
+    pub pool_creator_data: Box<Account<'info, PoolCreatorData>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + VehicleAsset::INIT_SPACE,
+        seeds = [b"vehicle", details.vin_hash.as_ref()],
+        bump,
+    )]
+    pub vehicle_asset: Account<'info, VehicleAsset>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateVehicleAssetStatus<'info> {
+    #[account(address = vehicle_asset.creator @ ErrorCode::SignerNotAuthorized)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vehicle", vehicle_asset.vin_hash.as_ref()],
+        bump = vehicle_asset.bump,
+    )]
+    pub vehicle_asset: Account<'info, VehicleAsset>,
+}
+
+#[derive(Accounts)]
+pub struct LinkVehicleAsset<'info> {
+    #[account(address = vehicle_asset.creator @ ErrorCode::SignerNotAuthorized)]
     pub creator: Signer<'info>,
 
-    // An ephemeral account that is used as a seed for the Pool PDA.
-    // Must be a signer to prevent front-running attack by someone else but the original creator.
-    // pub reference: Signer<'info>,
-    #[account(
-        init,
-        payer = creator,
-        space = 8 + std::mem::size_of::<Pool>(),
-        // seeds = [b"pool", reference.as_ref()],
-        seeds = [b"pool", reference.as_ref()],
-        bump,
-    )]
-    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        constraint = pool.creator == creator.key() @ ErrorCode::SignerNotAuthorized,
+        constraint = pool.status.is_any(PoolStatus::CONFIGURABLE) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        constraint = vehicle_asset.pool.is_none() @ ErrorCode::VehicleAssetAlreadyLinked,
+        seeds = [b"vehicle", vehicle_asset.vin_hash.as_ref()],
+        bump = vehicle_asset.bump,
+    )]
+    pub vehicle_asset: Account<'info, VehicleAsset>,
+}
+
+#[derive(Accounts)]
+pub struct AddAssetTranche<'info> {
+    #[account(mut, address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.status.is_any(PoolStatus::CONFIGURABLE) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        constraint = vehicle_asset.pool == Some(pool.key()) @ ErrorCode::VehicleAssetNotLinked,
+        constraint = vehicle_asset.tranche.is_none() @ ErrorCode::VehicleAssetAlreadyFinanced,
+        seeds = [b"vehicle", vehicle_asset.vin_hash.as_ref()],
+        bump = vehicle_asset.bump,
+    )]
+    pub vehicle_asset: Account<'info, VehicleAsset>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AssetTranche::INIT_SPACE,
+        seeds = [b"tranche", pool.key().as_ref(), &pool.tranches.to_le_bytes()],
+        bump,
+    )]
+    pub tranche: Account<'info, AssetTranche>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyShares<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The pool authority, vouching for buyers of `Approved` pools.
+    #[account(address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub approver: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        constraint = pool.status.is_any(PoolStatus::SELLING) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        token::token_program = token_program,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub mint: Account<'info, token::Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_mint_account: Account<'info, token::TokenAccount>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_usdc_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_usdc_account: Account<'info, token::TokenAccount>,
+
+    /// Escrow holding purchased shares until released, only for pools with a lockup.
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        token::mint = mint,
+        token::authority = pool,
+        token::token_program = token_program,
+        seeds = [b"escrow", pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_escrow_account: Option<Account<'info, token::TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + ShareLockup::INIT_SPACE,
+        seeds = [b"lockup", pool.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub buyer_lockup: Option<Account<'info, ShareLockup>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + InvestorPosition::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub buyer_position: Box<Account<'info, InvestorPosition>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseShares<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        constraint = pool.status.is_any(PoolStatus::HELD) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        token::token_program = token_program,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = pool,
+        seeds = [b"escrow", pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_escrow_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"lockup", pool.key().as_ref(), holder.key().as_ref()],
+        bump = holder_lockup.bump,
+    )]
+    pub holder_lockup: Account<'info, ShareLockup>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_mint_account: Account<'info, token::TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct ListShares<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(
+        constraint = pool.status.is_any(PoolStatus::LIVE) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        token::token_program = token_program,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub mint: Account<'info, token::Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + Listing::INIT_SPACE,
+        seeds = [b"listing", pool.key().as_ref(), seller.key().as_ref()],
+        bump,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = seller,
+        associated_token::token_program = token_program,
+    )]
+    pub seller_mint_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = mint,
+        associated_token::authority = listing,
+        associated_token::token_program = token_program,
+    )]
+    pub listing_mint_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + InvestorPosition::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), seller.key().as_ref()],
+        bump,
+    )]
+    pub seller_position: Box<Account<'info, InvestorPosition>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct CancelListing<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(
+        constraint = pool.status.is_any(PoolStatus::HELD) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        token::token_program = token_program,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        close = seller,
+        seeds = [b"listing", pool.key().as_ref(), seller.key().as_ref()],
+        bump = listing.bump,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = seller,
+        associated_token::token_program = token_program,
+    )]
+    pub seller_mint_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = listing,
+        associated_token::token_program = token_program,
+    )]
+    pub listing_mint_account: Account<'info, token::TokenAccount>,
+
+    pub token_program: Program<'info, token::Token>,
+}
+
+#[derive(Accounts)]
+pub struct FillListing<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The pool authority, vouching for buyers of `Approved` pools.
+    #[account(address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub approver: Option<Signer<'info>>,
+
+    #[account(
+        constraint = pool.status.is_any(PoolStatus::LIVE) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        token::token_program = token_program,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"listing", pool.key().as_ref(), listing.seller.as_ref()],
+        bump = listing.bump,
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = listing,
+        associated_token::token_program = token_program,
+    )]
+    pub listing_mint_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_mint_account: Account<'info, token::TokenAccount>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_usdc_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = listing.seller,
+        associated_token::token_program = token_program,
+    )]
+    pub seller_usdc_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"position", pool.key().as_ref(), listing.seller.as_ref()],
+        bump = seller_position.bump,
+    )]
+    pub seller_position: Box<Account<'info, InvestorPosition>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + InvestorPosition::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub buyer_position: Box<Account<'info, InvestorPosition>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct TransferShares<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    /// CHECKS: Any wallet the pool's eligibility admits may receive shares.
+    pub recipient: UncheckedAccount<'info>,
+
+    /// The pool authority, vouching for recipients in `Approved` pools.
+    #[account(address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub approver: Option<Signer<'info>>,
+
+    #[account(
+        constraint = pool.status.is_any(PoolStatus::LIVE) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        token::token_program = token_program,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_mint_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program,
+    )]
+    pub recipient_mint_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + InvestorPosition::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), holder.key().as_ref()],
+        bump,
+    )]
+    pub holder_position: Box<Account<'info, InvestorPosition>>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + InvestorPosition::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub recipient_position: Box<Account<'info, InvestorPosition>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct CreateDistribution<'info> {
+    #[account(mut, address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub authority: Signer<'info>,
+
+    pub distribution_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.status.is_any(PoolStatus::DISTRIBUTING) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    // Wraps past the last id so that `add_distribution` rejects it
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Distribution::INIT_SPACE,
+        seeds = [b"distribution", pool.key().as_ref(), &[pool.distributions.wrapping_add(1)]],
+        bump,
+    )]
+    pub distribution: Box<Account<'info, Distribution>>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Box<Account<'info, token::Mint>>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = distribution,
+        associated_token::token_program = token_program,
+    )]
+    pub distribution_usdc_account: Box<Account<'info, token::TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct SetDistributionAuthority<'info> {
+    #[account(
+        constraint = signer.key() == distribution.authority
+            || signer.key() == pool.authority @ ErrorCode::SignerNotAuthorized,
+    )]
+    pub signer: Signer<'info>,
+
+    pub new_authority: Signer<'info>,
+
+    #[account(
+        constraint = pool.status.is_any(PoolStatus::DISTRIBUTING) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        constraint = distribution.pool == pool.key() @ ErrorCode::InvalidDistribution,
+    )]
+    pub distribution: Account<'info, Distribution>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDistribution<'info> {
+    #[account(mut, address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.status.is_any(PoolStatus::DISTRIBUTING) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        constraint = distribution.pool == pool.key() @ ErrorCode::InvalidDistribution,
+        constraint = distribution.id > 0 @ ErrorCode::InvalidDistribution,
+        constraint = distribution.rewards == distribution.claimed @ ErrorCode::UnclaimedDistributionRewards,
+        close = authority,
+    )]
+    pub distribution: Account<'info, Distribution>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        constraint = distribution_usdc_account.amount == 0 @ ErrorCode::NonZeroDistributionUSDCBalance,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = distribution,
+        associated_token::token_program = token_program,
+    )]
+    pub distribution_usdc_account: Account<'info, token::TokenAccount>,
+
+    pub token_program: Program<'info, token::Token>,
+}
+
+#[derive(Accounts)]
+pub struct Distribute<'info> {
+    // #[account(
+    //     mut,
+    //     address = pool.authority,
+    // )]
+    // authority: Signer<'info>,
+    #[account(mut)]
+    signer: Signer<'info>,
+
+    #[account(address = distribution.authority)]
+    distribution_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.status.is_any(PoolStatus::DISTRIBUTING) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = signer,
+    )]
+    pub signer_usdc_account: Account<'info, token::TokenAccount>,
+    #[account(
+        mut,
+        constraint = distribution.pool == pool.key() @ ErrorCode::InvalidDistribution,
+    )]
+    pub distribution: Account<'info, Distribution>,
+
+    /// The tranche repaying, required for fleet pools.
+    #[account(
+        mut,
+        seeds = [b"tranche", pool.key().as_ref(), &tranche.index.to_le_bytes()],
+        bump = tranche.bump,
+    )]
+    pub tranche: Option<Box<Account<'info, AssetTranche>>>,
+
+    /// CHECKS: The pool reserve, which may not be configured.
+    #[account(
+        seeds = [b"reserve", pool.key().as_ref()],
+        bump,
+    )]
+    pub reserve: UncheckedAccount<'info>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = distribution,
+        associated_token::token_program = token_program,
+    )]
+    pub distribution_usdc_account: Account<'info, token::TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut)]
+    holder: Signer<'info>,
+
+    #[account(address = distribution.authority)]
+    authority: Signer<'info>,
+
+    #[account(
+        constraint = pool.status.is_any(PoolStatus::DISTRIBUTING) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        constraint = distribution.pool == pool.key() @ ErrorCode::InvalidDistribution,
+    )]
+    pub distribution: Account<'info, Distribution>,
+
+    #[account(
+        token::token_program = token_program,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub mint: Account<'info, token::Mint>,
+
+    #[account(
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_mint_account: Account<'info, token::TokenAccount>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = distribution,
+        associated_token::token_program = token_program,
+    )]
+    pub distribution_usdc_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_usdc_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + InvestorPosition::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), holder.key().as_ref()],
+        bump,
+    )]
+    pub holder_position: Box<Account<'info, InvestorPosition>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct CreateShareClass<'info> {
+    #[account(mut, address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.minted == pool.creator_shares @ ErrorCode::ShareClassesLocked,
+        constraint = pool.status.is_any(&[PoolStatus::Draft, PoolStatus::Fundraising]) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ShareClass::INIT_SPACE,
+        seeds = [b"share_class", pool.key().as_ref(), &[pool.share_classes]],
+        bump,
+    )]
+    pub share_class: Box<Account<'info, ShareClass>>,
+
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = 0,
+        mint::authority = pool,
+        mint::freeze_authority = pool,
+        token::token_program = token_program,
+        seeds = [b"mint", pool.key().as_ref(), &[pool.share_classes]],
+        bump,
+    )]
+    pub mint: Box<Account<'info, token::Mint>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+}
+
+#[derive(Accounts)]
+pub struct BuyClassShares<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The pool authority, vouching for buyers of `Approved` classes.
+    #[account(address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub approver: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        constraint = pool.status.is_any(PoolStatus::SELLING) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        mut,
+        seeds = [b"share_class", pool.key().as_ref(), &[share_class.class_id]],
+        bump = share_class.bump,
+    )]
+    pub share_class: Box<Account<'info, ShareClass>>,
+
+    #[account(
+        mut,
+        address = share_class.mint,
+    )]
+    pub mint: Box<Account<'info, token::Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_mint_account: Box<Account<'info, token::TokenAccount>>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Box<Account<'info, token::Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_usdc_account: Box<Account<'info, token::TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_usdc_account: Box<Account<'info, token::TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + InvestorPosition::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub buyer_position: Box<Account<'info, InvestorPosition>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + ClassPosition::INIT_SPACE,
+        seeds = [b"class_position", share_class.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub class_position: Box<Account<'info, ClassPosition>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct AllocateClassRewards<'info> {
+    #[account(
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        seeds = [b"distribution", pool.key().as_ref()],
+        bump = distribution.bump,
+    )]
+    pub distribution: Account<'info, Distribution>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimClassRewards<'info> {
+    #[account(mut)]
+    holder: Signer<'info>,
+
+    #[account(address = distribution.authority)]
+    authority: Signer<'info>,
+
+    #[account(
+        constraint = pool.status.is_any(PoolStatus::DISTRIBUTING) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        mut,
+        seeds = [b"distribution", pool.key().as_ref()],
+        bump = distribution.bump,
+    )]
+    pub distribution: Box<Account<'info, Distribution>>,
+
+    #[account(
+        mut,
+        seeds = [b"share_class", pool.key().as_ref(), &[share_class.class_id]],
+        bump = share_class.bump,
+    )]
+    pub share_class: Box<Account<'info, ShareClass>>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Box<Account<'info, token::Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = distribution,
+        associated_token::token_program = token_program,
+    )]
+    pub distribution_usdc_account: Box<Account<'info, token::TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_usdc_account: Box<Account<'info, token::TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + InvestorPosition::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), holder.key().as_ref()],
+        bump,
+    )]
+    pub holder_position: Box<Account<'info, InvestorPosition>>,
+
+    #[account(
+        mut,
+        seeds = [b"class_position", share_class.key().as_ref(), holder.key().as_ref()],
+        bump = class_position.bump,
+    )]
+    pub class_position: Box<Account<'info, ClassPosition>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureRevenue<'info> {
+    #[account(mut, address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(seeds = [PROJECT_PDA], bump)]
+    pub project_pda: Box<Account<'info, ProjectData>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + RevenueConfig::INIT_SPACE,
+        seeds = [b"revenue", pool.key().as_ref()],
+        bump,
+    )]
+    pub revenue_config: Box<Account<'info, RevenueConfig>>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Box<Account<'info, token::Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        token::mint = usdc_mint,
+        token::authority = pool,
+        token::token_program = token_program,
+        seeds = [b"driver_bucket", pool.key().as_ref()],
+        bump,
+    )]
+    pub driver_bucket: Box<Account<'info, token::TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        token::mint = usdc_mint,
+        token::authority = pool,
+        token::token_program = token_program,
+        seeds = [b"maintenance_bucket", pool.key().as_ref()],
+        bump,
+    )]
+    pub maintenance_bucket: Box<Account<'info, token::TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        token::mint = usdc_mint,
+        token::authority = pool,
+        token::token_program = token_program,
+        seeds = [b"protocol_bucket", pool.key().as_ref()],
+        bump,
+    )]
+    pub protocol_bucket: Box<Account<'info, token::TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        token::mint = usdc_mint,
+        token::authority = pool,
+        token::token_program = token_program,
+        seeds = [b"investor_bucket", pool.key().as_ref()],
+        bump,
+    )]
+    pub investor_bucket: Box<Account<'info, token::TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReportRevenue<'info> {
+    #[account(address = revenue_config.operator @ ErrorCode::SignerNotAuthorized)]
+    pub operator: Signer<'info>,
+
+    #[account(
+        constraint = pool.status.is_any(PoolStatus::LIVE) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(seeds = [PROJECT_PDA], bump)]
+    pub project_pda: Box<Account<'info, ProjectData>>,
+
+    #[account(
+        mut,
+        seeds = [b"revenue", pool.key().as_ref()],
+        bump = revenue_config.bump,
+    )]
+    pub revenue_config: Box<Account<'info, RevenueConfig>>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Box<Account<'info, token::Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = operator,
+    )]
+    pub operator_usdc_account: Box<Account<'info, token::TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = pool,
+        seeds = [b"driver_bucket", pool.key().as_ref()],
+        bump,
+    )]
+    pub driver_bucket: Box<Account<'info, token::TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = pool,
+        seeds = [b"maintenance_bucket", pool.key().as_ref()],
+        bump,
+    )]
+    pub maintenance_bucket: Box<Account<'info, token::TokenAccount>>,
+
+    /// The maintenance share funds the pool reserve, required once it is configured.
+    /// Until then the share waits in the maintenance bucket for `configure_reserve`.
+    #[account(
+        mut,
+        seeds = [b"reserve", pool.key().as_ref()],
+        bump = reserve.bump,
+    )]
+    pub reserve: Option<Box<Account<'info, PoolReserve>>>,
+
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = pool,
+        seeds = [b"protocol_bucket", pool.key().as_ref()],
+        bump,
+    )]
+    pub protocol_bucket: Box<Account<'info, token::TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = pool,
+        seeds = [b"investor_bucket", pool.key().as_ref()],
+        bump,
+    )]
+    pub investor_bucket: Box<Account<'info, token::TokenAccount>>,
+
+    pub token_program: Program<'info, token::Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(bucket: RevenueBucket)]
+pub struct WithdrawRevenue<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(seeds = [PROJECT_PDA], bump)]
+    pub project_pda: Box<Account<'info, ProjectData>>,
+
+    #[account(
+        seeds = [b"revenue", pool.key().as_ref()],
+        bump = revenue_config.bump,
+    )]
+    pub revenue_config: Box<Account<'info, RevenueConfig>>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Box<Account<'info, token::Mint>>,
+
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = pool,
+        seeds = [bucket.seed(), pool.key().as_ref()],
+        bump,
+    )]
+    pub bucket_account: Box<Account<'info, token::TokenAccount>>,
+
+    #[account(mut, token::mint = usdc_mint)]
+    pub to_usdc_account: Box<Account<'info, token::TokenAccount>>,
+
+    pub token_program: Program<'info, token::Token>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeRevenue<'info> {
+    /// The operator or the pool authority.
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.status.is_any(PoolStatus::DISTRIBUTING) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        seeds = [b"revenue", pool.key().as_ref()],
+        bump = revenue_config.bump,
+    )]
+    pub revenue_config: Box<Account<'info, RevenueConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"distribution", pool.key().as_ref()],
+        bump = distribution.bump,
+    )]
+    pub distribution: Box<Account<'info, Distribution>>,
+
+    /// The tranche repaying, required for fleet pools.
+    #[account(
+        mut,
+        seeds = [b"tranche", pool.key().as_ref(), &tranche.index.to_le_bytes()],
+        bump = tranche.bump,
+    )]
+    pub tranche: Option<Box<Account<'info, AssetTranche>>>,
+
+    /// CHECKS: The pool reserve, which may not be configured.
+    #[account(
+        seeds = [b"reserve", pool.key().as_ref()],
+        bump,
+    )]
+    pub reserve: UncheckedAccount<'info>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Box<Account<'info, token::Mint>>,
+
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = pool,
+        seeds = [b"investor_bucket", pool.key().as_ref()],
+        bump,
+    )]
+    pub investor_bucket: Box<Account<'info, token::TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = distribution,
+        associated_token::token_program = token_program,
+    )]
+    pub distribution_usdc_account: Box<Account<'info, token::TokenAccount>>,
+
+    pub token_program: Program<'info, token::Token>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureReserve<'info> {
+    #[account(mut, address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + PoolReserve::INIT_SPACE,
+        seeds = [b"reserve", pool.key().as_ref()],
+        bump,
+    )]
+    pub reserve: Box<Account<'info, PoolReserve>>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Box<Account<'info, token::Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        token::mint = usdc_mint,
+        token::authority = pool,
+        token::token_program = token_program,
+        seeds = [b"maintenance_bucket", pool.key().as_ref()],
+        bump,
+    )]
+    pub reserve_vault: Box<Account<'info, token::TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+}
+
+#[derive(Accounts)]
+pub struct SpendReserve<'info> {
+    #[account(mut, address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        constraint = pool.status.is_any(PoolStatus::LIVE) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        mut,
+        seeds = [b"reserve", pool.key().as_ref()],
+        bump = reserve.bump,
+    )]
+    pub reserve: Box<Account<'info, PoolReserve>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ReserveExpense::INIT_SPACE,
+        seeds = [b"expense", reserve.key().as_ref(), &reserve.expenses.to_le_bytes()],
+        bump,
+    )]
+    pub expense: Box<Account<'info, ReserveExpense>>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Box<Account<'info, token::Mint>>,
+
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = pool,
+        seeds = [b"maintenance_bucket", pool.key().as_ref()],
+        bump,
+    )]
+    pub reserve_vault: Box<Account<'info, token::TokenAccount>>,
+
+    #[account(mut, token::mint = usdc_mint)]
+    pub to_usdc_account: Box<Account<'info, token::TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetPoolValuator<'info> {
+    #[account(address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.status.is_any(PoolStatus::OPEN) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateNav<'info> {
+    #[account(address = pool.valuator @ ErrorCode::SignerNotAuthorized)]
+    pub valuator: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.status.is_any(PoolStatus::LIVE) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+#[derive(Accounts)]
+pub struct GetNav<'info> {
+    #[account(
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+#[derive(Accounts)]
+pub struct GetPoolSummary<'info> {
+    #[account(
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// Only exists once the pool has been activated or settled.
+    #[account(
+        seeds = [b"distribution", pool.key().as_ref()],
+        bump = distribution.bump,
+    )]
+    pub distribution: Option<Account<'info, Distribution>>,
+}
+
+#[derive(Accounts)]
+pub struct GetTrancheSummary<'info> {
+    #[account(
+        seeds = [b"tranche", tranche.pool.as_ref(), &tranche.index.to_le_bytes()],
+        bump = tranche.bump,
+    )]
+    pub tranche: Account<'info, AssetTranche>,
+}
+
+#[derive(Accounts)]
+#[instruction(holder: Pubkey)]
+pub struct GetClaimableRewards<'info> {
+    #[account(
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        constraint = distribution.pool == pool.key() @ ErrorCode::InvalidDistribution,
+    )]
+    pub distribution: Account<'info, Distribution>,
+
+    /// CHECKS: The holder's position, which may not exist.
+    #[account(
+        seeds = [b"position", pool.key().as_ref(), holder.as_ref()],
+        bump,
+    )]
+    pub holder_position: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(holder: Pubkey)]
+pub struct GetClaimableClassRewards<'info> {
+    #[account(
+        seeds = [b"share_class", share_class.pool.as_ref(), &[share_class.class_id]],
+        bump = share_class.bump,
+    )]
+    pub share_class: Account<'info, ShareClass>,
+
+    /// CHECKS: The holder's class position, which may not exist.
+    #[account(
+        seeds = [b"class_position", share_class.key().as_ref(), holder.as_ref()],
+        bump,
+    )]
+    pub class_position: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(holder: Pubkey)]
+pub struct GetRedeemablePrincipal<'info> {
+    #[account(
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        token::token_program = token_program,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub mint: Account<'info, token::Mint>,
+
+    #[account(
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_mint_account: Account<'info, token::TokenAccount>,
+
+    /// CHECKS: The holder's lockup, which may not exist.
+    #[account(
+        seeds = [b"lockup", pool.key().as_ref(), holder.as_ref()],
+        bump,
+    )]
+    pub holder_lockup: UncheckedAccount<'info>,
+
+    /// CHECKS: The holder's listing, which may not exist.
+    #[account(
+        seeds = [b"listing", pool.key().as_ref(), holder.as_ref()],
+        bump,
+    )]
+    pub holder_listing: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, token::Token>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteBuy<'info> {
+    #[account(
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFromPool<'info> {
+    #[account(
+        mut,
+        address = pool.authority,
+    )]
+    authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.status.is_any(&[PoolStatus::Fundraising, PoolStatus::Funded, PoolStatus::Active]) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_usdc_account: Account<'info, token::TokenAccount>,
+
+    #[account(mut)]
+    pub to_usdc_account: Account<'info, token::TokenAccount>,
+
+    /// The pool reserve, which takes its share of the principal once it is configured.
+    #[account(
+        mut,
+        has_one = pool,
+        seeds = [b"reserve", pool.key().as_ref()],
+        bump = reserve.bump,
+    )]
+    pub reserve: Option<Box<Account<'info, PoolReserve>>>,
+
+    /// Required when the pool has a reserve.
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = pool,
+        seeds = [b"maintenance_bucket", pool.key().as_ref()],
+        bump,
+    )]
+    pub reserve_vault: Option<Box<Account<'info, token::TokenAccount>>>,
+
+    /// The tranche the principal finances, required for fleet pools.
+    #[account(
+        mut,
+        seeds = [b"tranche", pool.key().as_ref(), &tranche.index.to_le_bytes()],
+        bump = tranche.bump,
+    )]
+    pub tranche: Option<Box<Account<'info, AssetTranche>>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct TransitionPool<'info> {
+    #[account(address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+#[derive(Accounts)]
+pub struct ActivatePool<'info> {
+    #[account(mut, address = pool.authority @ ErrorCode::SignerNotAuthorized)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// Already exists for pools that were activated or settled, which can't be activated.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + Distribution::INIT_SPACE,
+        seeds = [b"distribution", pool.key().as_ref()],
+        bump,
+    )]
+    pub distribution: Account<'info, Distribution>,
+
+    pub distribution_authority: Signer<'info>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Account<'info, token::Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = distribution,
+        associated_token::token_program = token_program,
+    )]
+    pub distribution_usdc_account: Account<'info, token::TokenAccount>,
+
+    pub token_program: Program<'info, token::Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    #[account(
+        mut,
+        address = pool.authority,
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + Distribution::INIT_SPACE,
+        seeds = [b"distribution", pool.key().as_ref()],
+        bump,
+    )]
+    pub distribution: Account<'info, Distribution>,
+
+    distribution_authority: Signer<'info>,
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Account<'info, token::Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = distribution,
+        associated_token::token_program = token_program,
+    )]
+    pub distribution_usdc_account: Account<'info, token::TokenAccount>,
+
+    pub token_program: Program<'info, token::Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct RefundShares<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.status == PoolStatus::Refunding @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        token::token_program = token_program,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_mint_account: Account<'info, token::TokenAccount>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_usdc_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_usdc_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + InvestorPosition::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), holder.key().as_ref()],
+        bump,
+    )]
+    pub holder_position: Box<Account<'info, InvestorPosition>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct RefundClassShares<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.status == PoolStatus::Refunding @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        mut,
+        seeds = [b"share_class", pool.key().as_ref(), &[share_class.class_id]],
+        bump = share_class.bump,
+    )]
+    pub share_class: Box<Account<'info, ShareClass>>,
+
+    #[account(
+        mut,
+        address = share_class.mint,
+    )]
+    pub mint: Box<Account<'info, token::Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_mint_account: Box<Account<'info, token::TokenAccount>>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Box<Account<'info, token::Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_usdc_account: Box<Account<'info, token::TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_usdc_account: Box<Account<'info, token::TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"position", pool.key().as_ref(), holder.key().as_ref()],
+        bump = holder_position.bump,
+    )]
+    pub holder_position: Box<Account<'info, InvestorPosition>>,
+
+    #[account(
+        mut,
+        seeds = [b"class_position", share_class.key().as_ref(), holder.key().as_ref()],
+        bump = class_position.bump,
+    )]
+    pub class_position: Box<Account<'info, ClassPosition>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimDeposit<'info> {
+    #[account(mut)]
+    holder: Signer<'info>,
+
+    #[account(
+        constraint = pool.status == PoolStatus::Settled @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        token::token_program = token_program,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_mint_account: Account<'info, token::TokenAccount>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_usdc_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_usdc_account: Account<'info, token::TokenAccount>,
+
+    pub token_program: Program<'info, token::Token>,
+}
+
+#[derive(Accounts)]
+pub struct OpenRedemptionWindow<'info> {
+    #[account(
+        mut,
+        address = pool.authority,
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.status.is_any(PoolStatus::LIVE) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + RedemptionWindow::INIT_SPACE,
+        seeds = [b"redemption", pool.key().as_ref()],
+        bump,
+    )]
+    pub redemption_window: Account<'info, RedemptionWindow>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_usdc_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = redemption_window,
+        associated_token::token_program = token_program,
+    )]
+    pub redemption_usdc_account: Account<'info, token::TokenAccount>,
+
+    /// Only required when the window is funded from the distribution vault.
+    #[account(
+        mut,
+        seeds = [b"distribution", pool.key().as_ref()],
+        bump = distribution.bump,
+    )]
+    pub distribution: Option<Account<'info, Distribution>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = distribution,
+        associated_token::token_program = token_program,
+    )]
+    pub distribution_usdc_account: Option<Account<'info, token::TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct TenderShares<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        constraint = pool.status.is_any(PoolStatus::LIVE) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        token::token_program = token_program,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        constraint = redemption_window.open @ ErrorCode::RedemptionWindowNotOpen,
+        seeds = [b"redemption", pool.key().as_ref()],
+        bump = redemption_window.bump,
+    )]
+    pub redemption_window: Account<'info, RedemptionWindow>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + RedemptionTender::INIT_SPACE,
+        seeds = [b"tender", redemption_window.key().as_ref(), holder.key().as_ref()],
+        bump,
+    )]
+    pub tender: Account<'info, RedemptionTender>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_mint_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        associated_token::mint = mint,
+        associated_token::authority = redemption_window,
+        associated_token::token_program = token_program,
+    )]
+    pub redemption_mint_account: Account<'info, token::TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct SettleTender<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// CHECKS: The holder that tendered the shares.
+    #[account(mut, address = tender.holder)]
+    pub holder: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.status.is_any(PoolStatus::REDEEMING) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        token::token_program = token_program,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        constraint = redemption_window.open @ ErrorCode::RedemptionWindowNotOpen,
+        seeds = [b"redemption", pool.key().as_ref()],
+        bump = redemption_window.bump,
+    )]
+    pub redemption_window: Account<'info, RedemptionWindow>,
+
+    #[account(
+        mut,
+        close = holder,
+        seeds = [b"tender", redemption_window.key().as_ref(), tender.holder.as_ref()],
+        bump = tender.bump,
+    )]
+    pub tender: Account<'info, RedemptionTender>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = redemption_window,
+        associated_token::token_program = token_program,
+    )]
+    pub redemption_mint_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_mint_account: Account<'info, token::TokenAccount>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = redemption_window,
+        associated_token::token_program = token_program,
+    )]
+    pub redemption_usdc_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_usdc_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + InvestorPosition::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), holder.key().as_ref()],
+        bump,
+    )]
+    pub holder_position: Box<Account<'info, InvestorPosition>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, token::Token>,
+    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRedemptionWindow<'info> {
+    #[account(
+        mut,
+        address = pool.authority,
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.status.is_any(PoolStatus::REDEEMING) @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        constraint = redemption_window.open @ ErrorCode::RedemptionWindowNotOpen,
+        seeds = [b"redemption", pool.key().as_ref()],
+        bump = redemption_window.bump,
+    )]
+    pub redemption_window: Account<'info, RedemptionWindow>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = redemption_window,
+        associated_token::token_program = token_program,
+    )]
+    pub redemption_usdc_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_usdc_account: Account<'info, token::TokenAccount>,
+
+    /// Only required when the window was funded from the distribution vault.
+    #[account(
+        mut,
+        seeds = [b"distribution", pool.key().as_ref()],
+        bump = distribution.bump,
+    )]
+    pub distribution: Option<Account<'info, Distribution>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = distribution,
+        associated_token::token_program = token_program,
+    )]
+    pub distribution_usdc_account: Option<Account<'info, token::TokenAccount>>,
+
+    pub token_program: Program<'info, token::Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePoolAccounts<'info> {
+    #[account(
+        mut,
+        address = pool.authority,
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool.open_distributions == 0 @ ErrorCode::OpenDistributions,
+        close = authority,
+        constraint = pool.status == PoolStatus::Settled @ ErrorCode::InvalidPoolState,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        constraint = distribution.rewards == distribution.claimed @ ErrorCode::UnclaimedDistributionRewards,
+        close = authority,
+        seeds = [b"distribution", pool.key().as_ref()],
+        bump = distribution.bump
+    )]
+    pub distribution: Account<'info, Distribution>,
+
+    #[account(
+        mut,
+        token::token_program = token_program,
+        seeds = [b"mint", pool.key().as_ref()],
+        bump,
+    )]
+    pub mint: Account<'info, token::Mint>,
+
+    /// CHECKS: The USDC mint.
+    #[account(address = CUSTOM_USDC_MINT)]
+    pub usdc_mint: Account<'info, token::Mint>,
+
+    #[account(
+        mut,
+        constraint = pool_usdc_account.amount == 0 @ ErrorCode::NonZeroPoolUSDCBalance,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_usdc_account: Account<'info, token::TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = distribution_usdc_account.amount == 0 @ ErrorCode::NonZeroDistributionUSDCBalance,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = distribution,
+        associated_token::token_program = token_program,
+    )]
+    pub distribution_usdc_account: Account<'info, token::TokenAccount>,
+
+    pub token_program: Program<'info, token::Token>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateProject<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECKS: The project account in any layout version, checked on migration.
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [PROJECT_PDA],
+        bump,
+    )]
+    pub project_pda: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECKS: A pool in any layout version, checked on migration.
+    #[account(mut, owner = crate::ID)]
+    pub pool: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateDistribution<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.reference.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECKS: A distribution in any layout version, checked on migration.
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"distribution", pool.key().as_ref()],
+        bump,
+    )]
+    pub distribution: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub version: u8,
+    pub creator: Pubkey,
+    pub authority: Pubkey,
+    pub reference: Pubkey,
+    pub mint: Pubkey,
+    // pub seed: u64,
+    pub shares: u64,
+    pub minted: u64,
+    pub status: PoolStatus,
+    pub bump: u8,
+    pub start_date: u64,
+    pub maturity_date: u64,
+    pub apy: u8,
+    pub lockup: Option<LockupConfig>,
+    pub valuator: Pubkey,
+    pub nav_per_share: u64,
+    pub nav_updated_at: u64,
+    pub assets: u16,
+    pub tranches: u16,
+    pub allocated: u64,
+    pub share_classes: u8,
+    pub class_shares: u64,
+    pub eligibility: BuyerEligibility,
+    pub protocol_fee_bps: Option<u16>,
+    /// Sequential id in the pool registry, `None` until the pool is registered.
+    pub id: Option<u64>,
+    /// Distributions created besides the primary one, which is also the last id used.
+    pub distributions: u8,
+    pub open_distributions: u8,
+    /// Rewards credited to all of the pool's distributions, which holders share pro rata.
+    pub rewards: u64,
+    /// NAV as of `nav_period_started_at`, which caps how far updates may move it within the period.
+    pub nav_period_nav: u64,
+    pub nav_period_started_at: u64,
+    /// Rewards credited per outstanding share across the pool's distributions, scaled by
+    /// `Pool::REWARD_PRECISION`. Positions earn the increase while they hold shares.
+    pub reward_per_share: u128,
+    /// Set once `configure_reserve` has created the reserve, which then takes its share of
+    /// revenue and principal.
+    pub reserve_configured: bool,
+    /// USDC paid in for shares, which caps the principal the pool's tranches may finance.
+    pub raised: u64,
+    /// Shares minted to the creator's seed deposit, the only ones before any are sold.
+    pub creator_shares: u64,
+    /// Issue price of a share, which principal is withdrawn at whatever the NAV.
+    pub share_price: u64,
+    pub reserved: [u8; 4],
+}
+
+/// Where a pool is in its life; `can_transition_to` is the only way between states.
+/// Variants keep the order they were added in, so pools written with the `closed` flag
+/// this replaced read as `Fundraising` or `Settled`.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolStatus {
+    /// Selling shares; nothing has been distributed yet.
+    Fundraising,
+    /// Closed for final settlement: no more shares are sold or withdrawn.
+    Settled,
+    /// Operating, with the primary distribution open for periodic payouts.
+    Active,
+    /// Created and being configured; only the creator's seed shares exist.
+    Draft,
+    /// Fully raised and no longer selling shares, waiting to be activated.
+    Funded,
+    /// Past its term; paying out what is left before settlement.
+    Matured,
+    /// Its vehicles stopped paying; recoveries are distributed before settlement.
+    Defaulted,
+    /// The raise failed, or the draft was abandoned, and holders are being paid back,
+    /// after which it is cancelled.
+    Refunding,
+    /// Abandoned before operating, with nothing left to pay out.
+    Cancelled,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NavQuote {
+    pub nav_per_share: u64,
+    pub nav_updated_at: u64,
+    pub stale: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolSummary {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub shares: u64,
+    pub minted: u64,
+    pub available: u64,
+    pub status: PoolStatus,
+    pub start_date: u64,
+    pub maturity_date: u64,
+    pub apy: u8,
+    pub nav_per_share: u64,
+    pub nav_stale: bool,
+    pub rewards: u64,
+    pub claimed: u64,
+    pub tranches: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BuyQuote {
+    pub shares: u64,
+    pub price_per_share: u64,
+    pub deposit: u64,
+    pub available: u64,
+}
+
+/// Schedule under which purchased shares are held in escrow.
+///
+/// Each purchase is locked from the later of `start_date` and the time of purchase.
+/// Nothing unlocks before that plus `cliff`; after that shares vest linearly over
+/// `duration` seconds, or all at once when `duration` is zero.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LockupConfig {
+    pub cliff: u64,
+    pub duration: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ShareLockup {
+    pub pool: Pubkey,
+    pub holder: Pubkey,
+    pub locked: u64,
+    pub released: u64,
+    pub bump: u8,
+    /// Shares of purchases that have fully vested.
+    pub vested: u64,
+    /// Purchases still vesting.
+    #[max_len(8)]
+    pub lots: Vec<LockLot>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LockLot {
+    pub shares: u64,
+    pub locked_at: u64,
+}
+
+/// What one holder paid for, received and got back from a pool, for portfolio and tax
+/// reporting. Shares that arrive outside the program, such as plain token transfers,
+/// carry no cost basis and are not counted.
+#[account]
+#[derive(InitSpace)]
+pub struct InvestorPosition {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub holder: Pubkey,
+    pub shares: u64,
+    /// USDC paid for the shares still held.
+    pub cost_basis: u64,
+    /// Cost basis of the shares sold or redeemed.
+    pub realized_cost_basis: u64,
+    /// USDC received for the shares sold or redeemed.
+    pub principal_returned: u64,
+    pub rewards_claimed: u64,
+    pub opened_at: u64,
+    pub last_acquired_at: u64,
+    pub bump: u8,
+    /// The pool's `reward_per_share` when the position's rewards were last settled; the
+    /// shares held since have earned the increase.
+    pub reward_per_share_paid: u128,
+    /// Rewards earned up to the last settlement and not yet claimed. They move along with
+    /// the shares the holder sells or transfers.
+    pub rewards_owed: u64,
+    pub reserved: [u8; 8],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Distribution {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub rewards: u64,
+    pub claimed: u64,
+    pub bump: u8,
+    pub allocated: u64,
+    /// 0 for the pool's primary distribution, which `activate_pool` creates, or
+    /// `close_pool` for pools settled without activation.
+    pub id: u8,
+    pub reserved: [u8; 23],
+}
+
+/// `ProjectData` as laid out before accounts were versioned.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LegacyProjectData {
+    pub grand_authority: Pubkey,
+}
+
+/// `Pool` as laid out before accounts were versioned.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LegacyPool {
+    pub creator: Pubkey,
+    pub authority: Pubkey,
+    pub reference: Pubkey,
+    pub mint: Pubkey,
+    pub shares: u64,
+    pub minted: u64,
+    pub closed: bool,
+    pub bump: u8,
+    pub start_date: u64,
+    pub maturity_date: u64,
+    pub apy: u8,
+}
+
+/// `Distribution` as laid out before accounts were versioned.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LegacyDistribution {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub rewards: u64,
+    pub claimed: u64,
+    pub bump: u8,
+}
+
+impl ProjectData {
+    pub const VERSION: u8 = 1;
+
+    pub fn migrate(legacy: LegacyProjectData) -> Self {
+        Self {
+            version: ProjectData::VERSION,
+            grand_authority: legacy.grand_authority,
+            protocol_fee_bps: 0,
+            pools: 0,
+            reserved: [0; 22],
+        }
+    }
+
+    pub fn add_pools(&mut self, pools: u64) -> Result<()> {
+        self.pools = self
+            .pools
+            .checked_add(pools)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+impl Pool {
+    pub const VERSION: u8 = 1;
+    pub const MIN_DEPOSIT: u64 = 100_u64 * 1e6 as u64;
+    /// Issue price of a share in pools created directly rather than from a template.
+    pub const PAR: u64 = 1e6 as u64;
+    /// Longest a NAV may go without an update before launched pools stop pricing off it.
+    pub const MAX_NAV_AGE: u64 = 30 * 24 * 60 * 60;
+    /// Largest change NAV updates may make within a period, in basis points of the NAV the
+    /// period opened at.
+    pub const MAX_NAV_CHANGE_BPS: u64 = 1_000;
+    pub const NAV_PERIOD: u64 = 24 * 60 * 60;
+    /// Scale of `reward_per_share`, so rewards smaller than the shares outstanding still count.
+    pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+    pub fn init(
+        &mut self,
+        [creator, authority, reference, mint]: [Pubkey; 4],
+        [shares, start_date, maturity_date, share_price]: [u64; 4],
+        bump: u8,
+        apy: u8,
+        lockup: Option<LockupConfig>,
+    ) -> Result<()> {
+        self.version = Pool::VERSION;
+        self.creator = creator;
+        self.authority = authority;
+        self.reference = reference;
+        self.mint = mint;
+        // self.seed = seed;
+        self.shares = shares;
+        self.minted = 0;
+        self.status = PoolStatus::Draft;
+        self.bump = bump;
+        self.start_date = start_date;
+        self.maturity_date = maturity_date;
+        self.apy = apy;
+        self.lockup = lockup;
+        // NAV starts at the issue price as of the start date
+        self.valuator = authority;
+        self.share_price = share_price;
+        self.nav_per_share = share_price;
+        self.nav_updated_at = start_date;
+        self.nav_period_nav = self.nav_per_share;
+        self.nav_period_started_at = start_date;
+        self.assets = 0;
+        self.tranches = 0;
+        self.allocated = 0;
+        self.share_classes = 0;
+        self.class_shares = 0;
+        self.eligibility = BuyerEligibility::Open;
+        self.protocol_fee_bps = None;
+        self.id = None;
+        self.distributions = 0;
+        self.open_distributions = 0;
+        self.rewards = 0;
+        self.reward_per_share = 0;
+        Ok(())
+    }
+
+    /// Upgrades a legacy pool, pricing it at par as of its start date like a new pool.
+    pub fn migrate(legacy: LegacyPool) -> Result<Self> {
+        let mut pool: Pool = Pool {
+            version: Pool::VERSION,
+            creator: legacy.creator,
+            authority: legacy.authority,
+            reference: legacy.reference,
+            mint: legacy.mint,
+            shares: legacy.shares,
+            minted: legacy.minted,
+            status: match legacy.closed {
+                true => PoolStatus::Settled,
+                false => PoolStatus::Fundraising,
+            },
+            bump: legacy.bump,
+            start_date: legacy.start_date,
+            maturity_date: legacy.maturity_date,
+            apy: legacy.apy,
+            lockup: None,
+            valuator: legacy.authority,
+            nav_per_share: 0,
+            nav_updated_at: legacy.start_date,
+            assets: 0,
+            tranches: 0,
+            allocated: 0,
+            share_classes: 0,
+            class_shares: 0,
+            eligibility: BuyerEligibility::Open,
+            protocol_fee_bps: None,
+            id: None,
+            distributions: 0,
+            open_distributions: 0,
+            rewards: 0,
+            nav_period_nav: 0,
+            nav_period_started_at: legacy.start_date,
+            reward_per_share: 0,
+            reserve_configured: false,
+            raised: 0,
+            creator_shares: 0,
+            share_price: 0,
+            reserved: [0; 4],
+        };
+        // Legacy pools sold every share at par
+        pool.share_price = Pool::PAR;
+        pool.nav_per_share = Pool::PAR;
+        pool.nav_period_nav = pool.nav_per_share;
+        pool.raised = pool.get_principal_from_shares(pool.minted)?;
+        Ok(pool)
+    }
+
+    /// Shares a deposit buys at the pool's NAV.
+    pub fn get_shares_from_deposit(&self, deposit: u64) -> Result<u64> {
+        Ok(deposit
+            .checked_div(self.nav_per_share)
+            .ok_or(ErrorCode::MathOverflow)?)
+    }
+
+    /// Deposit the shares are worth at the pool's NAV.
+    pub fn get_deposit_from_shares(&self, shares: u64) -> Result<u64> {
+        Ok(shares
+            .checked_mul(self.nav_per_share)
+            .ok_or(ErrorCode::MathOverflow)?)
+    }
+
+    /// Principal the shares were issued for, whatever they are marked at since.
+    pub fn get_principal_from_shares(&self, shares: u64) -> Result<u64> {
+        Ok(shares
+            .checked_mul(self.share_price)
+            .ok_or(ErrorCode::MathOverflow)?)
+    }
+
+    /// A refunding holder's pro-rata share of `balance`, the USDC the pool still holds,
+    /// for `shares`.
+    pub fn get_refund(&self, balance: u64, shares: u64) -> Result<u64> {
+        let refund: u128 = (balance as u128)
+            .checked_mul(shares as u128)
+            .and_then(|value| value.checked_div(self.minted as u128))
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(u64::try_from(refund).map_err(|_| ErrorCode::MathOverflow)?)
+    }
+
+    pub fn is_valid_deposit(&self, deposit: u64) -> Result<bool> {
+        Ok(deposit.checked_rem(self.nav_per_share) == Some(0))
+    }
+
+    pub fn get_available_shares(&self) -> Result<u64> {
+        Ok(self
+            .shares
+            .checked_sub(self.minted)
+            .ok_or(ErrorCode::MathUnderflow)?)
+    }
+
+    /// Price of one share: the valuator's NAV, which starts at the issue price and must
+    /// be fresh once the pool has started.
+    pub fn get_share_price(&self, now: u64) -> Result<u64> {
+        if now >= self.start_date {
+            require!(!self.is_nav_stale(now), ErrorCode::StaleNav);
+        }
+
+        Ok(self.nav_per_share)
+    }
+
+    pub fn get_shares_value(&self, shares: u64, now: u64) -> Result<u64> {
+        Ok(shares
+            .checked_mul(self.get_share_price(now)?)
+            .ok_or(ErrorCode::MathOverflow)?)
+    }
+
+    pub fn is_nav_stale(&self, now: u64) -> bool {
+        now.saturating_sub(self.nav_updated_at) > Pool::MAX_NAV_AGE
+    }
+
+    pub fn update_nav(&mut self, nav_per_share: u64, now: u64) -> Result<()> {
+        require_gt!(nav_per_share, 0_u64, ErrorCode::InvalidNav);
+
+        // Open a new period once the current one has run its course
+        if now >= self.nav_period_started_at.saturating_add(Pool::NAV_PERIOD) {
+            self.nav_period_nav = self.nav_per_share;
+            self.nav_period_started_at = now;
+        }
+
+        let change: u128 = nav_per_share.abs_diff(self.nav_period_nav) as u128;
+        require!(
+            change * 10_000 <= self.nav_period_nav as u128 * Pool::MAX_NAV_CHANGE_BPS as u128,
+            ErrorCode::NavChangeTooLarge
+        );
+
+        self.nav_per_share = nav_per_share;
+        self.nav_updated_at = now;
+        Ok(())
+    }
+
+    pub fn add_minted(&mut self, minted: u64) -> Result<()> {
+        self.minted = self
+            .minted
+            .checked_add(minted)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Credits `amount` to the shares outstanding now. Nobody earns rewards credited while
+    /// no shares are outstanding.
+    pub fn add_rewards(&mut self, amount: u64) -> Result<()> {
+        self.rewards = self
+            .rewards
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.reward_per_share = self
+            .reward_per_share
+            .checked_add(self.get_rewards_per_share(amount)?)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Takes `amount` back from the shares outstanding now, e.g. to fund redemptions.
+    pub fn sub_rewards(&mut self, amount: u64) -> Result<()> {
+        self.rewards = self
+            .rewards
+            .checked_sub(amount)
+            .ok_or(ErrorCode::MathUnderflow)?;
+        self.reward_per_share = self
+            .reward_per_share
+            .saturating_sub(self.get_rewards_per_share(amount)?);
+        Ok(())
+    }
+
+    /// `amount` spread over the shares outstanding, rounded down so the distributions
+    /// always cover what positions earn.
+    fn get_rewards_per_share(&self, amount: u64) -> Result<u128> {
+        if self.minted == 0 {
+            return Ok(0);
+        }
+
+        Ok((amount as u128)
+            .checked_mul(Pool::REWARD_PRECISION)
+            .ok_or(ErrorCode::MathOverflow)?
+            / self.minted as u128)
+    }
+
+    /// The pool's negotiated protocol fee, or the project-wide one.
+    pub fn get_protocol_fee_bps(&self, project_fee_bps: u16) -> u16 {
+        self.protocol_fee_bps.unwrap_or(project_fee_bps)
+    }
+
+    /// Fleet pools finance several vehicles through asset tranches.
+    pub fn is_fleet(&self) -> bool {
+        self.tranches > 0
+    }
+
+    pub fn add_tranches(&mut self, tranches: u16) -> Result<()> {
+        self.tranches = self
+            .tranches
+            .checked_add(tranches)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn add_allocated(&mut self, allocated: u64) -> Result<()> {
+        self.allocated = self
+            .allocated
+            .checked_add(allocated)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn add_raised(&mut self, deposit: u64) -> Result<()> {
+        self.raised = self
+            .raised
+            .checked_add(deposit)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Moves the pool to `status` if its lifecycle allows it as of `now`.
+    pub fn transition(&mut self, status: PoolStatus, now: u64) -> Result<()> {
+        require!(
+            self.status.can_transition_to(status),
+            ErrorCode::InvalidPoolState
+        );
+        match status {
+            // A raise has failed once the pool should have started, while a draft can
+            // hand its seed back at any time
+            PoolStatus::Refunding if self.status != PoolStatus::Draft => {
+                require_gte!(now, self.start_date, ErrorCode::StartDateNotPassed)
+            }
+            PoolStatus::Matured => {
+                require_gte!(now, self.maturity_date, ErrorCode::MaturityDateNotPassed)
+            }
+            // A pool is only cancelled once every share, the seed included, has been
+            // paid back
+            PoolStatus::Cancelled => require_eq!(self.minted, 0, ErrorCode::SharesNotRefunded),
+            _ => {}
+        }
+        self.status = status;
+        Ok(())
+    }
+
+    /// Counts a new distribution as created and open.
+    pub fn add_distribution(&mut self) -> Result<()> {
+        self.distributions = self
+            .distributions
+            .checked_add(1)
+            .ok_or(ErrorCode::TooManyDistributions)?;
+        self.open_distributions = self
+            .open_distributions
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn sub_open_distributions(&mut self, distributions: u8) -> Result<()> {
+        self.open_distributions = self
+            .open_distributions
+            .checked_sub(distributions)
+            .ok_or(ErrorCode::MathUnderflow)?;
+        Ok(())
+    }
+
+    pub fn add_share_classes(&mut self, share_classes: u8) -> Result<()> {
+        self.share_classes = self
+            .share_classes
+            .checked_add(share_classes)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn add_class_shares(&mut self, shares: u64) -> Result<()> {
+        self.class_shares = self
+            .class_shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn add_assets(&mut self, assets: u16) -> Result<()> {
+        self.assets = self
+            .assets
+            .checked_add(assets)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn sub_minted(&mut self, minted: u64) -> Result<()> {
+        self.minted = self
+            .minted
+            .checked_sub(minted)
+            .ok_or(ErrorCode::MathUnderflow)?;
+        Ok(())
+    }
+    // pub fn investment_period_ended(&self) -> bool {
+    //     let clock = Clock::get();
+    //     match clock{
+    //         Ok(T)=>{
+    //             if T.unix_timestamp > self.start_date{
+    //                 return true;
+    //             }
+    //             return false
+    //         },
+    //         Err(_E)=>{
+    //             return false;
+    //         }
+    //     }
+    // if clock.unix_timestamp > self.start_date{
+    //     return true;
+    // }
+    // return false
+    // }
+
+    // pub fn maturity_date_passed(&self) -> bool {
+    //     let clock = Clock::get();
+    //     match clock{
+    //         Ok(T)=>{
+    //             if T.unix_timestamp > self.maturity_date{
+    //                 return true;
+    //             }
+    //             return false
+    //         },
+    //         Err(_E)=>{
+    //             return false;
+    //         }
+    //     }
+    // if clock.unix_timestamp > self.maturity_date{
+    //     return true;
+    // }
+    // return false
+    // }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Listing {
+    pub pool: Pubkey,
+    pub seller: Pubkey,
+    pub shares: u64,
+    pub price_per_share: u64,
+    pub bump: u8,
+}
+
+#[event]
+pub struct SharesTraded {
+    pub pool: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub shares: u64,
+    pub price_per_share: u64,
+}
+
+/// Shares bought from the pool, including the creator's seed deposit.
+#[event]
+pub struct SharesPurchased {
+    pub pool: Pubkey,
+    pub buyer: Pubkey,
+    pub shares: u64,
+    pub cost: u64,
+}
+
+#[event]
+pub struct SharesTransferred {
+    pub pool: Pubkey,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub shares: u64,
+}
+
+#[event]
+pub struct TenderSettled {
+    pub pool: Pubkey,
+    pub holder: Pubkey,
+    pub shares: u64,
+    pub payout: u64,
+}
+
+/// Shares a holder of a refunding pool burned for `refund` USDC.
+#[event]
+pub struct SharesRefunded {
+    pub pool: Pubkey,
+    pub holder: Pubkey,
+    pub shares: u64,
+    pub refund: u64,
+}
+
+/// Principal the pool authority withdrew, `reserved` of which went to the pool reserve.
+#[event]
+pub struct PrincipalWithdrawn {
+    pub pool: Pubkey,
+    pub to: Pubkey,
+    pub shares: u64,
+    pub amount: u64,
+    pub reserved: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RedemptionSource {
+    Pool,
+    Distribution,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RedemptionWindow {
+    pub pool: Pubkey,
+    pub source: RedemptionSource,
+    pub round: u64,
+    pub price_per_share: u64,
+    pub close_date: u64,
+    pub liquidity: u64,
+    pub tendered: u64,
+    pub unsettled: u64,
+    pub open: bool,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RedemptionTender {
+    pub window: Pubkey,
+    pub holder: Pubkey,
+    pub round: u64,
+    pub shares: u64,
+    pub bump: u8,
+}
+
+/// What a vehicle asset is registered with. The VIN and the title document are only
+/// stored as hashes; the documents themselves stay off-chain.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VehicleAssetDetails {
+    pub vin_hash: [u8; 32],
+    pub make: String,
+    pub model: String,
+    pub purchase_price: u64,
+    pub jurisdiction: String,
+    pub custodian: Pubkey,
+    pub title_hash: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VehicleStatus {
+    Active,
+    InMaintenance,
+    Sold,
+    WrittenOff,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct VehicleAsset {
+    pub version: u8,
+    pub creator: Pubkey,
+    pub pool: Option<Pubkey>,
+    pub vin_hash: [u8; 32],
+    #[max_len(32)]
+    pub make: String,
+    #[max_len(32)]
+    pub model: String,
+    pub purchase_price: u64,
+    #[max_len(8)]
+    pub jurisdiction: String,
+    pub custodian: Pubkey,
+    pub title_hash: [u8; 32],
+    pub status: VehicleStatus,
+    pub registered_at: u64,
+    pub bump: u8,
+    /// Index of the pool tranche financing the vehicle, which can only be one.
+    pub tranche: Option<u16>,
+    pub reserved: [u8; 29],
+}
+
+/// Waterfall applied to the gross ride revenue an operator reports for a pool.
+///
+/// Each share is in basis points of the gross; the protocol fee comes from `ProjectData`
+/// and investors receive whatever is left, rounding dust included. The maintenance share
+/// funds the pool reserve.
+#[account]
+#[derive(InitSpace)]
+pub struct RevenueConfig {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub operator: Pubkey,
+    pub driver_bps: u16,
+    pub maintenance_bps: u16,
+    pub reported: u64,
+    pub bump: u8,
+    pub reserved: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RevenueSplit {
+    pub driver: u64,
+    pub maintenance: u64,
+    pub protocol: u64,
+    pub investors: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RevenueBucket {
+    Driver,
+    Maintenance,
+    Protocol,
+    Investors,
+}
+
+#[event]
+pub struct RevenueReported {
+    pub pool: Pubkey,
+    pub operator: Pubkey,
+    pub gross: u64,
+    pub driver: u64,
+    pub maintenance: u64,
+    pub protocol: u64,
+    pub investors: u64,
+}
 
-    #[account(
-        init,
-        payer = creator,
-        mint::decimals = 0,
-        mint::authority = pool,
-        mint::freeze_authority = pool,
-        token::token_program = token_program,
-        seeds = [b"mint", pool.key().as_ref()],
-        bump,
-    )]
-    pub mint: Account<'info, token::Mint>,
+/// USDC set aside for a pool's vehicles, held in the pool's maintenance bucket.
+///
+/// Funded by the maintenance share of reported revenue and by `principal_bps` of every
+/// principal withdrawal. Investor distributions wait until the reserve holds `target`.
+#[account]
+#[derive(InitSpace)]
+pub struct PoolReserve {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub principal_bps: u16,
+    pub target: u64,
+    pub funded: u64,
+    pub spent: u64,
+    pub expenses: u64,
+    pub bump: u8,
+    pub reserved: [u8; 32],
+}
 
-    #[account(
-        init_if_needed,
-        payer = creator,
-        associated_token::mint = mint,
-        associated_token::authority = creator,
-        associated_token::token_program = token_program,
-    )]
-    pub creator_mint_account: Account<'info, token::TokenAccount>,
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExpenseCategory {
+    Maintenance,
+    Repair,
+    Insurance,
+    Registration,
+    Tracking,
+}
 
-    /// CHECKS: The USDC mint.
-    #[account(address = CUSTOM_USDC_MINT)]
-    pub usdc_mint: Account<'info, token::Mint>,
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReceiptedExpense {
+    pub category: ExpenseCategory,
+    pub amount: u64,
+    pub recipient: Pubkey,
+    pub receipt_hash: [u8; 32],
+}
 
-    #[account(
-        mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = creator,
-    )]
-    pub creator_usdc_account: Account<'info, token::TokenAccount>,
+#[account]
+#[derive(InitSpace)]
+pub struct ReserveExpense {
+    pub reserve: Pubkey,
+    pub index: u64,
+    pub expense: ReceiptedExpense,
+    pub spent_at: u64,
+    pub bump: u8,
+}
 
-    #[account(
-        init_if_needed,
-        payer = creator,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = pool,
-        associated_token::token_program = token_program,
-    )]
-    pub pool_usdc_account: Account<'info, token::TokenAccount>,
+/// The terms a creator launches a recurring series of pools on. Each pool issues its
+/// shares at the template's share price, which the creator's seed deposit is rounded to.
+#[account]
+#[derive(InitSpace)]
+pub struct PoolTemplate {
+    pub version: u8,
+    pub creator: Pubkey,
+    pub reference: Pubkey,
+    pub authority: Pubkey,
+    #[max_len(24)]
+    pub name: String,
+    #[max_len(10)]
+    pub symbol: String,
+    #[max_len(160)]
+    pub uri_pattern: String,
+    /// USDC a share of each pool costs until the valuator moves its NAV.
+    pub share_price: u64,
+    pub term: u64,
+    pub apy: u8,
+    pub lockup: Option<LockupConfig>,
+    pub eligibility: BuyerEligibility,
+    /// Only the grand authority may set this.
+    pub protocol_fee_bps: Option<u16>,
+    pub pools: u32,
+    pub bump: u8,
+    pub reserved: [u8; 32],
+}
 
-    /// CHECKS: The metadata account.
-    #[account(
-        mut,
-        seeds = [b"metadata", metadata_program.key().as_ref(), mint.key().as_ref()],
-        bump,
-        seeds::program = metadata_program,
-    )]
-    pub metadata: UncheckedAccount<'info>,
+/// What a pool template is created or updated with. `uri_pattern` may contain `{}`,
+/// replaced by each pool's number within the series.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PoolTemplateTerms {
+    pub authority: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri_pattern: String,
+    pub share_price: u64,
+    pub term: u64,
+    pub apy: u8,
+    pub lockup: Option<LockupConfig>,
+    pub eligibility: BuyerEligibility,
+}
 
-    /// CHECKS: The metadata program.
-    #[account(address = MPL_TOKEN_METADATA_ID)]
-    pub metadata_program: UncheckedAccount<'info>,
+#[event]
+pub struct PoolCreatedFromTemplate {
+    pub template: Pubkey,
+    pub pool: Pubkey,
+    pub number: u32,
+}
 
-    pub rent: Sysvar<'info, Rent>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, token::Token>,
-    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+/// Number of pools a creator has registered, which is also the index of their next one.
+#[account]
+#[derive(InitSpace)]
+pub struct CreatorPools {
+    pub version: u8,
+    pub creator: Pubkey,
+    pub pools: u64,
+    pub bump: u8,
+    pub reserved: [u8; 32],
 }
 
-#[derive(Accounts)]
-pub struct BuyShares<'info> {
-    #[account(mut)]
-    pub buyer: Signer<'info>,
+/// Points a registry slot at a pool. The same entry is written under the pool's global
+/// id and under its index in the creator's list, so either can be walked without a scan.
+#[account]
+#[derive(InitSpace)]
+pub struct PoolRegistryEntry {
+    pub version: u8,
+    pub id: u64,
+    pub creator: Pubkey,
+    pub creator_index: u64,
+    pub pool: Pubkey,
+    pub bump: u8,
+    pub reserved: [u8; 32],
+}
 
-    #[account(
-        mut,
-        constraint = !pool.closed @ ErrorCode::PoolClosed,
-        seeds = [b"pool", pool.reference.as_ref()],
-        bump = pool.bump,
-    )]
-    pub pool: Account<'info, Pool>,
+/// A class of pool shares with its own mint, coupon and place in the payout waterfall.
+#[account]
+#[derive(InitSpace)]
+pub struct ShareClass {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub class_id: u8,
+    pub mint: Pubkey,
+    pub shares: u64,
+    pub minted: u64,
+    pub apy: u8,
+    pub eligibility: BuyerEligibility,
+    pub rewards: u64,
+    pub claimed: u64,
+    pub bump: u8,
+    /// Rewards allocated per class share, scaled by `Pool::REWARD_PRECISION`.
+    pub reward_per_share: u128,
+    pub reserved: [u8; 32],
+}
 
-    #[account(
-        mut,
-        token::token_program = token_program,
-        seeds = [b"mint", pool.key().as_ref()],
-        bump,
-    )]
-    pub mint: Account<'info, token::Mint>,
+/// A holder's class shares and the class rewards they have earned and claimed. Class
+/// tokens stay frozen in the holder's account, so the position tracks them exactly.
+#[account]
+#[derive(InitSpace)]
+pub struct ClassPosition {
+    pub version: u8,
+    pub share_class: Pubkey,
+    pub holder: Pubkey,
+    pub shares: u64,
+    pub rewards_claimed: u64,
+    /// The class `reward_per_share` the position has been credited up to.
+    pub reward_per_share_paid: u128,
+    /// Rewards earned and not yet claimed as of `reward_per_share_paid`.
+    pub rewards_owed: u64,
+    pub bump: u8,
+    pub reserved: [u8; 32],
+}
 
-    #[account(
-        init_if_needed,
-        payer = buyer,
-        associated_token::mint = mint,
-        associated_token::authority = buyer,
-        associated_token::token_program = token_program,
-    )]
-    pub buyer_mint_account: Account<'info, token::TokenAccount>,
+/// Who may buy into a pool or share class.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuyerEligibility {
+    Open,
+    /// Only the pool creator, typically holding the junior class.
+    Creator,
+    /// Buyers co-signed by the pool authority.
+    Approved,
+}
 
-    /// CHECKS: The USDC mint.
-    #[account(address = CUSTOM_USDC_MINT)]
-    pub usdc_mint: Account<'info, token::Mint>,
+/// One vehicle financed by a fleet pool: how much of the pool principal it may draw,
+/// how much it drew, and what it has paid back into the pool's distribution.
+#[account]
+#[derive(InitSpace)]
+pub struct AssetTranche {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub index: u16,
+    pub vehicle_asset: Pubkey,
+    pub principal_allocation: u64,
+    pub deployed: u64,
+    pub repaid: u64,
+    pub bump: u8,
+    pub reserved: [u8; 32],
+}
 
-    #[account(
-        mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = buyer,
-        associated_token::token_program = token_program,
-    )]
-    pub buyer_usdc_account: Account<'info, token::TokenAccount>,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TrancheSummary {
+    pub index: u16,
+    pub vehicle_asset: Pubkey,
+    pub principal_allocation: u64,
+    pub deployed: u64,
+    pub repaid: u64,
+    pub undeployed: u64,
+}
 
-    #[account(
-        mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = pool,
-        associated_token::token_program = token_program,
-    )]
-    pub pool_usdc_account: Account<'info, token::TokenAccount>,
+impl LockupConfig {
+    pub fn get_vested_shares(&self, locked_at: u64, locked: u64, now: u64) -> u64 {
+        let unlock_date: u64 = locked_at.saturating_add(self.cliff);
+        if now < unlock_date {
+            return 0;
+        }
 
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, token::Token>,
-    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+        let elapsed: u64 = now - unlock_date;
+        if self.duration == 0 || elapsed >= self.duration {
+            return locked;
+        }
+
+        (locked as u128 * elapsed as u128 / self.duration as u128) as u64
+    }
+}
+
+impl ShareLockup {
+    pub const MAX_LOTS: usize = 8;
+
+    pub fn set(&mut self, pool: Pubkey, holder: Pubkey, bump: u8) -> Result<()> {
+        self.pool = pool;
+        self.holder = holder;
+        self.bump = bump;
+
+        Ok(())
+    }
+
+    pub fn get_vested_shares(&self, lockup: &LockupConfig, now: u64) -> u64 {
+        self.lots.iter().fold(self.vested, |vested, lot| {
+            vested.saturating_add(lockup.get_vested_shares(lot.locked_at, lot.shares, now))
+        })
+    }
+
+    pub fn get_releasable(&self, vested: u64) -> u64 {
+        vested.saturating_sub(self.released)
+    }
+
+    /// Locks `shares` from `locked_at`, folding purchases that finished vesting into
+    /// `vested` to make room.
+    pub fn add_locked(
+        &mut self,
+        lockup: &LockupConfig,
+        shares: u64,
+        locked_at: u64,
+        now: u64,
+    ) -> Result<()> {
+        self.locked = self
+            .locked
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let mut vested: u64 = self.vested;
+        self.lots.retain(|lot| {
+            let done: bool = lockup.get_vested_shares(lot.locked_at, lot.shares, now) == lot.shares;
+            if done {
+                vested = vested.saturating_add(lot.shares);
+            }
+            !done
+        });
+        self.vested = vested;
+
+        match self.lots.iter_mut().find(|lot| lot.locked_at == locked_at) {
+            Some(lot) => {
+                lot.shares = lot
+                    .shares
+                    .checked_add(shares)
+                    .ok_or(ErrorCode::MathOverflow)?
+            }
+            None => {
+                require_gt!(
+                    ShareLockup::MAX_LOTS,
+                    self.lots.len(),
+                    ErrorCode::TooManyLockups
+                );
+                self.lots.push(LockLot { shares, locked_at });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn add_released(&mut self, shares: u64) -> Result<()> {
+        self.released = self
+            .released
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+impl InvestorPosition {
+    pub const VERSION: u8 = 1;
+
+    pub fn set(&mut self, pool: Pubkey, holder: Pubkey, bump: u8) -> Result<()> {
+        self.version = InvestorPosition::VERSION;
+        self.pool = pool;
+        self.holder = holder;
+        self.bump = bump;
+
+        Ok(())
+    }
+
+    /// Records shares bought or received for `cost`.
+    pub fn add_shares(&mut self, shares: u64, cost: u64, now: u64) -> Result<()> {
+        self.shares = self
+            .shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.cost_basis = self
+            .cost_basis
+            .checked_add(cost)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if self.opened_at == 0 {
+            self.opened_at = now;
+        }
+        self.last_acquired_at = now;
+        Ok(())
+    }
+
+    /// Removes shares at their average cost and returns the cost basis they carried.
+    /// Untracked shares leave at no cost.
+    pub fn sub_shares(&mut self, shares: u64) -> Result<u64> {
+        let tracked: u64 = shares.min(self.shares);
+        if tracked == 0 {
+            return Ok(0);
+        }
+        let cost_basis: u64 =
+            (self.cost_basis as u128 * tracked as u128 / self.shares as u128) as u64;
+        self.shares -= tracked;
+        self.cost_basis = self
+            .cost_basis
+            .checked_sub(cost_basis)
+            .ok_or(ErrorCode::MathUnderflow)?;
+        Ok(cost_basis)
+    }
+
+    pub fn add_realized(&mut self, cost_basis: u64, proceeds: u64) -> Result<()> {
+        self.realized_cost_basis = self
+            .realized_cost_basis
+            .checked_add(cost_basis)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.principal_returned = self
+            .principal_returned
+            .checked_add(proceeds)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn add_rewards_claimed(&mut self, amount: u64) -> Result<()> {
+        self.rewards_claimed = self
+            .rewards_claimed
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Rewards earned and not claimed as of the pool's `reward_per_share`.
+    pub fn get_pending_rewards(&self, reward_per_share: u128) -> Result<u64> {
+        let earned: u128 = (self.shares as u128)
+            .checked_mul(reward_per_share.saturating_sub(self.reward_per_share_paid))
+            .ok_or(ErrorCode::MathOverflow)?
+            / Pool::REWARD_PRECISION;
+        let earned: u64 = u64::try_from(earned).map_err(|_| ErrorCode::MathOverflow)?;
+        Ok(self
+            .rewards_owed
+            .checked_add(earned)
+            .ok_or(ErrorCode::MathOverflow)?)
+    }
+
+    /// Books what the shares held so far have earned. Call before the shares change.
+    pub fn settle_rewards(&mut self, reward_per_share: u128) -> Result<()> {
+        self.rewards_owed = self.get_pending_rewards(reward_per_share)?;
+        self.reward_per_share_paid = reward_per_share;
+        Ok(())
+    }
+
+    /// Pays `amount` out of the settled rewards.
+    pub fn claim_rewards(&mut self, amount: u64) -> Result<()> {
+        self.rewards_owed = self
+            .rewards_owed
+            .checked_sub(amount)
+            .ok_or(ErrorCode::ExceedsClaimableRewards)?;
+        self.add_rewards_claimed(amount)
+    }
+
+    /// Removes the share of the owed rewards carried by `shares` about to leave the
+    /// position and returns it. Call after `settle_rewards` and before `sub_shares`.
+    pub fn take_rewards_owed(&mut self, shares: u64) -> Result<u64> {
+        if self.shares == 0 {
+            return Ok(0);
+        }
+        let tracked: u64 = shares.min(self.shares);
+        let rewards: u128 = (self.rewards_owed as u128)
+            .checked_mul(tracked as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            / self.shares as u128;
+        let rewards: u64 = u64::try_from(rewards).map_err(|_| ErrorCode::MathOverflow)?;
+        self.rewards_owed = self
+            .rewards_owed
+            .checked_sub(rewards)
+            .ok_or(ErrorCode::MathUnderflow)?;
+        Ok(rewards)
+    }
+
+    pub fn add_rewards_owed(&mut self, amount: u64) -> Result<()> {
+        self.rewards_owed = self
+            .rewards_owed
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+impl Listing {
+    pub fn set(
+        &mut self,
+        pool: Pubkey,
+        seller: Pubkey,
+        price_per_share: u64,
+        bump: u8,
+    ) -> Result<()> {
+        self.pool = pool;
+        self.seller = seller;
+        self.price_per_share = price_per_share;
+        self.bump = bump;
+
+        Ok(())
+    }
+
+    pub fn get_price(&self, shares: u64) -> Result<u64> {
+        Ok(shares
+            .checked_mul(self.price_per_share)
+            .ok_or(ErrorCode::MathOverflow)?)
+    }
+
+    pub fn add_shares(&mut self, shares: u64) -> Result<()> {
+        self.shares = self
+            .shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn sub_shares(&mut self, shares: u64) -> Result<()> {
+        self.shares = self
+            .shares
+            .checked_sub(shares)
+            .ok_or(ErrorCode::MathUnderflow)?;
+        Ok(())
+    }
+}
+
+impl RedemptionWindow {
+    pub fn open(
+        &mut self,
+        pool: Pubkey,
+        source: RedemptionSource,
+        price_per_share: u64,
+        close_date: u64,
+        liquidity: u64,
+        bump: u8,
+    ) -> Result<()> {
+        self.pool = pool;
+        self.source = source;
+        self.round = self.round.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        self.price_per_share = price_per_share;
+        self.close_date = close_date;
+        self.liquidity = liquidity;
+        self.tendered = 0;
+        self.unsettled = 0;
+        self.open = true;
+        self.bump = bump;
+
+        Ok(())
+    }
+
+    /// Shares filled out of a tender, scaled down pro-rata when the window's tenders
+    /// are worth more than its liquidity.
+    pub fn get_filled_shares(&self, shares: u64) -> u64 {
+        let demand: u128 = self.tendered as u128 * self.price_per_share as u128;
+        if demand <= self.liquidity as u128 {
+            return shares;
+        }
+
+        (shares as u128 * self.liquidity as u128 / demand) as u64
+    }
+
+    pub fn add_tendered(&mut self, shares: u64) -> Result<()> {
+        self.tendered = self
+            .tendered
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.unsettled = self
+            .unsettled
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn sub_unsettled(&mut self, shares: u64) -> Result<()> {
+        self.unsettled = self
+            .unsettled
+            .checked_sub(shares)
+            .ok_or(ErrorCode::MathUnderflow)?;
+        Ok(())
+    }
 }
 
-#[derive(Accounts)]
-pub struct Distribute<'info> {
-    // #[account(
-    //     mut,
-    //     address = pool.authority,
-    // )]
-    // authority: Signer<'info>,
-    #[account(mut,)]
-    signer: Signer<'info>,
+impl RedemptionTender {
+    pub fn set(&mut self, window: Pubkey, holder: Pubkey, round: u64, bump: u8) -> Result<()> {
+        self.window = window;
+        self.holder = holder;
+        self.round = round;
+        self.bump = bump;
 
-    #[account(address = distribution.authority)]
-    distribution_authority: Signer<'info>,
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        constraint = pool.closed @ ErrorCode::PoolClosed,
-        seeds = [b"pool", pool.reference.as_ref()],
-        bump = pool.bump,
-    )]
-    pub pool: Account<'info, Pool>,
-    #[account(
-        mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = signer,
-    )]
-    pub signer_usdc_account: Account<'info, token::TokenAccount>,
-    #[account(
-        mut,
-        seeds = [b"distribution", pool.key().as_ref()],
-        bump,
-    )]
-    pub distribution: Account<'info, Distribution>,
+    pub fn add_shares(&mut self, shares: u64) -> Result<()> {
+        self.shares = self
+            .shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
 
-    /// CHECKS: The USDC mint.
-    #[account(address = CUSTOM_USDC_MINT)]
-    pub usdc_mint: Account<'info, token::Mint>,
+impl Distribution {
+    pub const VERSION: u8 = 1;
+
+    pub fn migrate(legacy: LegacyDistribution) -> Self {
+        Self {
+            version: Distribution::VERSION,
+            pool: legacy.pool,
+            authority: legacy.authority,
+            rewards: legacy.rewards,
+            claimed: legacy.claimed,
+            bump: legacy.bump,
+            allocated: 0,
+            id: 0,
+            reserved: [0; 23],
+        }
+    }
 
-    #[account(
-        mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = distribution,
-        associated_token::token_program = token_program,
-    )]
-    pub distribution_usdc_account: Account<'info, token::TokenAccount>,
+    pub fn set(&mut self, pool: Pubkey, authority: Pubkey, id: u8, bump: u8) -> Result<()> {
+        self.version = Distribution::VERSION;
+        self.pool = pool;
+        self.authority = authority;
+        self.id = id;
+        self.bump = bump;
 
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, token::Token>,
-    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct ClaimRewards<'info> {
-    #[account(mut)]
-    holder: Signer<'info>,
+    pub fn set_authority(&mut self, authority: Pubkey) {
+        self.authority = authority;
+    }
 
-    #[account(address = distribution.authority)]
-    authority: Signer<'info>,
+    /// A holder's `pending` rewards, capped by what this distribution still holds.
+    pub fn get_claimable_rewards(&self, pending: u64) -> Result<u64> {
+        Ok(pending.min(self.get_unclaimed_rewards()?))
+    }
 
-    #[account(
-        seeds = [b"pool", pool.reference.as_ref()],
-        bump = pool.bump,
-    )]
-    pub pool: Account<'info, Pool>,
+    pub fn get_unclaimed_rewards(&self) -> Result<u64> {
+        Ok(self
+            .rewards
+            .checked_sub(self.claimed)
+            .ok_or(ErrorCode::MathUnderflow)?)
+    }
 
-    #[account(
-        mut,
-        seeds = [b"distribution", pool.key().as_ref()],
-        bump = distribution.bump,
-    )]
-    pub distribution: Account<'info, Distribution>,
+    pub fn add_rewards(&mut self, amount: u64) -> Result<()> {
+        self.rewards = self
+            .rewards
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
 
-    #[account(
-        token::token_program = token_program,
-        seeds = [b"mint", pool.key().as_ref()],
-        bump,
-    )]
-    pub mint: Account<'info, token::Mint>,
+    pub fn sub_rewards(&mut self, amount: u64) -> Result<()> {
+        self.rewards = self
+            .rewards
+            .checked_sub(amount)
+            .ok_or(ErrorCode::MathUnderflow)?;
+        Ok(())
+    }
 
-    #[account(
-        associated_token::mint = mint,
-        associated_token::authority = holder,
-        associated_token::token_program = token_program,
-    )]
-    pub holder_mint_account: Account<'info, token::TokenAccount>,
+    /// Rewards not yet run down the share class waterfall.
+    pub fn get_unallocated_rewards(&self) -> Result<u64> {
+        Ok(self
+            .rewards
+            .checked_sub(self.allocated)
+            .ok_or(ErrorCode::MathUnderflow)?)
+    }
 
-    /// CHECKS: The USDC mint.
-    #[account(address = CUSTOM_USDC_MINT)]
-    pub usdc_mint: Account<'info, token::Mint>,
+    pub fn add_allocated(&mut self, amount: u64) -> Result<()> {
+        self.allocated = self
+            .allocated
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = distribution,
-        associated_token::token_program = token_program,
-    )]
-    pub distribution_usdc_account: Account<'info, token::TokenAccount>,
+    pub fn add_claimed(&mut self, amount: u64) -> Result<()> {
+        self.claimed = self
+            .claimed
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = holder,
-        associated_token::token_program = token_program,
-    )]
-    pub holder_usdc_account: Account<'info, token::TokenAccount>,
+    pub fn sub_claimed(&mut self, amount: u64) -> Result<()> {
+        self.claimed = self
+            .claimed
+            .checked_sub(amount)
+            .ok_or(ErrorCode::MathUnderflow)?;
+        Ok(())
+    }
+}
 
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, token::Token>,
-    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+impl PoolTemplateTerms {
+    // Leaves room in the 32 byte Metaplex name for the " #<number>" suffix
+    pub const MAX_NAME_LEN: usize = 24;
+    pub const MAX_SYMBOL_LEN: usize = 10;
+    pub const MAX_URI_PATTERN_LEN: usize = 160;
+
+    pub fn is_valid(&self) -> bool {
+        self.name.len() <= Self::MAX_NAME_LEN
+            && self.symbol.len() <= Self::MAX_SYMBOL_LEN
+            && self.uri_pattern.len() <= Self::MAX_URI_PATTERN_LEN
+            && self.share_price > 0
+            && self.term > 0
+    }
 }
 
-#[derive(Accounts)]
-pub struct WithdrawFromPool<'info> {
-    #[account(
-        mut,
-        address = pool.authority,
-    )]
-    authority: Signer<'info>,
+impl PoolTemplate {
+    pub const VERSION: u8 = 1;
 
-    #[account(
-        mut,
-        constraint = !pool.closed @ ErrorCode::PoolClosed,
-        seeds = [b"pool", pool.reference.as_ref()],
-        bump = pool.bump,
-    )]
-    pub pool: Account<'info, Pool>,
+    pub fn set(
+        &mut self,
+        creator: Pubkey,
+        reference: Pubkey,
+        terms: PoolTemplateTerms,
+        bump: u8,
+    ) -> Result<()> {
+        self.version = PoolTemplate::VERSION;
+        self.creator = creator;
+        self.reference = reference;
+        self.set_terms(terms);
+        self.protocol_fee_bps = None;
+        self.pools = 0;
+        self.bump = bump;
 
-    /// CHECKS: The USDC mint.
-    #[account(address = CUSTOM_USDC_MINT)]
-    pub usdc_mint: Account<'info, token::Mint>,
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = pool,
-        associated_token::token_program = token_program,
-    )]
-    pub pool_usdc_account: Account<'info, token::TokenAccount>,
+    pub fn set_terms(&mut self, terms: PoolTemplateTerms) {
+        self.authority = terms.authority;
+        self.name = terms.name;
+        self.symbol = terms.symbol;
+        self.uri_pattern = terms.uri_pattern;
+        self.share_price = terms.share_price;
+        self.term = terms.term;
+        self.apy = terms.apy;
+        self.lockup = terms.lockup;
+        self.eligibility = terms.eligibility;
+    }
 
-    #[account(mut)]
-    pub to_usdc_account: Account<'info, token::TokenAccount>,
+    pub fn get_pool_name(&self, number: u32) -> String {
+        format!("{} #{}", self.name, number)
+    }
 
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, token::Token>,
-    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+    pub fn get_pool_uri(&self, number: u32) -> String {
+        self.uri_pattern.replace("{}", &number.to_string())
+    }
+
+    pub fn add_pools(&mut self, pools: u32) -> Result<()> {
+        self.pools = self
+            .pools
+            .checked_add(pools)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
 }
 
-#[derive(Accounts)]
-pub struct ClosePool<'info> {
-    #[account(
-        mut,
-        address = pool.authority,
-    )]
-    pub authority: Signer<'info>,
+impl ShareClass {
+    pub const VERSION: u8 = 1;
 
-    #[account(
-        mut,
-        constraint = !pool.closed @ ErrorCode::PoolClosed,
-        seeds = [b"pool", pool.reference.as_ref()],
-        bump = pool.bump
-    )]
-    pub pool: Account<'info, Pool>,
+    #[allow(clippy::too_many_arguments)]
+    pub fn set(
+        &mut self,
+        pool: Pubkey,
+        class_id: u8,
+        mint: Pubkey,
+        shares: u64,
+        apy: u8,
+        eligibility: BuyerEligibility,
+        bump: u8,
+    ) -> Result<()> {
+        self.version = ShareClass::VERSION;
+        self.pool = pool;
+        self.class_id = class_id;
+        self.mint = mint;
+        self.shares = shares;
+        self.apy = apy;
+        self.eligibility = eligibility;
+        self.bump = bump;
 
-    #[account(
-        init_if_needed,
-        payer = authority,
-        space = 8 + std::mem::size_of::<Distribution>(),
-        seeds = [b"distribution", pool.key().as_ref()],
-        bump,
-    )]
-    pub distribution: Account<'info, Distribution>,
+        Ok(())
+    }
 
-    distribution_authority: Signer<'info>,
-    /// CHECKS: The USDC mint.
-    #[account(address = CUSTOM_USDC_MINT)]
-    pub usdc_mint: Account<'info, token::Mint>,
+    pub fn get_available_shares(&self) -> Result<u64> {
+        Ok(self
+            .shares
+            .checked_sub(self.minted)
+            .ok_or(ErrorCode::MathUnderflow)?)
+    }
 
-    #[account(
-        init_if_needed,
-        payer = authority,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = distribution,
-        associated_token::token_program = token_program,
-    )]
-    pub distribution_usdc_account: Account<'info, token::TokenAccount>,
+    /// What the class is owed before any junior class is paid: its principal as issued
+    /// plus its coupon.
+    pub fn get_entitlement(&self, pool: &Pool) -> Result<u64> {
+        let principal: u64 = pool.get_principal_from_shares(self.minted)?;
+        let coupon: u64 = principal
+            .checked_mul(self.apy as u64)
+            .ok_or(ErrorCode::MathOverflow)?
+            / 100;
+        Ok(principal
+            .checked_add(coupon)
+            .ok_or(ErrorCode::MathOverflow)?)
+    }
 
-    pub token_program: Program<'info, token::Token>,
-    pub system_program: Program<'info, System>,
-    pub associated_token_program: Program<'info, associated_token::AssociatedToken>,
+    pub fn get_outstanding(&self, pool: &Pool) -> Result<u64> {
+        Ok(self.get_entitlement(pool)?.saturating_sub(self.rewards))
+    }
+
+    pub fn get_unclaimed_rewards(&self) -> Result<u64> {
+        Ok(self
+            .rewards
+            .checked_sub(self.claimed)
+            .ok_or(ErrorCode::MathUnderflow)?)
+    }
+
+    pub fn add_minted(&mut self, shares: u64) -> Result<()> {
+        self.minted = self
+            .minted
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn sub_minted(&mut self, shares: u64) -> Result<()> {
+        self.minted = self
+            .minted
+            .checked_sub(shares)
+            .ok_or(ErrorCode::MathUnderflow)?;
+        Ok(())
+    }
+
+    /// Credits `amount` to the class shares outstanding now.
+    pub fn add_rewards(&mut self, amount: u64) -> Result<()> {
+        self.rewards = self
+            .rewards
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if self.minted > 0 {
+            let reward_per_share: u128 = (amount as u128)
+                .checked_mul(Pool::REWARD_PRECISION)
+                .ok_or(ErrorCode::MathOverflow)?
+                / self.minted as u128;
+            self.reward_per_share = self
+                .reward_per_share
+                .checked_add(reward_per_share)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+        Ok(())
+    }
+
+    pub fn add_claimed(&mut self, amount: u64) -> Result<()> {
+        self.claimed = self
+            .claimed
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
 }
 
-#[derive(Accounts)]
-pub struct ClaimDeposit<'info> {
-    #[account(mut)]
-    holder: Signer<'info>,
+impl ClassPosition {
+    pub const VERSION: u8 = 1;
 
-    #[account(
-        constraint = pool.closed @ ErrorCode::PoolNotClosed,
-        seeds = [b"pool", pool.reference.as_ref()],
-        bump = pool.bump,
-    )]
-    pub pool: Account<'info, Pool>,
+    pub fn set(&mut self, share_class: Pubkey, holder: Pubkey, bump: u8) {
+        self.version = ClassPosition::VERSION;
+        self.share_class = share_class;
+        self.holder = holder;
+        self.bump = bump;
+    }
 
-    #[account(
-        mut,
-        token::token_program = token_program,
-        seeds = [b"mint", pool.key().as_ref()],
-        bump,
-    )]
-    pub mint: Account<'info, token::Mint>,
+    pub fn add_shares(&mut self, shares: u64) -> Result<()> {
+        self.shares = self
+            .shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn sub_shares(&mut self, shares: u64) -> Result<()> {
+        self.shares = self
+            .shares
+            .checked_sub(shares)
+            .ok_or(ErrorCode::MathUnderflow)?;
+        Ok(())
+    }
+
+    /// Rewards earned and not claimed as of the class `reward_per_share`.
+    pub fn get_pending_rewards(&self, reward_per_share: u128) -> Result<u64> {
+        let earned: u128 = (self.shares as u128)
+            .checked_mul(reward_per_share.saturating_sub(self.reward_per_share_paid))
+            .ok_or(ErrorCode::MathOverflow)?
+            / Pool::REWARD_PRECISION;
+        let earned: u64 = u64::try_from(earned).map_err(|_| ErrorCode::MathOverflow)?;
+        Ok(self
+            .rewards_owed
+            .checked_add(earned)
+            .ok_or(ErrorCode::MathOverflow)?)
+    }
 
-    #[account(
-        mut,
-        associated_token::mint = mint,
-        associated_token::authority = holder,
-        associated_token::token_program = token_program,
-    )]
-    pub holder_mint_account: Account<'info, token::TokenAccount>,
+    /// Books what the class shares held so far have earned. Call before the shares change.
+    pub fn settle_rewards(&mut self, reward_per_share: u128) -> Result<()> {
+        self.rewards_owed = self.get_pending_rewards(reward_per_share)?;
+        self.reward_per_share_paid = reward_per_share;
+        Ok(())
+    }
 
-    /// CHECKS: The USDC mint.
-    #[account(address = CUSTOM_USDC_MINT)]
-    pub usdc_mint: Account<'info, token::Mint>,
+    /// Pays `amount` out of the settled rewards.
+    pub fn claim_rewards(&mut self, amount: u64) -> Result<()> {
+        self.rewards_owed = self
+            .rewards_owed
+            .checked_sub(amount)
+            .ok_or(ErrorCode::ExceedsClassRewards)?;
+        self.add_rewards_claimed(amount)
+    }
 
-    #[account(
-        mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = pool,
-        associated_token::token_program = token_program,
-    )]
-    pub pool_usdc_account: Account<'info, token::TokenAccount>,
+    pub fn add_rewards_claimed(&mut self, amount: u64) -> Result<()> {
+        self.rewards_claimed = self
+            .rewards_claimed
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
 
-    #[account(
-        mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = holder,
-        associated_token::token_program = token_program,
-    )]
-    pub holder_usdc_account: Account<'info, token::TokenAccount>,
+impl BuyerEligibility {
+    pub fn is_eligible(&self, buyer: Pubkey, creator: Pubkey, approved: bool) -> bool {
+        match self {
+            BuyerEligibility::Open => true,
+            BuyerEligibility::Creator => buyer == creator,
+            BuyerEligibility::Approved => approved,
+        }
+    }
+}
 
-    pub token_program: Program<'info, token::Token>,
+impl CreatorPools {
+    pub const VERSION: u8 = 1;
+
+    pub fn set(&mut self, creator: Pubkey, bump: u8) {
+        self.version = CreatorPools::VERSION;
+        self.creator = creator;
+        self.bump = bump;
+    }
+
+    pub fn add_pools(&mut self, pools: u64) -> Result<()> {
+        self.pools = self
+            .pools
+            .checked_add(pools)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
 }
 
-#[derive(Accounts)]
-pub struct ClosePoolAccounts<'info> {
-    #[account(
-        mut,
-        address = pool.authority,
-    )]
-    pub authority: Signer<'info>,
+impl PoolRegistryEntry {
+    pub const VERSION: u8 = 1;
 
-    #[account(
-        mut,
-        constraint = pool.closed @ ErrorCode::PoolNotClosed,
-        close = authority,
-        seeds = [b"pool", pool.reference.as_ref()],
-        bump = pool.bump
-    )]
-    pub pool: Account<'info, Pool>,
+    pub fn set(&mut self, id: u64, creator: Pubkey, creator_index: u64, pool: Pubkey, bump: u8) {
+        self.version = PoolRegistryEntry::VERSION;
+        self.id = id;
+        self.creator = creator;
+        self.creator_index = creator_index;
+        self.pool = pool;
+        self.bump = bump;
+    }
+}
 
-    #[account(
-        mut,
-        constraint = distribution.rewards == distribution.claimed @ ErrorCode::UnclaimedDistributionRewards,
-        close = authority,
-        seeds = [b"distribution", pool.key().as_ref()],
-        bump = distribution.bump
-    )]
-    pub distribution: Account<'info, Distribution>,
+impl AssetTranche {
+    pub const VERSION: u8 = 1;
 
-    #[account(
-        mut,
-        token::token_program = token_program,
-        seeds = [b"mint", pool.key().as_ref()],
-        bump,
-    )]
-    pub mint: Account<'info, token::Mint>,
+    pub fn set(
+        &mut self,
+        pool: Pubkey,
+        index: u16,
+        vehicle_asset: Pubkey,
+        principal_allocation: u64,
+        bump: u8,
+    ) -> Result<()> {
+        self.version = AssetTranche::VERSION;
+        self.pool = pool;
+        self.index = index;
+        self.vehicle_asset = vehicle_asset;
+        self.principal_allocation = principal_allocation;
+        self.bump = bump;
 
-    /// CHECKS: The USDC mint.
-    #[account(address = CUSTOM_USDC_MINT)]
-    pub usdc_mint: Account<'info, token::Mint>,
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        constraint = pool_usdc_account.amount == 0 @ ErrorCode::NonZeroPoolUSDCBalance,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = pool,
-        associated_token::token_program = token_program,
-    )]
-    pub pool_usdc_account: Account<'info, token::TokenAccount>,
+    pub fn get_undeployed(&self) -> Result<u64> {
+        Ok(self
+            .principal_allocation
+            .checked_sub(self.deployed)
+            .ok_or(ErrorCode::MathUnderflow)?)
+    }
 
-    #[account(
-        mut,
-        constraint = distribution_usdc_account.amount == 0 @ ErrorCode::NonZeroDistributionUSDCBalance,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = distribution,
-        associated_token::token_program = token_program,
-    )]
-    pub distribution_usdc_account: Account<'info, token::TokenAccount>,
+    pub fn add_deployed(&mut self, amount: u64) -> Result<()> {
+        require_gte!(
+            self.get_undeployed()?,
+            amount,
+            ErrorCode::ExceedsTrancheAllocation
+        );
+        self.deployed = self
+            .deployed
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
 
-    pub token_program: Program<'info, token::Token>,
+    pub fn add_repaid(&mut self, amount: u64) -> Result<()> {
+        self.repaid = self
+            .repaid
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct Pool {
-    pub creator: Pubkey,
-    pub authority: Pubkey,
-    pub reference: Pubkey,
-    pub mint: Pubkey,
-    // pub seed: u64,
-    pub shares: u64,
-    pub minted: u64,
-    pub closed: bool,
-    pub bump: u8,
-    pub start_date: u64,
-    pub maturity_date: u64,
-    pub apy: u8,
-}
+impl VehicleAssetDetails {
+    pub const MAX_MAKE_LEN: usize = 32;
+    pub const MAX_MODEL_LEN: usize = 32;
+    pub const MAX_JURISDICTION_LEN: usize = 8;
 
-#[account]
-#[derive(InitSpace)]
-pub struct Distribution {
-    pub pool: Pubkey,
-    pub authority: Pubkey,
-    pub rewards: u64,
-    pub claimed: u64,
-    pub bump: u8,
+    pub fn is_valid(&self) -> bool {
+        self.make.len() <= Self::MAX_MAKE_LEN
+            && self.model.len() <= Self::MAX_MODEL_LEN
+            && self.jurisdiction.len() <= Self::MAX_JURISDICTION_LEN
+    }
 }
 
-impl Pool {
-    pub const MIN_DEPOSIT: u64 = 100_u64 * 1e6 as u64;
+impl VehicleAsset {
+    pub const VERSION: u8 = 1;
 
-    pub fn init(
+    pub fn set(
         &mut self,
-        [creator, authority, reference, mint]: [Pubkey; 4],
-        [shares, start_date, maturity_date]: [u64; 3],
+        creator: Pubkey,
+        details: VehicleAssetDetails,
+        registered_at: u64,
         bump: u8,
-        apy: u8,
     ) -> Result<()> {
+        self.version = VehicleAsset::VERSION;
         self.creator = creator;
-        self.authority = authority;
-        self.reference = reference;
-        self.mint = mint;
-        // self.seed = seed;
-        self.shares = shares;
-        self.minted = 0;
-        self.closed = false;
+        self.pool = None;
+        self.vin_hash = details.vin_hash;
+        self.make = details.make;
+        self.model = details.model;
+        self.purchase_price = details.purchase_price;
+        self.jurisdiction = details.jurisdiction;
+        self.custodian = details.custodian;
+        self.title_hash = details.title_hash;
+        self.status = VehicleStatus::Active;
+        self.registered_at = registered_at;
         self.bump = bump;
-        self.start_date = start_date;
-        self.maturity_date = maturity_date;
-        self.apy = apy;
+        self.tranche = None;
+
         Ok(())
     }
+}
 
-    pub fn get_shares_from_deposit(&self, deposit: u64) -> u64 {
-        (deposit * self.shares) / (self.shares * 1e6 as u64)
+impl RevenueConfig {
+    pub const VERSION: u8 = 1;
+    pub const MAX_BPS: u16 = 10_000;
+
+    pub fn set(
+        &mut self,
+        pool: Pubkey,
+        operator: Pubkey,
+        driver_bps: u16,
+        maintenance_bps: u16,
+        bump: u8,
+    ) -> Result<()> {
+        self.version = RevenueConfig::VERSION;
+        self.pool = pool;
+        self.operator = operator;
+        self.driver_bps = driver_bps;
+        self.maintenance_bps = maintenance_bps;
+        self.bump = bump;
+
+        Ok(())
     }
 
-    pub fn get_deposit_from_shares(&self, shares: u64) -> u64 {
-        (shares * (self.shares * 1e6 as u64)) / self.shares
+    pub fn is_valid_split(&self, protocol_fee_bps: u16) -> bool {
+        self.driver_bps as u32 + self.maintenance_bps as u32 + protocol_fee_bps as u32
+            <= RevenueConfig::MAX_BPS as u32
     }
 
-    pub fn get_min_deposit(&self) -> u64 {
-        self.get_deposit_from_shares(1)
+    pub fn split(&self, gross: u64, protocol_fee_bps: u16) -> Result<RevenueSplit> {
+        let driver: u64 = get_bps_share(gross, self.driver_bps)?;
+        let maintenance: u64 = get_bps_share(gross, self.maintenance_bps)?;
+        let protocol: u64 = get_bps_share(gross, protocol_fee_bps)?;
+        let investors: u64 = gross
+            .checked_sub(driver)
+            .and_then(|rest| rest.checked_sub(maintenance))
+            .and_then(|rest| rest.checked_sub(protocol))
+            .ok_or(ErrorCode::MathUnderflow)?;
+
+        Ok(RevenueSplit {
+            driver,
+            maintenance,
+            protocol,
+            investors,
+        })
     }
 
-    pub fn is_valid_deposit(&self, deposit: u64) -> bool {
-        deposit % self.get_min_deposit() == 0
+    pub fn add_reported(&mut self, amount: u64) -> Result<()> {
+        self.reported = self
+            .reported
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
     }
+}
 
-    pub fn add_minted(&mut self, minted: u64) {
-        self.minted += minted;
+impl PoolStatus {
+    /// Vehicles and tranches may still be added.
+    pub const CONFIGURABLE: &'static [PoolStatus] = &[
+        PoolStatus::Draft,
+        PoolStatus::Fundraising,
+        PoolStatus::Funded,
+        PoolStatus::Active,
+    ];
+    /// The pool sells new shares.
+    pub const SELLING: &'static [PoolStatus] = &[PoolStatus::Fundraising, PoolStatus::Active];
+    /// Shares are outstanding and priced, so they trade and revenue is reported.
+    pub const LIVE: &'static [PoolStatus] = &[
+        PoolStatus::Fundraising,
+        PoolStatus::Funded,
+        PoolStatus::Active,
+        PoolStatus::Matured,
+        PoolStatus::Defaulted,
+    ];
+    /// The primary distribution exists and takes deposits.
+    pub const DISTRIBUTING: &'static [PoolStatus] = &[
+        PoolStatus::Active,
+        PoolStatus::Matured,
+        PoolStatus::Defaulted,
+        PoolStatus::Settled,
+    ];
+    /// A redemption window opened while live is settled even after settlement.
+    pub const REDEEMING: &'static [PoolStatus] = &[
+        PoolStatus::Fundraising,
+        PoolStatus::Funded,
+        PoolStatus::Active,
+        PoolStatus::Matured,
+        PoolStatus::Defaulted,
+        PoolStatus::Settled,
+    ];
+    /// Investors hold shares, which come back out of lockups and listings.
+    pub const HELD: &'static [PoolStatus] = &[
+        PoolStatus::Fundraising,
+        PoolStatus::Funded,
+        PoolStatus::Active,
+        PoolStatus::Matured,
+        PoolStatus::Defaulted,
+        PoolStatus::Refunding,
+        PoolStatus::Settled,
+    ];
+    /// Neither settled nor cancelled, so the pool's settings may still change.
+    pub const OPEN: &'static [PoolStatus] = &[
+        PoolStatus::Draft,
+        PoolStatus::Fundraising,
+        PoolStatus::Funded,
+        PoolStatus::Active,
+        PoolStatus::Matured,
+        PoolStatus::Defaulted,
+        PoolStatus::Refunding,
+    ];
+
+    pub fn is_any(&self, statuses: &[PoolStatus]) -> bool {
+        statuses.contains(self)
     }
 
-    pub fn sub_minted(&mut self, minted: u64) {
-        self.minted -= minted;
+    pub fn can_transition_to(&self, next: PoolStatus) -> bool {
+        use PoolStatus::*;
+
+        matches!(
+            (self, next),
+            (Draft, Fundraising | Refunding | Cancelled)
+                | (Fundraising, Funded | Active | Refunding | Settled)
+                | (Funded, Active | Refunding)
+                | (Active, Matured | Defaulted | Settled)
+                | (Matured, Defaulted | Settled)
+                | (Defaulted, Settled)
+                | (Refunding, Cancelled)
+        )
     }
-    // pub fn investment_period_ended(&self) -> bool {
-    //     let clock = Clock::get();
-    //     match clock{
-    //         Ok(T)=>{
-    //             if T.unix_timestamp > self.start_date{
-    //                 return true;
-    //             }
-    //             return false
-    //         },
-    //         Err(_E)=>{
-    //             return false;
-    //         }
-    //     }
-    // if clock.unix_timestamp > self.start_date{
-    //     return true;
-    // }
-    // return false
-    // }
+}
 
-    // pub fn maturity_date_passed(&self) -> bool {
-    //     let clock = Clock::get();
-    //     match clock{
-    //         Ok(T)=>{
-    //             if T.unix_timestamp > self.maturity_date{
-    //                 return true;
-    //             }
-    //             return false
-    //         },
-    //         Err(_E)=>{
-    //             return false;
-    //         }
-    //     }
-    // if clock.unix_timestamp > self.maturity_date{
-    //     return true;
-    // }
-    // return false
-    // }
+impl VehicleStatus {
+    /// A vehicle goes in and out of maintenance until it is sold or written off.
+    pub fn can_transition_to(&self, next: VehicleStatus) -> bool {
+        use VehicleStatus::*;
+
+        matches!(
+            (self, next),
+            (Active, InMaintenance | Sold | WrittenOff)
+                | (InMaintenance, Active | Sold | WrittenOff)
+        )
+    }
 }
 
-impl Distribution {
-    pub fn set(&mut self, pool: Pubkey, authority: Pubkey, bump: u8) -> Result<()> {
+impl RevenueBucket {
+    pub fn seed(&self) -> &'static [u8] {
+        match self {
+            RevenueBucket::Driver => b"driver_bucket",
+            RevenueBucket::Maintenance => b"maintenance_bucket",
+            RevenueBucket::Protocol => b"protocol_bucket",
+            RevenueBucket::Investors => b"investor_bucket",
+        }
+    }
+}
+
+impl PoolReserve {
+    pub const VERSION: u8 = 1;
+
+    pub fn configure(
+        &mut self,
+        pool: Pubkey,
+        principal_bps: u16,
+        target: u64,
+        bump: u8,
+    ) -> Result<()> {
+        self.version = PoolReserve::VERSION;
         self.pool = pool;
-        self.authority = authority;
+        self.principal_bps = principal_bps;
+        self.target = target;
         self.bump = bump;
 
         Ok(())
     }
 
-    pub fn set_authority(&mut self, authority: Pubkey) {
-        self.authority = authority;
+    pub fn get_balance(&self) -> Result<u64> {
+        Ok(self
+            .funded
+            .checked_sub(self.spent)
+            .ok_or(ErrorCode::MathUnderflow)?)
+    }
+
+    pub fn is_funded(&self) -> Result<bool> {
+        Ok(self.get_balance()? >= self.target)
+    }
+
+    pub fn add_funded(&mut self, amount: u64) -> Result<()> {
+        self.funded = self
+            .funded
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn add_spent(&mut self, amount: u64) -> Result<()> {
+        self.spent = self
+            .spent
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.expenses = self
+            .expenses
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
     }
+}
+
+impl ReserveExpense {
+    pub fn set(
+        &mut self,
+        reserve: Pubkey,
+        index: u64,
+        expense: ReceiptedExpense,
+        spent_at: u64,
+        bump: u8,
+    ) -> Result<()> {
+        self.reserve = reserve;
+        self.index = index;
+        self.expense = expense;
+        self.spent_at = spent_at;
+        self.bump = bump;
 
-    pub fn add_rewards(&mut self, amount: u64) {
-        self.rewards += amount;
+        Ok(())
     }
+}
+
+/// Creates a pool whose shares are issued at `share_price`, seeded by the creator's deposit.
+#[allow(clippy::too_many_arguments)]
+fn issue_pool(
+    ctx: Context<CreatePool>,
+    reference: Pubkey,
+    authority: Pubkey,
+    // seed: u64,
+    shares: u64,
+    deposit: u64,
+    share_price: u64,
+    name: String,
+    symbol: String,
+    uri: String,
+    start_date: u64,
+    maturity_date: u64,
+    apy: u8,
+    lockup: Option<LockupConfig>,
+) -> Result<()> {
+    // Get the pool account
+    let pool: &mut Account<'_, Pool> = &mut ctx.accounts.pool;
+
+    // Validate the input
+    require_gte!(shares, 1_u64, ErrorCode::MinimumShares);
+    require_gte!(deposit, Pool::MIN_DEPOSIT, ErrorCode::MinimumDeposit);
+
+    // Initialize the pool account
+    pool.init(
+        [
+            *ctx.accounts.creator.key,
+            authority,
+            reference,
+            // *ctx.accounts.reference.key,
+            ctx.accounts.mint.key(),
+        ],
+        [shares, start_date, maturity_date, share_price],
+        ctx.bumps.pool,
+        apy,
+        lockup,
+    )?;
+
+    // let clock = Clock::get()?;
+    // Validate the input
+    // require_gt!(
+    //     start_date,
+    //     clock.unix_timestamp,
+    //     ErrorCode::StartDatePassed
+    // );
+    // require_gt!(
+    //     maturity_date,
+    //     clock.unix_timestamp,
+    //     ErrorCode::MaturityDatePassed
+    // );
+    // require_gt!(maturity_date, start_date, ErrorCode::StartDatePassed);
+    // if !ctx.accounts.pool_creator_data.can_create || ctx.accounts.pool_creator_data.creator != ctx.accounts.creator.key() {
+    //  return Err(ErrorCode::CreatorNotAuthorized.into());
+    // }
+    // require_eq!(seed % shares, 0, ErrorCode::InvalidSeedSharesRatio);
+    require!(pool.is_valid_deposit(deposit)?, ErrorCode::MinimumDeposit);
+
+    // Get the shares minted from the deposit
+    let minted: u64 = pool.get_shares_from_deposit(deposit)?;
+    require_gte!(
+        pool.get_available_shares()?,
+        minted,
+        ErrorCode::ExceedsAvailableShares
+    );
+
+    // Signer seeds
+    let pool_seeds: &[&[u8]; 3] = &[b"pool", pool.reference.as_ref(), &[pool.bump]];
+    let signers_seeds: &[&[&[u8]]; 1] = &[&pool_seeds[..]];
+
+    // Send the USDC to the pool account
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.creator_usdc_account.to_account_info(),
+                to: ctx.accounts.pool_usdc_account.to_account_info(),
+                authority: ctx.accounts.creator.to_account_info(),
+            },
+        ),
+        deposit,
+    )?;
+
+    // Create the metadata account
+    let metadata_program: &AccountInfo<'_> = &ctx.accounts.metadata_program.to_account_info();
+    let metadata: &AccountInfo<'_> = &ctx.accounts.metadata.to_account_info();
+    let mint: &AccountInfo<'_> = &ctx.accounts.mint.to_account_info();
+    let mint_authority: &AccountInfo<'_> = &pool.to_account_info();
+    let payer: &AccountInfo<'_> = &ctx.accounts.creator.to_account_info();
+    let system_program: &AccountInfo<'_> = &ctx.accounts.system_program.to_account_info();
+    let rent: &AccountInfo<'_> = &ctx.accounts.rent.to_account_info();
+    let update_authority: &AccountInfo<'_> = &pool.to_account_info();
+
+    let metadata_v3_cpi: CreateMetadataAccountV3Cpi<'_, '_> = CreateMetadataAccountV3Cpi::new(
+        metadata_program,
+        CreateMetadataAccountV3CpiAccounts {
+            metadata,
+            mint,
+            mint_authority,
+            payer,
+            system_program,
+            rent: Some(rent),
+            update_authority: (update_authority, true),
+        },
+        CreateMetadataAccountV3InstructionArgs {
+            collection_details: None,
+            data: DataV2 {
+                name: name.to_string(),
+                symbol: symbol.to_string(),
+                uri: uri.to_string(),
+                seller_fee_basis_points: 0,
+                creators: Some(vec![Creator {
+                    address: ctx.accounts.creator.key(),
+                    verified: false,
+                    share: 100,
+                }]),
+                collection: Some(Collection {
+                    verified: false,
+                    key: ctx.accounts.collection_mint.key(),
+                }),
+                uses: None,
+            },
+            is_mutable: true,
+        },
+    );
+    metadata_v3_cpi.invoke_signed(signers_seeds)?;
+
+    // Record the creator as a verified creator
+    let sysvar_instructions: &AccountInfo<'_> = &ctx.accounts.sysvar_instructions.to_account_info();
+    VerifyCreatorV1Cpi::new(
+        metadata_program,
+        VerifyCreatorV1CpiAccounts {
+            authority: payer,
+            delegate_record: None,
+            metadata,
+            collection_mint: None,
+            collection_metadata: None,
+            collection_master_edition: None,
+            system_program,
+            sysvar_instructions,
+        },
+    )
+    .invoke()?;
+
+    // Join the BlockRide collection
+    let project_seeds: &[&[u8]; 2] = &[PROJECT_PDA, &[ctx.bumps.project_pda]];
+    VerifyCollectionV1Cpi::new(
+        metadata_program,
+        VerifyCollectionV1CpiAccounts {
+            authority: &ctx.accounts.project_pda.to_account_info(),
+            delegate_record: None,
+            metadata,
+            collection_mint: &ctx.accounts.collection_mint.to_account_info(),
+            collection_metadata: Some(&ctx.accounts.collection_metadata.to_account_info()),
+            collection_master_edition: Some(
+                &ctx.accounts.collection_master_edition.to_account_info(),
+            ),
+            system_program,
+            sysvar_instructions,
+        },
+    )
+    .invoke_signed(&[&project_seeds[..]])?;
+
+    // Mint tokens to the creator token account
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::MintTo {
+                authority: pool.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.creator_mint_account.to_account_info(),
+            },
+            signers_seeds,
+        ),
+        minted,
+    )?;
+
+    // Update the pool account
+    pool.add_minted(minted)?;
+    pool.add_raised(deposit)?;
+    pool.creator_shares = minted;
+
+    // Open the creator's position with the seed deposit
+    let creator_position: &mut Account<'_, InvestorPosition> = &mut ctx.accounts.creator_position;
+    creator_position.set(
+        pool.key(),
+        ctx.accounts.creator.key(),
+        ctx.bumps.creator_position,
+    )?;
+    creator_position.add_shares(minted, deposit, Clock::get()?.unix_timestamp as u64)?;
+    emit!(SharesPurchased {
+        pool: pool.key(),
+        buyer: ctx.accounts.creator.key(),
+        shares: minted,
+        cost: deposit,
+    });
+
+    // Give the pool the next id in the registry
+    add_to_registry(
+        &mut ctx.accounts.project_pda,
+        &mut ctx.accounts.creator_pools,
+        [
+            &mut ctx.accounts.pool_entry,
+            &mut ctx.accounts.creator_pool_entry,
+        ],
+        pool,
+        [
+            ctx.bumps.creator_pools,
+            ctx.bumps.pool_entry,
+            ctx.bumps.creator_pool_entry,
+        ],
+    )
+}
 
-    pub fn sub_rewards(&mut self, amount: u64) {
-        self.rewards -= amount;
+/// Fails while a configured reserve is below its target; pools without one pass.
+fn require_reserve_funded(reserve: &UncheckedAccount) -> Result<()> {
+    if reserve.data_is_empty() {
+        return Ok(());
     }
 
-    pub fn add_claimed(&mut self, amount: u64) {
-        self.claimed += amount;
+    // Only the program can have allocated its PDA, so the data is a `PoolReserve`
+    let reserve: PoolReserve = PoolReserve::try_deserialize(&mut &reserve.try_borrow_data()?[..])?;
+    require!(reserve.is_funded()?, ErrorCode::ReserveBelowTarget);
+    Ok(())
+}
+
+/// Deserializes a program PDA that may not have been created yet.
+fn load_if_created<T: AccountDeserialize>(account: &UncheckedAccount) -> Result<Option<T>> {
+    if account.data_is_empty() {
+        return Ok(None);
     }
 
-    pub fn sub_claimed(&mut self, amount: u64) {
-        self.claimed -= amount;
+    // Only the program can have allocated its PDA, so the data is a `T`
+    Ok(Some(T::try_deserialize(
+        &mut &account.try_borrow_data()?[..],
+    )?))
+}
+
+/// The pool shares a holder owns, including those escrowed in their lockup or listing.
+fn get_held_shares(
+    holder_mint_account: &token::TokenAccount,
+    holder_lockup: &UncheckedAccount,
+    holder_listing: &UncheckedAccount,
+) -> Result<u64> {
+    let locked: u64 = match load_if_created::<ShareLockup>(holder_lockup)? {
+        Some(lockup) => lockup.locked.saturating_sub(lockup.released),
+        None => 0,
+    };
+    let listed: u64 = match load_if_created::<Listing>(holder_listing)? {
+        Some(listing) => listing.shares,
+        None => 0,
+    };
+
+    Ok(holder_mint_account
+        .amount
+        .checked_add(locked)
+        .and_then(|shares| shares.checked_add(listed))
+        .ok_or(ErrorCode::MathOverflow)?)
+}
+
+/// Gives `pool` the next sequential id and appends it to its creator's pool list.
+fn add_to_registry(
+    project: &mut ProjectData,
+    creator_pools: &mut CreatorPools,
+    [pool_entry, creator_pool_entry]: [&mut PoolRegistryEntry; 2],
+    pool: &mut Account<Pool>,
+    [creator_pools_bump, pool_entry_bump, creator_pool_entry_bump]: [u8; 3],
+) -> Result<()> {
+    let id: u64 = project.pools;
+    let creator_index: u64 = creator_pools.pools;
+
+    creator_pools.set(pool.creator, creator_pools_bump);
+    pool_entry.set(id, pool.creator, creator_index, pool.key(), pool_entry_bump);
+    creator_pool_entry.set(
+        id,
+        pool.creator,
+        creator_index,
+        pool.key(),
+        creator_pool_entry_bump,
+    );
+    pool.id = Some(id);
+
+    project.add_pools(1)?;
+    creator_pools.add_pools(1)
+}
+
+fn get_bps_share(amount: u64, bps: u16) -> Result<u64> {
+    let share: u128 = amount as u128 * bps as u128 / RevenueConfig::MAX_BPS as u128;
+    Ok(u64::try_from(share).map_err(|_| ErrorCode::MathOverflow)?)
+}
+
+/// Rewrites a program account in its current layout, growing it to `space` and topping
+/// up its rent from `payer`. Accounts that were never versioned are shorter than their
+/// current layout, so anything already `space` long is up to date.
+fn migrate_account<'info, T: AccountSerialize + Discriminator>(
+    account: &UncheckedAccount<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    upgrade: impl FnOnce(&mut &[u8]) -> Result<T>,
+) -> Result<()> {
+    // Validations
+    let upgraded: T = {
+        let data = account.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == T::DISCRIMINATOR,
+            anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+        );
+        require_gt!(space, data.len(), ErrorCode::AccountAlreadyMigrated);
+        upgrade(&mut &data[8..])?
+    };
+
+    // Fund the larger account
+    let rent: u64 = Rent::get()?.minimum_balance(space);
+    let shortfall: u64 = rent.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: account.to_account_info(),
+                },
+            ),
+            shortfall,
+        )?;
     }
+
+    // Write the account in its current layout
+    account.realloc(space, true)?;
+    let mut data = account.try_borrow_mut_data()?;
+    upgraded.try_serialize(&mut &mut data[..])?;
+
+    Ok(())
 }
 
 #[error_code]
@@ -1099,8 +7347,10 @@ pub enum ErrorCode {
     SignerNotAuthorized,
     #[msg("Creator not authorized")]
     CreatorNotAuthorized,
+    /// Deprecated: no longer raised, pool status is checked with `InvalidPoolState`.
     #[msg("Pool not closed")]
     PoolNotClosed,
+    /// Deprecated: no longer raised, pool status is checked with `InvalidPoolState`.
     #[msg("Pool closed")]
     PoolClosed,
     #[msg("Shares must be at least 1")]
@@ -1123,4 +7373,110 @@ pub enum ErrorCode {
     NonZeroPoolUSDCBalance,
     #[msg("Non-zero distribution USDC balance")]
     NonZeroDistributionUSDCBalance,
+    #[msg("Lockup accounts are required for this pool")]
+    LockupAccountsMissing,
+    #[msg("Pool does not lock shares")]
+    PoolNotLocked,
+    #[msg("No shares available to release")]
+    NoSharesToRelease,
+    #[msg("Listing price must be greater than zero")]
+    InvalidListingPrice,
+    #[msg("Exceeds listed shares")]
+    ExceedsListedShares,
+    #[msg("Redemption window already open")]
+    RedemptionWindowOpen,
+    #[msg("Redemption window not open")]
+    RedemptionWindowNotOpen,
+    #[msg("Redemption window has ended")]
+    RedemptionWindowEnded,
+    #[msg("Redemption window has not ended")]
+    RedemptionWindowNotEnded,
+    #[msg("Redemption close date must be in the future")]
+    InvalidRedemptionCloseDate,
+    #[msg("Redemption liquidity must be greater than zero")]
+    InvalidRedemptionLiquidity,
+    #[msg("Unsettled redemption tenders")]
+    UnsettledRedemptionTenders,
+    #[msg("Distribution accounts are required for this source")]
+    DistributionAccountsMissing,
+    #[msg("NAV per share must be greater than zero")]
+    InvalidNav,
+    #[msg("NAV change exceeds the allowed limit")]
+    NavChangeTooLarge,
+    #[msg("Pool NAV is stale")]
+    StaleNav,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Math underflow")]
+    MathUnderflow,
+    #[msg("Account is already at the current version")]
+    AccountAlreadyMigrated,
+    #[msg("Vehicle details exceed the maximum length")]
+    VehicleDetailsTooLong,
+    #[msg("Vehicle asset is already linked to a pool")]
+    VehicleAssetAlreadyLinked,
+    #[msg("Pool has no registered vehicle asset")]
+    NoVehicleAsset,
+    #[msg("Revenue split exceeds 100%")]
+    InvalidRevenueSplit,
+    #[msg("Revenue amount must be greater than zero")]
+    InvalidRevenueAmount,
+    #[msg("Revenue bucket cannot be withdrawn directly")]
+    InvalidRevenueBucket,
+    #[msg("Reserve share exceeds 100%")]
+    InvalidReserveShare,
+    #[msg("Insufficient reserve balance")]
+    InsufficientReserveBalance,
+    #[msg("Reserve is below its target level")]
+    ReserveBelowTarget,
+    #[msg("Vehicle asset is not linked to this pool")]
+    VehicleAssetNotLinked,
+    #[msg("Tranche allocation must be greater than zero")]
+    InvalidTrancheAllocation,
+    #[msg("Tranche allocations exceed the pool principal")]
+    ExceedsPoolPrincipal,
+    #[msg("Exceeds the tranche principal allocation")]
+    ExceedsTrancheAllocation,
+    #[msg("Fleet pools require an asset tranche")]
+    TrancheRequired,
+    #[msg("Share classes must be created before any shares are sold")]
+    ShareClassesLocked,
+    #[msg("Pools with share classes sell shares through a class")]
+    ShareClassRequired,
+    #[msg("Invalid share class")]
+    InvalidShareClass,
+    #[msg("Buyer is not eligible for these shares")]
+    BuyerNotEligible,
+    #[msg("Exceeds the holder's share of the class rewards")]
+    ExceedsClassRewards,
+    #[msg("Invalid pool template terms")]
+    InvalidTemplateTerms,
+    #[msg("Pool is already registered")]
+    PoolAlreadyRegistered,
+    #[msg("Distribution does not belong to the pool")]
+    InvalidDistribution,
+    #[msg("Pool has too many distributions")]
+    TooManyDistributions,
+    #[msg("Pool still has open distributions")]
+    OpenDistributions,
+    #[msg("Instruction not allowed in the pool's current state")]
+    InvalidPoolState,
+    #[msg("Holder has too many purchases still vesting")]
+    TooManyLockups,
+    #[msg("Listed shares cannot be re-priced; cancel the listing first")]
+    ListingPriceChanged,
+    #[msg("The vehicle cannot move to this status")]
+    InvalidVehicleStatus,
+    #[msg("The reserve vault is required when the pool has a reserve")]
+    ReserveVaultRequired,
+    #[msg("The vehicle asset is already financed by a tranche")]
+    VehicleAssetAlreadyFinanced,
+    #[msg("Maturity date has not passed")]
+    MaturityDateNotPassed,
+    #[msg("Shares remain to be refunded")]
+    SharesNotRefunded,
+    #[msg("Exceeds the holder's claimable rewards")]
+    ExceedsClaimableRewards,
+    #[msg("The reserve is required once the pool has configured it")]
+    ReserveRequired,
 }
@@ -0,0 +1,537 @@
+//! In-process test harness for the octo-program.
+//!
+//! The program runs natively inside `solana-program-test` next to the SPL token programs,
+//! with a locally controlled mint at `CUSTOM_USDC_MINT`. Creating pools goes through the
+//! Metaplex token-metadata program, which `start_with_metadata` loads from the committed
+//! `tests/fixtures/mpl_token_metadata.so` (refresh it with `tests/fixtures/dump.sh`).
+//! Every other flow starts from a pool written straight into the bank by `inject_pool`.
+
+#![allow(dead_code)]
+
+pub mod scenario;
+
+use std::path::Path;
+
+use anchor_lang::{
+    prelude::{AccountInfo, Pubkey},
+    solana_program::{entrypoint::ProgramResult, program_pack::Pack},
+    AccountDeserialize, AccountSerialize, AnchorDeserialize, AnchorSerialize, Space,
+};
+use anchor_spl::{associated_token, token::spl_token};
+use mpl_token_metadata::{accounts::Metadata, types::Key};
+use octo_client::{instructions, pda};
+use octo_program::{
+    BuyerEligibility, LockupConfig, Pool, PoolStatus, VehicleAsset, VehicleStatus, CUSTOM_USDC_MINT,
+};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    clock::Clock,
+    instruction::{Instruction, InstructionError},
+    rent::Rent,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+pub const USDC: u64 = 1_000_000;
+pub const DAY: u64 = 24 * 60 * 60;
+
+fn process_instruction<'a, 'b, 'c, 'd>(
+    program_id: &'a Pubkey,
+    accounts: &'b [AccountInfo<'c>],
+    data: &'d [u8],
+) -> ProgramResult {
+    // Anchor's entry wants the account slice to live as long as the accounts themselves
+    let accounts: &'c [AccountInfo<'c>] = Box::leak(accounts.to_vec().into_boxed_slice());
+    octo_program::entry(program_id, accounts, data)
+}
+
+const METADATA_FIXTURE: &str = "tests/fixtures/mpl_token_metadata.so";
+/// Size of a metadata account as the token-metadata program allocates it.
+const METADATA_SPACE: usize = 679;
+
+pub fn assert_error(result: Result<(), TransactionError>, error: impl Into<u32>) {
+    assert_eq!(
+        result.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::Custom(error.into()))
+    );
+}
+
+pub struct TestPool {
+    pub address: Pubkey,
+    pub reference: Pubkey,
+    pub mint: Pubkey,
+    pub authority: Keypair,
+}
+
+pub struct TestEnv {
+    pub context: ProgramTestContext,
+    pub usdc_authority: Keypair,
+}
+
+impl TestEnv {
+    pub async fn start() -> Self {
+        Self::start_with(false).await
+    }
+
+    /// Starts with the Metaplex token-metadata program loaded, for flows that create pools
+    /// or collections.
+    pub async fn start_with_metadata() -> Self {
+        assert!(
+            Path::new(METADATA_FIXTURE).exists(),
+            "{METADATA_FIXTURE} is missing; run tests/fixtures/dump.sh"
+        );
+        Self::start_with(true).await
+    }
+
+    async fn start_with(metadata: bool) -> Self {
+        let usdc_authority: Keypair = Keypair::new();
+        let mut program_test: ProgramTest = ProgramTest::new(
+            "octo_program",
+            octo_program::ID,
+            processor!(process_instruction),
+        );
+        if metadata {
+            program_test.add_program("mpl_token_metadata", mpl_token_metadata::ID, None);
+        }
+        program_test.add_account(
+            CUSTOM_USDC_MINT,
+            mint_account(&usdc_authority.pubkey(), 6, 0),
+        );
+
+        Self {
+            context: program_test.start_with_context().await,
+            usdc_authority,
+        }
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.context.payer.pubkey()
+    }
+
+    pub async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), TransactionError> {
+        process(&mut self.context, instructions, signers).await
+    }
+
+    /// Returns the decoded return data of a view instruction.
+    pub async fn simulate<T: AnchorDeserialize>(&mut self, instruction: Instruction) -> T {
+        let transaction: Transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&self.context.payer.pubkey()),
+            &[&self.context.payer],
+            self.context.last_blockhash,
+        );
+        let simulation = self
+            .context
+            .banks_client
+            .simulate_transaction(transaction)
+            .await
+            .unwrap();
+        assert_eq!(simulation.result, Some(Ok(())));
+
+        let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+        T::try_from_slice(&return_data.data).unwrap()
+    }
+
+    /// A new funded keypair.
+    pub async fn create_user(&mut self) -> Keypair {
+        let user: Keypair = Keypair::new();
+        let payer: Pubkey = self.payer();
+        self.process(
+            &[system_instruction::transfer(
+                &payer,
+                &user.pubkey(),
+                10_000_000_000,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+        user
+    }
+
+    /// Initializes the program with the payer as grand authority and creates the BlockRide
+    /// collection that `create_pool` verifies pool mints into. Needs the Metaplex fixture.
+    pub async fn create_collection(&mut self) {
+        let payer: Pubkey = self.payer();
+        self.process(
+            &[
+                instructions::initialize_program(payer, payer),
+                instructions::create_collection(
+                    payer,
+                    "BlockRide Pools".to_string(),
+                    "BRIDE".to_string(),
+                    "https://blockride.xyz/collection.json".to_string(),
+                ),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+
+    /// Writes the BlockRide collection mint without its metadata, so `create_pool` gets
+    /// through account validation where the Metaplex fixture is missing.
+    pub fn set_collection_mint(&mut self) {
+        let mint: Pubkey = pda::find_collection_mint_address().0;
+        let project: Pubkey = pda::find_project_address().0;
+        self.context
+            .set_account(&mint, &mint_account(&project, 0, 1).into());
+    }
+
+    /// Creates the USDC account of `owner` if needed and mints `amount` into it.
+    pub async fn fund_usdc(&mut self, owner: &Pubkey, amount: u64) -> Pubkey {
+        let payer: Pubkey = self.payer();
+        let account: Pubkey = pda::get_usdc_address(owner);
+        process(
+            &mut self.context,
+            &[
+                create_associated_token_account_idempotent(
+                    &payer,
+                    owner,
+                    &CUSTOM_USDC_MINT,
+                    &spl_token::ID,
+                ),
+                spl_token::instruction::mint_to(
+                    &spl_token::ID,
+                    &CUSTOM_USDC_MINT,
+                    &account,
+                    &self.usdc_authority.pubkey(),
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+            ],
+            &[&self.usdc_authority],
+        )
+        .await
+        .unwrap();
+        account
+    }
+
+    /// Writes a pool, its share mint and its USDC account straight into the bank.
+    pub async fn inject_pool(
+        &mut self,
+        shares: u64,
+        start_date: u64,
+        lockup: Option<LockupConfig>,
+    ) -> TestPool {
+        let authority: Keypair = self.create_user().await;
+        let reference: Pubkey = Pubkey::new_unique();
+        let (address, bump): (Pubkey, u8) = pda::find_pool_address(&reference);
+        let mint: Pubkey = pda::find_mint_address(&address).0;
+
+        let pool: Pool = Pool {
+            version: Pool::VERSION,
+            creator: authority.pubkey(),
+            authority: authority.pubkey(),
+            reference,
+            mint,
+            shares,
+            minted: 0,
+            status: PoolStatus::Fundraising,
+            bump,
+            start_date,
+            maturity_date: start_date + 365 * DAY,
+            apy: 12,
+            lockup,
+            valuator: authority.pubkey(),
+            nav_per_share: USDC,
+            nav_updated_at: start_date,
+            assets: 0,
+            tranches: 0,
+            allocated: 0,
+            share_classes: 0,
+            class_shares: 0,
+            eligibility: BuyerEligibility::Open,
+            protocol_fee_bps: None,
+            id: None,
+            distributions: 0,
+            open_distributions: 0,
+            rewards: 0,
+            nav_period_nav: USDC,
+            nav_period_started_at: start_date,
+            reward_per_share: 0,
+            reserve_configured: false,
+            raised: 0,
+            creator_shares: 0,
+            share_price: USDC,
+            reserved: [0; 4],
+        };
+        self.set_program_account(&address, &pool, 8 + Pool::INIT_SPACE);
+        self.context
+            .set_account(&mint, &mint_account(&address, 0, 0).into());
+        self.fund_usdc(&address, 0).await;
+
+        TestPool {
+            address,
+            reference,
+            mint,
+            authority,
+        }
+    }
+
+    /// Writes a vehicle asset financed by `pool` and counts it on the pool, which
+    /// `withdraw_from_pool` requires.
+    pub async fn inject_vehicle_asset(&mut self, pool: &TestPool) -> Pubkey {
+        let vin_hash: [u8; 32] = Pubkey::new_unique().to_bytes();
+        let (address, bump): (Pubkey, u8) = pda::find_vehicle_asset_address(&vin_hash);
+        let vehicle_asset: VehicleAsset = VehicleAsset {
+            version: VehicleAsset::VERSION,
+            creator: pool.authority.pubkey(),
+            pool: Some(pool.address),
+            vin_hash,
+            make: "Toyota".to_string(),
+            model: "Corolla".to_string(),
+            purchase_price: 12_000 * USDC,
+            jurisdiction: "NG-LA".to_string(),
+            custodian: pool.authority.pubkey(),
+            title_hash: [0; 32],
+            status: VehicleStatus::Active,
+            registered_at: self.now().await,
+            bump,
+            tranche: None,
+            reserved: [0; 29],
+        };
+        self.set_program_account(&address, &vehicle_asset, 8 + VehicleAsset::INIT_SPACE);
+
+        let mut account: Pool = self.account(&pool.address).await;
+        account.assets += 1;
+        self.set_program_account(&pool.address, &account, 8 + Pool::INIT_SPACE);
+        address
+    }
+
+    /// Links a new vehicle to `pool` and finances it with the pool's next asset
+    /// tranche, returning the tranche index.
+    pub async fn add_asset_tranche(&mut self, pool: &TestPool, principal_allocation: u64) -> u16 {
+        let vehicle_asset: Pubkey = self.inject_vehicle_asset(pool).await;
+        let vin_hash: [u8; 32] = self.account::<VehicleAsset>(&vehicle_asset).await.vin_hash;
+        let index: u16 = self.account::<Pool>(&pool.address).await.tranches;
+
+        self.process(
+            &[instructions::add_asset_tranche(
+                pool.authority.pubkey(),
+                pool.address,
+                index,
+                &vin_hash,
+                principal_allocation,
+            )],
+            &[&pool.authority],
+        )
+        .await
+        .unwrap();
+        index
+    }
+
+    /// Gives `pool` a reserve setting `principal_bps` of withdrawals aside.
+    pub async fn configure_reserve(&mut self, pool: &TestPool, principal_bps: u16, target: u64) {
+        self.process(
+            &[instructions::configure_reserve(
+                pool.authority.pubkey(),
+                pool.address,
+                principal_bps,
+                target,
+            )],
+            &[&pool.authority],
+        )
+        .await
+        .unwrap();
+    }
+
+    pub fn set_program_account<T: AccountSerialize>(
+        &mut self,
+        address: &Pubkey,
+        value: &T,
+        space: usize,
+    ) {
+        let mut data: Vec<u8> = Vec::with_capacity(space);
+        value.try_serialize(&mut data).unwrap();
+        self.set_raw_account(address, data, space);
+    }
+
+    /// Writes `data`, zero-padded to `space`, as an account owned by the program.
+    pub fn set_raw_account(&mut self, address: &Pubkey, mut data: Vec<u8>, space: usize) {
+        data.resize(space, 0);
+
+        let account: AccountSharedData = Account {
+            lamports: Rent::default().minimum_balance(space),
+            data,
+            owner: octo_program::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+        .into();
+        self.context.set_account(address, &account);
+    }
+
+    /// Writes token metadata for `mint` that is outside any collection, like the metadata of
+    /// pools created before the BlockRide collection existed.
+    pub fn set_legacy_metadata(&mut self, mint: &Pubkey, update_authority: &Pubkey) {
+        let metadata: Metadata = Metadata {
+            key: Key::MetadataV1,
+            update_authority: *update_authority,
+            mint: *mint,
+            name: "BlockRide Pool".to_string(),
+            symbol: "BRP".to_string(),
+            uri: "https://blockride.xyz/pool.json".to_string(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: None,
+            collection: None,
+            uses: None,
+            collection_details: None,
+            programmable_config: None,
+        };
+        let mut data: Vec<u8> = metadata.try_to_vec().unwrap();
+        data.resize(METADATA_SPACE, 0);
+
+        let account: AccountSharedData = Account {
+            lamports: Rent::default().minimum_balance(METADATA_SPACE),
+            data,
+            owner: mpl_token_metadata::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+        .into();
+        self.context
+            .set_account(&pda::find_metadata_address(mint).0, &account);
+    }
+
+    pub fn set_token_account(
+        &mut self,
+        address: &Pubkey,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+    ) {
+        let mut data: Vec<u8> = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(
+            spl_token::state::Account {
+                mint: *mint,
+                owner: *owner,
+                amount,
+                state: spl_token::state::AccountState::Initialized,
+                ..Default::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+
+        let account: AccountSharedData = Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: spl_token::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+        .into();
+        self.context.set_account(address, &account);
+    }
+
+    pub async fn account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account: Account = self.raw_account(address).await.unwrap();
+        T::try_deserialize(&mut &account.data[..]).unwrap()
+    }
+
+    pub async fn raw_account(&mut self, address: &Pubkey) -> Option<Account> {
+        self.context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+    }
+
+    /// The Metaplex metadata of `mint`.
+    pub async fn metadata(&mut self, mint: &Pubkey) -> Metadata {
+        let account: Account = self
+            .raw_account(&pda::find_metadata_address(mint).0)
+            .await
+            .unwrap();
+        Metadata::from_bytes(&account.data).unwrap()
+    }
+
+    pub async fn token_balance(&mut self, address: &Pubkey) -> u64 {
+        let account: Account = self.raw_account(address).await.unwrap();
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    pub async fn now(&mut self) -> u64 {
+        self.clock().await.unix_timestamp as u64
+    }
+
+    pub async fn warp_to(&mut self, unix_timestamp: u64) {
+        let mut clock: Clock = self.clock().await;
+        clock.unix_timestamp = unix_timestamp as i64;
+        self.context.set_sysvar(&clock);
+    }
+
+    async fn clock(&mut self) -> Clock {
+        self.context
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .unwrap()
+    }
+}
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut all_signers: Vec<&Keypair> = vec![&context.payer];
+    all_signers.extend(signers);
+    let transaction: Transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+fn mint_account(authority: &Pubkey, decimals: u8, supply: u64) -> Account {
+    let mut data: Vec<u8> = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint::pack(
+        spl_token::state::Mint {
+            mint_authority: Some(*authority).into(),
+            supply,
+            decimals,
+            is_initialized: true,
+            freeze_authority: Some(*authority).into(),
+        },
+        &mut data,
+    )
+    .unwrap();
+
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// The shares account of `owner` in `pool`.
+pub fn shares_address(pool: &TestPool, owner: &Pubkey) -> Pubkey {
+    associated_token::get_associated_token_address(owner, &pool.mint)
+}
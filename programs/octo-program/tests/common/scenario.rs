@@ -0,0 +1,563 @@
+//! Random instruction sequences against a single pool, shared by the `invariants`
+//! property test and the `instruction_sequence` fuzz target.
+//!
+//! Actions are allowed to fail; after every action the pool's bookkeeping is checked
+//! against the token accounts that back it.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::spl_token;
+use octo_client::{instructions, pda};
+use octo_program::{
+    Distribution, ExpenseCategory, Listing, LockupConfig, Pool, PoolReserve, PoolStatus,
+    ReceiptedExpense, RedemptionSource, RedemptionWindow, ShareLockup,
+};
+use solana_sdk::{
+    program_pack::Pack,
+    signature::{Keypair, Signer},
+};
+
+use super::{TestEnv, TestPool, DAY, USDC};
+
+pub const INVESTORS: usize = 3;
+pub const POOL_SHARES: u64 = 100;
+/// States `Action::Transition` picks from; activation and settlement have their own actions.
+const TRANSITIONS: [PoolStatus; 6] = [
+    PoolStatus::Fundraising,
+    PoolStatus::Funded,
+    PoolStatus::Matured,
+    PoolStatus::Defaulted,
+    PoolStatus::Refunding,
+    PoolStatus::Cancelled,
+];
+/// Most USDC minted to the pool authority ahead of a single distribution.
+const MAX_FUNDING: u64 = 1_000_000_000 * USDC;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(fuzzing, derive(arbitrary::Arbitrary))]
+pub enum Action {
+    Buy {
+        investor: u8,
+        shares: u64,
+    },
+    Release {
+        investor: u8,
+    },
+    List {
+        investor: u8,
+        shares: u64,
+        price_per_share: u64,
+    },
+    CancelListing {
+        investor: u8,
+    },
+    FillListing {
+        buyer: u8,
+        seller: u8,
+        shares: u64,
+    },
+    Withdraw {
+        shares: u64,
+    },
+    SpendReserve {
+        amount: u64,
+    },
+    UpdateNav {
+        nav_per_share: u64,
+    },
+    OpenRedemptionWindow {
+        days: u8,
+        liquidity: u64,
+        from_distribution: bool,
+    },
+    Tender {
+        investor: u8,
+        shares: u64,
+    },
+    Settle {
+        investor: u8,
+    },
+    CloseRedemptionWindow,
+    Transition {
+        status: u8,
+    },
+    Activate,
+    ClosePool,
+    Refund {
+        investor: u8,
+        shares: u64,
+    },
+    Distribute {
+        amount: u64,
+    },
+    Claim {
+        investor: u8,
+        rewards: u64,
+    },
+    Warp {
+        days: u8,
+    },
+}
+
+pub struct Scenario {
+    pub env: TestEnv,
+    pub pool: TestPool,
+    pub investors: Vec<Keypair>,
+    pub distribution_authority: Keypair,
+    pub locked: bool,
+}
+
+impl Scenario {
+    pub async fn start(locked: bool) -> Self {
+        Self::new(TestEnv::start().await, locked).await
+    }
+
+    /// A pool of `POOL_SHARES` starting in a day, and `INVESTORS` funded investors.
+    /// Every scenario gets fresh keys, so several can share one `env` independently.
+    pub async fn new(mut env: TestEnv, locked: bool) -> Self {
+        let start_date: u64 = env.now().await + DAY;
+        let lockup: Option<LockupConfig> = locked.then_some(LockupConfig {
+            cliff: 2 * DAY,
+            duration: 10 * DAY,
+        });
+        let pool: TestPool = env.inject_pool(POOL_SHARES, start_date, lockup).await;
+        env.inject_vehicle_asset(&pool).await;
+        env.configure_reserve(&pool, 1_000, 0).await;
+        env.fund_usdc(&pool.authority.pubkey(), 0).await;
+
+        let mut investors: Vec<Keypair> = Vec::with_capacity(INVESTORS);
+        for _ in 0..INVESTORS {
+            let investor: Keypair = env.create_user().await;
+            env.fund_usdc(&investor.pubkey(), 1_000_000 * USDC).await;
+            investors.push(investor);
+        }
+
+        Self {
+            env,
+            pool,
+            investors,
+            distribution_authority: Keypair::new(),
+            locked,
+        }
+    }
+
+    pub fn into_env(self) -> TestEnv {
+        self.env
+    }
+
+    /// Runs every action, checking the invariants after each of them.
+    pub async fn run(&mut self, actions: &[Action]) {
+        self.check_invariants().await;
+        for action in actions {
+            self.apply(action).await;
+            self.check_invariants().await;
+        }
+    }
+
+    pub async fn apply(&mut self, action: &Action) {
+        let pool: Pubkey = self.pool.address;
+        let authority: &Keypair = &self.pool.authority;
+
+        // Failed actions are expected; only the invariants matter
+        let _ = match *action {
+            Action::Buy { investor, shares } => {
+                let investor: &Keypair = investor_at(&self.investors, investor);
+                self.env
+                    .process(
+                        &[instructions::buy_shares(
+                            investor.pubkey(),
+                            None,
+                            pool,
+                            shares,
+                            self.locked,
+                        )],
+                        &[investor],
+                    )
+                    .await
+            }
+            Action::Release { investor } => {
+                let investor: &Keypair = investor_at(&self.investors, investor);
+                self.env
+                    .process(
+                        &[instructions::release_shares(investor.pubkey(), pool)],
+                        &[investor],
+                    )
+                    .await
+            }
+            Action::List {
+                investor,
+                shares,
+                price_per_share,
+            } => {
+                let investor: &Keypair = investor_at(&self.investors, investor);
+                self.env
+                    .process(
+                        &[instructions::list_shares(
+                            investor.pubkey(),
+                            pool,
+                            shares,
+                            price_per_share,
+                        )],
+                        &[investor],
+                    )
+                    .await
+            }
+            Action::CancelListing { investor } => {
+                let investor: &Keypair = investor_at(&self.investors, investor);
+                self.env
+                    .process(
+                        &[instructions::cancel_listing(investor.pubkey(), pool)],
+                        &[investor],
+                    )
+                    .await
+            }
+            Action::FillListing {
+                buyer,
+                seller,
+                shares,
+            } => {
+                let buyer: &Keypair = investor_at(&self.investors, buyer);
+                let seller: Pubkey = investor_at(&self.investors, seller).pubkey();
+                self.env
+                    .process(
+                        &[instructions::fill_listing(
+                            buyer.pubkey(),
+                            None,
+                            pool,
+                            seller,
+                            shares,
+                        )],
+                        &[buyer],
+                    )
+                    .await
+            }
+            Action::Withdraw { shares } => {
+                self.env
+                    .process(
+                        &[instructions::withdraw_from_pool(
+                            authority.pubkey(),
+                            pool,
+                            pda::get_usdc_address(&authority.pubkey()),
+                            shares,
+                            None,
+                            true,
+                        )],
+                        &[authority],
+                    )
+                    .await
+            }
+            Action::SpendReserve { amount } => {
+                let reserve: PoolReserve =
+                    self.env.account(&pda::find_reserve_address(&pool).0).await;
+                self.env
+                    .process(
+                        &[instructions::spend_reserve(
+                            authority.pubkey(),
+                            pool,
+                            reserve.expenses,
+                            pda::get_usdc_address(&authority.pubkey()),
+                            ReceiptedExpense {
+                                category: ExpenseCategory::Repair,
+                                amount,
+                                recipient: pda::get_usdc_address(&authority.pubkey()),
+                                receipt_hash: [0; 32],
+                            },
+                        )],
+                        &[authority],
+                    )
+                    .await
+            }
+            Action::UpdateNav { nav_per_share } => {
+                self.env
+                    .process(
+                        &[instructions::update_nav(
+                            authority.pubkey(),
+                            pool,
+                            nav_per_share,
+                        )],
+                        &[authority],
+                    )
+                    .await
+            }
+            Action::OpenRedemptionWindow {
+                days,
+                liquidity,
+                from_distribution,
+            } => {
+                let close_date: u64 = self.env.now().await + days as u64 * DAY;
+                self.env
+                    .process(
+                        &[instructions::open_redemption_window(
+                            authority.pubkey(),
+                            pool,
+                            close_date,
+                            liquidity,
+                            source(from_distribution),
+                        )],
+                        &[authority],
+                    )
+                    .await
+            }
+            Action::Tender { investor, shares } => {
+                let investor: &Keypair = investor_at(&self.investors, investor);
+                self.env
+                    .process(
+                        &[instructions::tender_shares(investor.pubkey(), pool, shares)],
+                        &[investor],
+                    )
+                    .await
+            }
+            Action::Settle { investor } => {
+                let holder: Pubkey = investor_at(&self.investors, investor).pubkey();
+                self.env
+                    .process(
+                        &[instructions::settle_tender(
+                            authority.pubkey(),
+                            pool,
+                            holder,
+                        )],
+                        &[authority],
+                    )
+                    .await
+            }
+            Action::CloseRedemptionWindow => {
+                let window: Pubkey = pda::find_redemption_window_address(&pool).0;
+                let from_distribution: bool =
+                    match fetch::<RedemptionWindow>(&mut self.env, &window).await {
+                        Some(window) => window.source == RedemptionSource::Distribution,
+                        None => false,
+                    };
+                self.env
+                    .process(
+                        &[instructions::close_redemption_window(
+                            authority.pubkey(),
+                            pool,
+                            source(from_distribution),
+                        )],
+                        &[authority],
+                    )
+                    .await
+            }
+            Action::Transition { status } => {
+                let status: PoolStatus = TRANSITIONS[status as usize % TRANSITIONS.len()];
+                self.env
+                    .process(
+                        &[instructions::transition_pool(
+                            authority.pubkey(),
+                            pool,
+                            status,
+                        )],
+                        &[authority],
+                    )
+                    .await
+            }
+            Action::Activate => {
+                self.env
+                    .process(
+                        &[instructions::activate_pool(
+                            authority.pubkey(),
+                            self.distribution_authority.pubkey(),
+                            pool,
+                        )],
+                        &[authority, &self.distribution_authority],
+                    )
+                    .await
+            }
+            Action::ClosePool => {
+                self.env
+                    .process(
+                        &[instructions::close_pool(
+                            authority.pubkey(),
+                            self.distribution_authority.pubkey(),
+                            pool,
+                        )],
+                        &[authority, &self.distribution_authority],
+                    )
+                    .await
+            }
+            Action::Refund { investor, shares } => {
+                let investor: &Keypair = investor_at(&self.investors, investor);
+                self.env
+                    .process(
+                        &[instructions::refund_shares(investor.pubkey(), pool, shares)],
+                        &[investor],
+                    )
+                    .await
+            }
+            Action::Distribute { amount } => {
+                self.env
+                    .fund_usdc(&authority.pubkey(), amount.min(MAX_FUNDING))
+                    .await;
+                self.env
+                    .process(
+                        &[instructions::distribute(
+                            authority.pubkey(),
+                            self.distribution_authority.pubkey(),
+                            pool,
+                            0,
+                            amount,
+                            None,
+                        )],
+                        &[authority, &self.distribution_authority],
+                    )
+                    .await
+            }
+            Action::Claim { investor, rewards } => {
+                let investor: &Keypair = investor_at(&self.investors, investor);
+                self.env
+                    .process(
+                        &[instructions::claim_rewards(
+                            investor.pubkey(),
+                            self.distribution_authority.pubkey(),
+                            pool,
+                            0,
+                            rewards,
+                        )],
+                        &[investor, &self.distribution_authority],
+                    )
+                    .await
+            }
+            Action::Warp { days } => {
+                let now: u64 = self.env.now().await;
+                self.env.warp_to(now + days as u64 * DAY).await;
+                Ok(())
+            }
+        };
+    }
+
+    pub async fn check_invariants(&mut self) {
+        let address: Pubkey = self.pool.address;
+        let pool: Pool = self.env.account(&address).await;
+        let supply: u64 = self.mint_supply().await;
+        assert!(
+            pool.minted <= pool.shares,
+            "minted {} exceeds shares {}",
+            pool.minted,
+            pool.shares
+        );
+        assert_eq!(supply, pool.minted, "share supply differs from minted");
+
+        // Locked shares are all held in the escrow
+        if self.locked {
+            let mut outstanding: u64 = 0;
+            for holder in self
+                .investors
+                .iter()
+                .map(Signer::pubkey)
+                .collect::<Vec<_>>()
+            {
+                let lockup: Pubkey = pda::find_lockup_address(&address, &holder).0;
+                if let Some(lockup) = fetch::<ShareLockup>(&mut self.env, &lockup).await {
+                    assert!(
+                        lockup.released <= lockup.locked,
+                        "released {} exceeds locked {}",
+                        lockup.released,
+                        lockup.locked
+                    );
+                    outstanding += lockup.locked - lockup.released;
+                }
+            }
+            let escrow: Pubkey = pda::find_escrow_address(&address).0;
+            assert_eq!(
+                balance(&mut self.env, &escrow).await,
+                outstanding,
+                "escrow differs from lockups"
+            );
+        }
+
+        // Listed shares are all held by their listing
+        for seller in self
+            .investors
+            .iter()
+            .map(Signer::pubkey)
+            .collect::<Vec<_>>()
+        {
+            let listing: Pubkey = pda::find_listing_address(&address, &seller).0;
+            if let Some(state) = fetch::<Listing>(&mut self.env, &listing).await {
+                let held: u64 =
+                    balance(&mut self.env, &pda::get_shares_address(&address, &listing)).await;
+                assert_eq!(held, state.shares, "listing escrow differs from listing");
+            }
+        }
+
+        // Tendered shares are held by the window, which can pay for what it will fill
+        let window: Pubkey = pda::find_redemption_window_address(&address).0;
+        if let Some(state) = fetch::<RedemptionWindow>(&mut self.env, &window).await {
+            let held: u64 =
+                balance(&mut self.env, &pda::get_shares_address(&address, &window)).await;
+            assert_eq!(
+                held, state.unsettled,
+                "window escrow differs from unsettled"
+            );
+
+            let liquidity: u64 = balance(&mut self.env, &pda::get_usdc_address(&window)).await;
+            let owed: u128 =
+                state.get_filled_shares(state.unsettled) as u128 * state.price_per_share as u128;
+            assert!(
+                liquidity as u128 >= owed,
+                "window holds {liquidity} but owes {owed}"
+            );
+        }
+
+        // The reserve vault holds exactly what was set aside and not spent
+        let reserve: Pubkey = pda::find_reserve_address(&address).0;
+        if let Some(state) = fetch::<PoolReserve>(&mut self.env, &reserve).await {
+            let vault: u64 =
+                balance(&mut self.env, &pda::find_reserve_vault_address(&address).0).await;
+            assert_eq!(
+                vault,
+                state.funded - state.spent,
+                "reserve vault differs from reserve"
+            );
+        }
+
+        // The distribution vault covers every unclaimed reward
+        let distribution: Pubkey = pda::find_distribution_address(&address, 0).0;
+        if let Some(state) = fetch::<Distribution>(&mut self.env, &distribution).await {
+            assert!(
+                state.claimed <= state.rewards,
+                "claimed {} exceeds rewards {}",
+                state.claimed,
+                state.rewards
+            );
+            let vault: u64 = balance(&mut self.env, &pda::get_usdc_address(&distribution)).await;
+            assert!(
+                vault >= state.rewards - state.claimed,
+                "vault holds {vault} but owes {}",
+                state.rewards - state.claimed
+            );
+        }
+    }
+
+    async fn mint_supply(&mut self) -> u64 {
+        let account = self.env.raw_account(&self.pool.mint).await.unwrap();
+        spl_token::state::Mint::unpack(&account.data)
+            .unwrap()
+            .supply
+    }
+}
+
+async fn fetch<T: anchor_lang::AccountDeserialize>(
+    env: &mut TestEnv,
+    address: &Pubkey,
+) -> Option<T> {
+    let account = env.raw_account(address).await?;
+    T::try_deserialize(&mut &account.data[..]).ok()
+}
+
+async fn balance(env: &mut TestEnv, address: &Pubkey) -> u64 {
+    match env.raw_account(address).await {
+        Some(_) => env.token_balance(address).await,
+        None => 0,
+    }
+}
+
+fn investor_at(investors: &[Keypair], index: u8) -> &Keypair {
+    &investors[index as usize % investors.len()]
+}
+
+fn source(from_distribution: bool) -> RedemptionSource {
+    match from_distribution {
+        true => RedemptionSource::Distribution,
+        false => RedemptionSource::Pool,
+    }
+}
@@ -0,0 +1,1690 @@
+//! One test per `ErrorCode` that some instruction raises.
+//!
+//! The date checks that would raise `StartDatePassed` and `MaturityDatePassed` are
+//! commented out in the handlers, so those variants have no test. Neither do
+//! `InvalidSeedSharesRatio` and `SeedRoundsNotCompleted`, which nothing raises, nor
+//! the deprecated `PoolNotClosed` and `PoolClosed`.
+
+mod common;
+
+use anchor_lang::{prelude::Pubkey, InstructionData, Space};
+use common::{assert_error, TestEnv, TestPool, DAY, USDC};
+use octo_client::{instructions, pda};
+use octo_program::{
+    instruction as ix, AssetTranche, BuyerEligibility, Distribution, ErrorCode, ExpenseCategory,
+    LockLot, LockupConfig, Pool, PoolStatus, PoolTemplateTerms, ReceiptedExpense, RedemptionSource,
+    RevenueBucket, ShareLockup, VehicleAsset, VehicleAssetDetails, VehicleStatus,
+};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    signature::{Keypair, Signer},
+};
+
+const LOCKUP: LockupConfig = LockupConfig {
+    cliff: 10 * DAY,
+    duration: 0,
+};
+
+/// A pool starting tomorrow and an investor holding 1,000 USDC.
+async fn setup(lockup: Option<LockupConfig>) -> (TestEnv, TestPool, Keypair) {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool: TestPool = env.inject_pool(100, start_date, lockup).await;
+    let investor: Keypair = env.create_user().await;
+    env.fund_usdc(&investor.pubkey(), 1_000 * USDC).await;
+    (env, pool, investor)
+}
+
+async fn buy(env: &mut TestEnv, pool: &TestPool, investor: &Keypair, shares: u64) {
+    let locked: bool = env.account::<Pool>(&pool.address).await.lockup.is_some();
+    env.process(
+        &[instructions::buy_shares(
+            investor.pubkey(),
+            None,
+            pool.address,
+            shares,
+            locked,
+        )],
+        &[investor],
+    )
+    .await
+    .unwrap();
+}
+
+/// Closes the pool and returns the distribution authority.
+async fn close(env: &mut TestEnv, pool: &TestPool) -> Keypair {
+    let distribution_authority: Keypair = Keypair::new();
+    env.process(
+        &[instructions::close_pool(
+            pool.authority.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+        )],
+        &[&pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+    distribution_authority
+}
+
+/// Sets the payer up as grand authority and gives `pool` a funded fleet operator.
+async fn configure_revenue(env: &mut TestEnv, pool: &TestPool) -> Keypair {
+    let operator: Keypair = env.create_user().await;
+    env.fund_usdc(&operator.pubkey(), 100 * USDC).await;
+    let payer: Pubkey = env.payer();
+    env.process(&[instructions::initialize_program(payer, payer)], &[])
+        .await
+        .unwrap();
+    env.configure_reserve(pool, 0, 0).await;
+    env.process(
+        &[instructions::configure_revenue(
+            pool.authority.pubkey(),
+            pool.address,
+            operator.pubkey(),
+            3_000,
+            1_000,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    operator
+}
+
+fn vehicle_details(custodian: Pubkey) -> VehicleAssetDetails {
+    VehicleAssetDetails {
+        vin_hash: Pubkey::new_unique().to_bytes(),
+        make: "Toyota".to_string(),
+        model: "Corolla".to_string(),
+        purchase_price: 12_000 * USDC,
+        jurisdiction: "NG-LA".to_string(),
+        custodian,
+        title_hash: [0; 32],
+    }
+}
+
+/// Sets the payer up as grand authority and approves `creator`.
+async fn approve_creator(env: &mut TestEnv, creator: Pubkey, can_create: bool) {
+    let payer: Pubkey = env.payer();
+    env.process(
+        &[
+            instructions::initialize_program(payer, payer),
+            instructions::add_pool_creator(payer, creator, can_create),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+}
+
+async fn open_window(env: &mut TestEnv, pool: &TestPool, close_date: u64, liquidity: u64) {
+    env.process(
+        &[instructions::open_redemption_window(
+            pool.authority.pubkey(),
+            pool.address,
+            close_date,
+            liquidity,
+            RedemptionSource::Pool,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn signer_not_authorized() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let intruder: Keypair = env.create_user().await;
+    let payer: Pubkey = env.payer();
+    env.process(&[instructions::initialize_program(payer, payer)], &[])
+        .await
+        .unwrap();
+
+    let result = env
+        .process(
+            &[instructions::update_program_grand_authority(
+                intruder.pubkey(),
+                intruder.pubkey(),
+            )],
+            &[&intruder],
+        )
+        .await;
+    assert_error(result, ErrorCode::SignerNotAuthorized);
+}
+
+#[tokio::test]
+async fn invalid_pool_state() {
+    let (mut env, pool, investor) = setup(None).await;
+    close(&mut env, &pool).await;
+
+    let result = env
+        .process(
+            &[instructions::buy_shares(
+                investor.pubkey(),
+                None,
+                pool.address,
+                1,
+                false,
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidPoolState);
+}
+
+#[tokio::test]
+async fn minimum_shares() {
+    let (mut env, pool, investor) = setup(None).await;
+
+    let result = env
+        .process(
+            &[instructions::buy_shares(
+                investor.pubkey(),
+                None,
+                pool.address,
+                0,
+                false,
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::MinimumShares);
+}
+
+#[tokio::test]
+async fn minimum_deposit() {
+    let mut env: TestEnv = TestEnv::start_with_metadata().await;
+    env.create_collection().await;
+    let creator: Keypair = env.create_user().await;
+    env.fund_usdc(&creator.pubkey(), 1_000 * USDC).await;
+    let start_date: u64 = env.now().await + DAY;
+
+    let result = env
+        .process(
+            &[instructions::create_pool(
+                creator.pubkey(),
+                ix::CreatePool {
+                    reference: Pubkey::new_unique(),
+                    authority: creator.pubkey(),
+                    shares: 1_000,
+                    deposit: USDC,
+                    name: "BlockRide Pool".to_string(),
+                    symbol: "BRP".to_string(),
+                    uri: "https://blockride.xyz/pool.json".to_string(),
+                    start_date,
+                    maturity_date: start_date + 365 * DAY,
+                    apy: 12,
+                    lockup: None,
+                },
+                0,
+                0,
+            )],
+            &[&creator],
+        )
+        .await;
+    assert_error(result, ErrorCode::MinimumDeposit);
+}
+
+#[tokio::test]
+async fn exceeds_available_shares() {
+    let (mut env, pool, investor) = setup(None).await;
+
+    let result = env
+        .process(
+            &[instructions::buy_shares(
+                investor.pubkey(),
+                None,
+                pool.address,
+                101,
+                false,
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::ExceedsAvailableShares);
+}
+
+#[tokio::test]
+async fn insufficient_distribution_usdc_balance() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 10).await;
+    let distribution_authority: Keypair = close(&mut env, &pool).await;
+    // The investor's shares have earned rewards the distribution was never funded with
+    let mut state: Pool = env.account(&pool.address).await;
+    state.reward_per_share = Pool::REWARD_PRECISION * USDC as u128 / 10;
+    env.set_program_account(&pool.address, &state, 8 + Pool::INIT_SPACE);
+
+    let result = env
+        .process(
+            &[instructions::claim_rewards(
+                investor.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                USDC,
+            )],
+            &[&investor, &distribution_authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::InsufficientDistributionUSDCBalance);
+}
+
+#[tokio::test]
+async fn insufficient_pool_usdc_balance() {
+    let (mut env, pool, _) = setup(None).await;
+    env.inject_vehicle_asset(&pool).await;
+    let to: Pubkey = env.fund_usdc(&pool.authority.pubkey(), 0).await;
+
+    let result = env
+        .process(
+            &[instructions::withdraw_from_pool(
+                pool.authority.pubkey(),
+                pool.address,
+                to,
+                1,
+                None,
+                false,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::InsufficientPoolUSDCBalance);
+}
+
+#[tokio::test]
+async fn unclaimed_distribution_rewards() {
+    let (mut env, pool, _) = setup(None).await;
+    let distribution_authority: Keypair = close(&mut env, &pool).await;
+    env.fund_usdc(&pool.authority.pubkey(), 10 * USDC).await;
+    env.process(
+        &[instructions::distribute(
+            pool.authority.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+            0,
+            10 * USDC,
+            None,
+        )],
+        &[&pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .process(
+            &[instructions::close_pool_accounts(
+                pool.authority.pubkey(),
+                pool.address,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::UnclaimedDistributionRewards);
+}
+
+#[tokio::test]
+async fn non_zero_pool_usdc_balance() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 10).await;
+    close(&mut env, &pool).await;
+
+    let result = env
+        .process(
+            &[instructions::close_pool_accounts(
+                pool.authority.pubkey(),
+                pool.address,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::NonZeroPoolUSDCBalance);
+}
+
+#[tokio::test]
+async fn non_zero_distribution_usdc_balance() {
+    let (mut env, pool, _) = setup(None).await;
+    close(&mut env, &pool).await;
+    let distribution: Pubkey = pda::find_distribution_address(&pool.address, 0).0;
+    env.fund_usdc(&distribution, USDC).await;
+
+    let result = env
+        .process(
+            &[instructions::close_pool_accounts(
+                pool.authority.pubkey(),
+                pool.address,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::NonZeroDistributionUSDCBalance);
+}
+
+#[tokio::test]
+async fn lockup_accounts_missing() {
+    let (mut env, pool, investor) = setup(Some(LOCKUP)).await;
+
+    let result = env
+        .process(
+            &[instructions::buy_shares(
+                investor.pubkey(),
+                None,
+                pool.address,
+                1,
+                false,
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::LockupAccountsMissing);
+}
+
+#[tokio::test]
+async fn too_many_lockups() {
+    let (mut env, pool, investor) = setup(Some(LOCKUP)).await;
+    let start_date: u64 = env.account::<Pool>(&pool.address).await.start_date;
+    let (lockup, bump): (Pubkey, u8) = pda::find_lockup_address(&pool.address, &investor.pubkey());
+    env.set_program_account(
+        &lockup,
+        &ShareLockup {
+            pool: pool.address,
+            holder: investor.pubkey(),
+            locked: ShareLockup::MAX_LOTS as u64,
+            released: 0,
+            bump,
+            vested: 0,
+            lots: (1..=ShareLockup::MAX_LOTS as u64)
+                .map(|day| LockLot {
+                    shares: 1,
+                    locked_at: start_date + day * DAY,
+                })
+                .collect(),
+        },
+        8 + ShareLockup::INIT_SPACE,
+    );
+
+    let result = env
+        .process(
+            &[instructions::buy_shares(
+                investor.pubkey(),
+                None,
+                pool.address,
+                1,
+                true,
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::TooManyLockups);
+}
+
+#[tokio::test]
+async fn pool_not_locked() {
+    let (mut env, pool, investor) = setup(None).await;
+    let escrow: Pubkey = pda::find_escrow_address(&pool.address).0;
+    env.set_token_account(&escrow, &pool.mint, &pool.address, 0);
+    let (lockup, bump): (Pubkey, u8) = pda::find_lockup_address(&pool.address, &investor.pubkey());
+    env.set_program_account(
+        &lockup,
+        &ShareLockup {
+            pool: pool.address,
+            holder: investor.pubkey(),
+            locked: 1,
+            released: 0,
+            bump,
+            vested: 0,
+            lots: vec![LockLot {
+                shares: 1,
+                locked_at: 0,
+            }],
+        },
+        8 + ShareLockup::INIT_SPACE,
+    );
+
+    let result = env
+        .process(
+            &[instructions::release_shares(
+                investor.pubkey(),
+                pool.address,
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::PoolNotLocked);
+}
+
+#[tokio::test]
+async fn no_shares_to_release() {
+    let (mut env, pool, investor) = setup(Some(LOCKUP)).await;
+    buy(&mut env, &pool, &investor, 10).await;
+
+    let result = env
+        .process(
+            &[instructions::release_shares(
+                investor.pubkey(),
+                pool.address,
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::NoSharesToRelease);
+}
+
+#[tokio::test]
+async fn invalid_listing_price() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 10).await;
+
+    let result = env
+        .process(
+            &[instructions::list_shares(
+                investor.pubkey(),
+                pool.address,
+                10,
+                0,
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidListingPrice);
+}
+
+#[tokio::test]
+async fn listing_price_changed() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 10).await;
+    env.process(
+        &[instructions::list_shares(
+            investor.pubkey(),
+            pool.address,
+            5,
+            USDC,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .process(
+            &[instructions::list_shares(
+                investor.pubkey(),
+                pool.address,
+                5,
+                2 * USDC,
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::ListingPriceChanged);
+}
+
+#[tokio::test]
+async fn exceeds_listed_shares() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 10).await;
+    env.process(
+        &[instructions::list_shares(
+            investor.pubkey(),
+            pool.address,
+            5,
+            USDC,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+    let buyer: Keypair = env.create_user().await;
+    env.fund_usdc(&buyer.pubkey(), 100 * USDC).await;
+
+    let result = env
+        .process(
+            &[instructions::fill_listing(
+                buyer.pubkey(),
+                None,
+                pool.address,
+                investor.pubkey(),
+                6,
+            )],
+            &[&buyer],
+        )
+        .await;
+    assert_error(result, ErrorCode::ExceedsListedShares);
+}
+
+#[tokio::test]
+async fn redemption_window_open() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 10).await;
+    let close_date: u64 = env.now().await + DAY;
+    open_window(&mut env, &pool, close_date, USDC).await;
+
+    let result = env
+        .process(
+            &[instructions::open_redemption_window(
+                pool.authority.pubkey(),
+                pool.address,
+                close_date,
+                USDC,
+                RedemptionSource::Pool,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::RedemptionWindowOpen);
+}
+
+#[tokio::test]
+async fn redemption_window_not_open() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 10).await;
+    let close_date: u64 = env.now().await + DAY;
+    open_window(&mut env, &pool, close_date, USDC).await;
+    env.warp_to(close_date).await;
+    env.process(
+        &[instructions::close_redemption_window(
+            pool.authority.pubkey(),
+            pool.address,
+            RedemptionSource::Pool,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .process(
+            &[instructions::tender_shares(
+                investor.pubkey(),
+                pool.address,
+                1,
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::RedemptionWindowNotOpen);
+}
+
+#[tokio::test]
+async fn redemption_window_ended() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 10).await;
+    let close_date: u64 = env.now().await + DAY;
+    open_window(&mut env, &pool, close_date, USDC).await;
+    env.warp_to(close_date).await;
+
+    let result = env
+        .process(
+            &[instructions::tender_shares(
+                investor.pubkey(),
+                pool.address,
+                1,
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::RedemptionWindowEnded);
+}
+
+#[tokio::test]
+async fn redemption_window_not_ended() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 10).await;
+    let close_date: u64 = env.now().await + DAY;
+    open_window(&mut env, &pool, close_date, USDC).await;
+    env.process(
+        &[instructions::tender_shares(
+            investor.pubkey(),
+            pool.address,
+            1,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .process(
+            &[instructions::settle_tender(
+                investor.pubkey(),
+                pool.address,
+                investor.pubkey(),
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::RedemptionWindowNotEnded);
+}
+
+#[tokio::test]
+async fn invalid_redemption_close_date() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 10).await;
+    let now: u64 = env.now().await;
+
+    let result = env
+        .process(
+            &[instructions::open_redemption_window(
+                pool.authority.pubkey(),
+                pool.address,
+                now,
+                USDC,
+                RedemptionSource::Pool,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidRedemptionCloseDate);
+}
+
+#[tokio::test]
+async fn invalid_redemption_liquidity() {
+    let (mut env, pool, _) = setup(None).await;
+    let close_date: u64 = env.now().await + DAY;
+
+    let result = env
+        .process(
+            &[instructions::open_redemption_window(
+                pool.authority.pubkey(),
+                pool.address,
+                close_date,
+                0,
+                RedemptionSource::Pool,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidRedemptionLiquidity);
+}
+
+#[tokio::test]
+async fn unsettled_redemption_tenders() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 10).await;
+    let close_date: u64 = env.now().await + DAY;
+    open_window(&mut env, &pool, close_date, USDC).await;
+    env.process(
+        &[instructions::tender_shares(
+            investor.pubkey(),
+            pool.address,
+            1,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+    env.warp_to(close_date).await;
+
+    let result = env
+        .process(
+            &[instructions::close_redemption_window(
+                pool.authority.pubkey(),
+                pool.address,
+                RedemptionSource::Pool,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::UnsettledRedemptionTenders);
+}
+
+#[tokio::test]
+async fn distribution_accounts_missing() {
+    let (mut env, pool, _) = setup(None).await;
+    let close_date: u64 = env.now().await + DAY;
+    let mut instruction = instructions::open_redemption_window(
+        pool.authority.pubkey(),
+        pool.address,
+        close_date,
+        USDC,
+        RedemptionSource::Pool,
+    );
+    instruction.data = ix::OpenRedemptionWindow {
+        close_date,
+        liquidity: USDC,
+        source: RedemptionSource::Distribution,
+    }
+    .data();
+
+    let result = env.process(&[instruction], &[&pool.authority]).await;
+    assert_error(result, ErrorCode::DistributionAccountsMissing);
+}
+
+#[tokio::test]
+async fn invalid_nav() {
+    let (mut env, pool, _) = setup(None).await;
+
+    let result = env
+        .process(
+            &[instructions::update_nav(
+                pool.authority.pubkey(),
+                pool.address,
+                0,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidNav);
+}
+
+#[tokio::test]
+async fn nav_change_too_large() {
+    let (mut env, pool, _) = setup(None).await;
+
+    let result = env
+        .process(
+            &[instructions::update_nav(
+                pool.authority.pubkey(),
+                pool.address,
+                2 * USDC,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::NavChangeTooLarge);
+
+    // Updates within a period are capped against its opening NAV, so they can't compound
+    env.process(
+        &[instructions::update_nav(
+            pool.authority.pubkey(),
+            pool.address,
+            1_100_000,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    let result = env
+        .process(
+            &[instructions::update_nav(
+                pool.authority.pubkey(),
+                pool.address,
+                1_200_000,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::NavChangeTooLarge);
+}
+
+#[tokio::test]
+async fn stale_nav() {
+    let (mut env, pool, investor) = setup(None).await;
+    let start_date: u64 = env.account::<Pool>(&pool.address).await.start_date;
+    env.warp_to(start_date + Pool::MAX_NAV_AGE + 1).await;
+
+    let result = env
+        .process(
+            &[instructions::buy_shares(
+                investor.pubkey(),
+                None,
+                pool.address,
+                1,
+                false,
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::StaleNav);
+}
+
+#[tokio::test]
+async fn math_overflow() {
+    let (mut env, pool, investor) = setup(None).await;
+
+    let result = env
+        .process(
+            &[instructions::buy_shares(
+                investor.pubkey(),
+                None,
+                pool.address,
+                u64::MAX,
+                false,
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::MathOverflow);
+}
+
+#[tokio::test]
+async fn math_underflow() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 10).await;
+    let distribution_authority: Keypair = close(&mut env, &pool).await;
+    let (distribution, bump): (Pubkey, u8) = pda::find_distribution_address(&pool.address, 0);
+    env.set_program_account(
+        &distribution,
+        &Distribution {
+            version: Distribution::VERSION,
+            pool: pool.address,
+            authority: distribution_authority.pubkey(),
+            rewards: 0,
+            claimed: 1,
+            bump,
+            allocated: 0,
+            id: 0,
+            reserved: [0; 23],
+        },
+        8 + Distribution::INIT_SPACE,
+    );
+
+    let result = env
+        .process(
+            &[instructions::claim_rewards(
+                investor.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                0,
+            )],
+            &[&investor, &distribution_authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::MathUnderflow);
+}
+
+#[tokio::test]
+async fn creator_not_authorized() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let creator: Keypair = env.create_user().await;
+    approve_creator(&mut env, creator.pubkey(), false).await;
+
+    let result = env
+        .process(
+            &[instructions::register_vehicle_asset(
+                creator.pubkey(),
+                vehicle_details(creator.pubkey()),
+            )],
+            &[&creator],
+        )
+        .await;
+    assert_error(result, ErrorCode::CreatorNotAuthorized);
+}
+
+#[tokio::test]
+async fn vehicle_details_too_long() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let creator: Keypair = env.create_user().await;
+    approve_creator(&mut env, creator.pubkey(), true).await;
+    let mut details: VehicleAssetDetails = vehicle_details(creator.pubkey());
+    details.model = "C".repeat(VehicleAssetDetails::MAX_MODEL_LEN + 1);
+
+    let result = env
+        .process(
+            &[instructions::register_vehicle_asset(
+                creator.pubkey(),
+                details,
+            )],
+            &[&creator],
+        )
+        .await;
+    assert_error(result, ErrorCode::VehicleDetailsTooLong);
+}
+
+#[tokio::test]
+async fn vehicle_asset_already_linked() {
+    let (mut env, pool, _) = setup(None).await;
+    let creator: Pubkey = pool.authority.pubkey();
+    approve_creator(&mut env, creator, true).await;
+    let details: VehicleAssetDetails = vehicle_details(creator);
+    let vin_hash: [u8; 32] = details.vin_hash;
+    env.process(
+        &[
+            instructions::register_vehicle_asset(creator, details),
+            instructions::link_vehicle_asset(creator, pool.address, &vin_hash),
+        ],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .process(
+            &[instructions::link_vehicle_asset(
+                creator,
+                pool.address,
+                &vin_hash,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::VehicleAssetAlreadyLinked);
+}
+
+#[tokio::test]
+async fn no_vehicle_asset() {
+    let (mut env, pool, _) = setup(None).await;
+    let to: Pubkey = env.fund_usdc(&pool.authority.pubkey(), 0).await;
+
+    let result = env
+        .process(
+            &[instructions::withdraw_from_pool(
+                pool.authority.pubkey(),
+                pool.address,
+                to,
+                0,
+                None,
+                false,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::NoVehicleAsset);
+}
+
+#[tokio::test]
+async fn invalid_revenue_split() {
+    let (mut env, pool, _) = setup(None).await;
+    let payer: Pubkey = env.payer();
+    env.process(&[instructions::initialize_program(payer, payer)], &[])
+        .await
+        .unwrap();
+    env.configure_reserve(&pool, 0, 0).await;
+
+    let result = env
+        .process(
+            &[instructions::configure_revenue(
+                pool.authority.pubkey(),
+                pool.address,
+                payer,
+                9_000,
+                2_000,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidRevenueSplit);
+}
+
+#[tokio::test]
+async fn invalid_revenue_amount() {
+    let (mut env, pool, _) = setup(None).await;
+    let operator: Keypair = configure_revenue(&mut env, &pool).await;
+
+    let result = env
+        .process(
+            &[instructions::report_revenue(
+                operator.pubkey(),
+                pool.address,
+                0,
+                true,
+            )],
+            &[&operator],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidRevenueAmount);
+}
+
+#[tokio::test]
+async fn invalid_revenue_bucket() {
+    let (mut env, pool, _) = setup(None).await;
+    let operator: Keypair = configure_revenue(&mut env, &pool).await;
+    env.process(
+        &[instructions::report_revenue(
+            operator.pubkey(),
+            pool.address,
+            10 * USDC,
+            true,
+        )],
+        &[&operator],
+    )
+    .await
+    .unwrap();
+    let to: Pubkey = env.fund_usdc(&pool.authority.pubkey(), 0).await;
+
+    // The maintenance bucket holds the reserve, which is only spent against receipts
+    for bucket in [RevenueBucket::Maintenance, RevenueBucket::Investors] {
+        let result = env
+            .process(
+                &[instructions::withdraw_revenue(
+                    pool.authority.pubkey(),
+                    pool.address,
+                    bucket,
+                    to,
+                    USDC,
+                )],
+                &[&pool.authority],
+            )
+            .await;
+        assert_error(result, ErrorCode::InvalidRevenueBucket);
+    }
+}
+
+#[tokio::test]
+async fn invalid_reserve_share() {
+    let (mut env, pool, _) = setup(None).await;
+
+    let result = env
+        .process(
+            &[instructions::configure_reserve(
+                pool.authority.pubkey(),
+                pool.address,
+                10_001,
+                0,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidReserveShare);
+}
+
+#[tokio::test]
+async fn insufficient_reserve_balance() {
+    let (mut env, pool, _) = setup(None).await;
+    env.configure_reserve(&pool, 0, 0).await;
+    let to: Pubkey = env.fund_usdc(&pool.authority.pubkey(), 0).await;
+
+    let result = env
+        .process(
+            &[instructions::spend_reserve(
+                pool.authority.pubkey(),
+                pool.address,
+                0,
+                to,
+                ReceiptedExpense {
+                    category: ExpenseCategory::Insurance,
+                    amount: USDC,
+                    recipient: to,
+                    receipt_hash: [0; 32],
+                },
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::InsufficientReserveBalance);
+}
+
+#[tokio::test]
+async fn reserve_below_target() {
+    let (mut env, pool, _) = setup(None).await;
+    env.configure_reserve(&pool, 0, 10 * USDC).await;
+    let distribution_authority: Keypair = close(&mut env, &pool).await;
+    env.fund_usdc(&pool.authority.pubkey(), 10 * USDC).await;
+
+    let result = env
+        .process(
+            &[instructions::distribute(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                10 * USDC,
+                None,
+            )],
+            &[&pool.authority, &distribution_authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::ReserveBelowTarget);
+}
+
+#[tokio::test]
+async fn vehicle_asset_not_linked() {
+    let (mut env, pool, _) = setup(None).await;
+    let start_date: u64 = env.now().await + DAY;
+    let other_pool: TestPool = env.inject_pool(100, start_date, None).await;
+    let vehicle_asset: Pubkey = env.inject_vehicle_asset(&other_pool).await;
+    let vin_hash: [u8; 32] = env.account::<VehicleAsset>(&vehicle_asset).await.vin_hash;
+
+    let result = env
+        .process(
+            &[instructions::add_asset_tranche(
+                pool.authority.pubkey(),
+                pool.address,
+                0,
+                &vin_hash,
+                10 * USDC,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::VehicleAssetNotLinked);
+}
+
+#[tokio::test]
+async fn invalid_tranche_allocation() {
+    let (mut env, pool, _) = setup(None).await;
+    let vehicle_asset: Pubkey = env.inject_vehicle_asset(&pool).await;
+    let vin_hash: [u8; 32] = env.account::<VehicleAsset>(&vehicle_asset).await.vin_hash;
+
+    let result = env
+        .process(
+            &[instructions::add_asset_tranche(
+                pool.authority.pubkey(),
+                pool.address,
+                0,
+                &vin_hash,
+                0,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidTrancheAllocation);
+}
+
+#[tokio::test]
+async fn exceeds_pool_principal() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 50).await;
+    env.add_asset_tranche(&pool, 30 * USDC).await;
+    let vehicle_asset: Pubkey = env.inject_vehicle_asset(&pool).await;
+    let vin_hash: [u8; 32] = env.account::<VehicleAsset>(&vehicle_asset).await.vin_hash;
+
+    let result = env
+        .process(
+            &[instructions::add_asset_tranche(
+                pool.authority.pubkey(),
+                pool.address,
+                1,
+                &vin_hash,
+                21 * USDC,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::ExceedsPoolPrincipal);
+}
+
+#[tokio::test]
+async fn exceeds_tranche_allocation() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 20).await;
+    let tranche: u16 = env.add_asset_tranche(&pool, 10 * USDC).await;
+    let to: Pubkey = env.fund_usdc(&pool.authority.pubkey(), 0).await;
+
+    let result = env
+        .process(
+            &[instructions::withdraw_from_pool(
+                pool.authority.pubkey(),
+                pool.address,
+                to,
+                20,
+                Some(tranche),
+                false,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::ExceedsTrancheAllocation);
+}
+
+#[tokio::test]
+async fn tranche_required() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 10).await;
+    env.add_asset_tranche(&pool, 10 * USDC).await;
+    let to: Pubkey = env.fund_usdc(&pool.authority.pubkey(), 0).await;
+
+    let result = env
+        .process(
+            &[instructions::withdraw_from_pool(
+                pool.authority.pubkey(),
+                pool.address,
+                to,
+                10,
+                None,
+                false,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::TrancheRequired);
+}
+
+/// Gives the pool a single open share class covering all of its shares.
+async fn create_share_class(env: &mut TestEnv, pool: &TestPool, eligibility: BuyerEligibility) {
+    env.process(
+        &[instructions::create_share_class(
+            pool.authority.pubkey(),
+            pool.address,
+            0,
+            100,
+            10,
+            eligibility,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn share_classes_locked() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 10).await;
+
+    let result = env
+        .process(
+            &[instructions::create_share_class(
+                pool.authority.pubkey(),
+                pool.address,
+                0,
+                50,
+                10,
+                BuyerEligibility::Open,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::ShareClassesLocked);
+}
+
+#[tokio::test]
+async fn share_class_required() {
+    let (mut env, pool, investor) = setup(None).await;
+    create_share_class(&mut env, &pool, BuyerEligibility::Open).await;
+
+    let result = env
+        .process(
+            &[instructions::buy_shares(
+                investor.pubkey(),
+                None,
+                pool.address,
+                10,
+                false,
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::ShareClassRequired);
+}
+
+#[tokio::test]
+async fn invalid_share_class() {
+    let (mut env, pool, _) = setup(None).await;
+    close(&mut env, &pool).await;
+
+    let result = env
+        .process(
+            &[instructions::allocate_class_rewards(pool.address, 0)],
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidShareClass);
+}
+
+#[tokio::test]
+async fn buyer_not_eligible() {
+    let (mut env, pool, investor) = setup(None).await;
+    create_share_class(&mut env, &pool, BuyerEligibility::Approved).await;
+
+    let result = env
+        .process(
+            &[instructions::buy_class_shares(
+                investor.pubkey(),
+                None,
+                pool.address,
+                0,
+                10,
+            )],
+            &[&investor],
+        )
+        .await;
+    assert_error(result, ErrorCode::BuyerNotEligible);
+}
+
+#[tokio::test]
+async fn exceeds_class_rewards() {
+    let (mut env, pool, investor) = setup(None).await;
+    create_share_class(&mut env, &pool, BuyerEligibility::Open).await;
+    env.process(
+        &[instructions::buy_class_shares(
+            investor.pubkey(),
+            None,
+            pool.address,
+            0,
+            10,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+    let distribution_authority: Keypair = close(&mut env, &pool).await;
+
+    let result = env
+        .process(
+            &[instructions::claim_class_rewards(
+                investor.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                USDC,
+            )],
+            &[&investor, &distribution_authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::ExceedsClassRewards);
+}
+
+#[tokio::test]
+async fn invalid_template_terms() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let creator: Keypair = env.create_user().await;
+
+    let result = env
+        .process(
+            &[instructions::create_pool_template(
+                creator.pubkey(),
+                Pubkey::new_unique(),
+                PoolTemplateTerms {
+                    authority: creator.pubkey(),
+                    name: "BlockRide Fleet Series Twelve".to_string(),
+                    symbol: "BRF".to_string(),
+                    uri_pattern: "https://blockride.xyz/fleet-{}.json".to_string(),
+                    share_price: USDC,
+                    term: 365 * DAY,
+                    apy: 12,
+                    lockup: None,
+                    eligibility: BuyerEligibility::Open,
+                },
+            )],
+            &[&creator],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidTemplateTerms);
+}
+
+#[tokio::test]
+async fn pool_already_registered() {
+    let (mut env, pool, _investor) = setup(None).await;
+    let payer: Pubkey = env.payer();
+    let creator: Pubkey = pool.authority.pubkey();
+    env.process(&[instructions::initialize_program(payer, payer)], &[])
+        .await
+        .unwrap();
+    env.process(
+        &[instructions::register_pool(
+            payer,
+            pool.address,
+            creator,
+            0,
+            0,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .process(
+            &[instructions::register_pool(
+                payer,
+                pool.address,
+                creator,
+                1,
+                1,
+            )],
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::PoolAlreadyRegistered);
+}
+
+#[tokio::test]
+async fn invalid_distribution() {
+    let (mut env, pool, _) = setup(None).await;
+    close(&mut env, &pool).await;
+
+    // The primary distribution closes with the pool's accounts
+    let result = env
+        .process(
+            &[instructions::close_distribution(
+                pool.authority.pubkey(),
+                pool.address,
+                0,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidDistribution);
+}
+
+#[tokio::test]
+async fn too_many_distributions() {
+    let (mut env, pool, _) = setup(None).await;
+    close(&mut env, &pool).await;
+    let mut state: Pool = env.account(&pool.address).await;
+    state.distributions = u8::MAX;
+    env.set_program_account(&pool.address, &state, 8 + Pool::INIT_SPACE);
+    let distribution_authority: Keypair = Keypair::new();
+
+    // The id after the last one wraps around to 0
+    let distribution: Pubkey = Pubkey::find_program_address(
+        &[b"distribution", pool.address.as_ref(), &[0]],
+        &octo_program::ID,
+    )
+    .0;
+    let mut instruction = instructions::create_distribution(
+        pool.authority.pubkey(),
+        distribution_authority.pubkey(),
+        pool.address,
+        1,
+    );
+    instruction.accounts[3].pubkey = distribution;
+    instruction.accounts[5].pubkey = pda::get_usdc_address(&distribution);
+
+    let result = env
+        .process(&[instruction], &[&pool.authority, &distribution_authority])
+        .await;
+    assert_error(result, ErrorCode::TooManyDistributions);
+}
+
+#[tokio::test]
+async fn open_distributions() {
+    let (mut env, pool, _) = setup(None).await;
+    let distribution_authority: Keypair = close(&mut env, &pool).await;
+    env.process(
+        &[instructions::create_distribution(
+            pool.authority.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+            1,
+        )],
+        &[&pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .process(
+            &[instructions::close_pool_accounts(
+                pool.authority.pubkey(),
+                pool.address,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::OpenDistributions);
+}
+
+#[tokio::test]
+async fn invalid_vehicle_status() {
+    let (mut env, pool, _) = setup(None).await;
+    let vehicle_asset: Pubkey = env.inject_vehicle_asset(&pool).await;
+    let vin_hash: [u8; 32] = env.account::<VehicleAsset>(&vehicle_asset).await.vin_hash;
+    env.process(
+        &[instructions::update_vehicle_asset_status(
+            pool.authority.pubkey(),
+            &vin_hash,
+            VehicleStatus::WrittenOff,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+
+    // A written-off vehicle stays written off
+    let result = env
+        .process(
+            &[instructions::update_vehicle_asset_status(
+                pool.authority.pubkey(),
+                &vin_hash,
+                VehicleStatus::Active,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidVehicleStatus);
+}
+
+#[tokio::test]
+async fn reserve_vault_required() {
+    let (mut env, pool, investor) = setup(None).await;
+    env.inject_vehicle_asset(&pool).await;
+    env.configure_reserve(&pool, 1_000, 0).await;
+    buy(&mut env, &pool, &investor, 10).await;
+    let to: Pubkey = env.fund_usdc(&pool.authority.pubkey(), 0).await;
+
+    // A configured reserve can't be skipped by leaving its vault out
+    let mut instruction: Instruction =
+        instructions::withdraw_from_pool(pool.authority.pubkey(), pool.address, to, 10, None, true);
+    let reserve_vault: Pubkey = pda::find_reserve_vault_address(&pool.address).0;
+    for account in instruction.accounts.iter_mut() {
+        if account.pubkey == reserve_vault {
+            *account = AccountMeta::new_readonly(octo_program::ID, false);
+        }
+    }
+    let result = env.process(&[instruction], &[&pool.authority]).await;
+    assert_error(result, ErrorCode::ReserveVaultRequired);
+}
+
+#[tokio::test]
+async fn vehicle_asset_already_financed() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 20).await;
+    let index: u16 = env.add_asset_tranche(&pool, 10 * USDC).await;
+    let tranche: AssetTranche = env
+        .account(&pda::find_tranche_address(&pool.address, index).0)
+        .await;
+    let vin_hash: [u8; 32] = env
+        .account::<VehicleAsset>(&tranche.vehicle_asset)
+        .await
+        .vin_hash;
+
+    let result = env
+        .process(
+            &[instructions::add_asset_tranche(
+                pool.authority.pubkey(),
+                pool.address,
+                index + 1,
+                &vin_hash,
+                10 * USDC,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::VehicleAssetAlreadyFinanced);
+}
+
+#[tokio::test]
+async fn start_date_not_passed() {
+    let (mut env, pool, _) = setup(None).await;
+
+    let result = env
+        .process(
+            &[instructions::transition_pool(
+                pool.authority.pubkey(),
+                pool.address,
+                PoolStatus::Refunding,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::StartDateNotPassed);
+}
+
+#[tokio::test]
+async fn maturity_date_not_passed() {
+    let (mut env, pool, _) = setup(None).await;
+    let mut state: Pool = env.account(&pool.address).await;
+    state.status = PoolStatus::Active;
+    env.set_program_account(&pool.address, &state, 8 + Pool::INIT_SPACE);
+
+    let result = env
+        .process(
+            &[instructions::transition_pool(
+                pool.authority.pubkey(),
+                pool.address,
+                PoolStatus::Matured,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::MaturityDateNotPassed);
+}
+
+#[tokio::test]
+async fn shares_not_refunded() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 1).await;
+    let mut state: Pool = env.account(&pool.address).await;
+    state.status = PoolStatus::Refunding;
+    env.set_program_account(&pool.address, &state, 8 + Pool::INIT_SPACE);
+
+    let result = env
+        .process(
+            &[instructions::transition_pool(
+                pool.authority.pubkey(),
+                pool.address,
+                PoolStatus::Cancelled,
+            )],
+            &[&pool.authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::SharesNotRefunded);
+}
+
+#[tokio::test]
+async fn exceeds_claimable_rewards() {
+    let (mut env, pool, investor) = setup(None).await;
+    buy(&mut env, &pool, &investor, 10).await;
+    let distribution_authority: Keypair = close(&mut env, &pool).await;
+    env.fund_usdc(&pool.authority.pubkey(), 10 * USDC).await;
+    env.process(
+        &[instructions::distribute(
+            pool.authority.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+            0,
+            10 * USDC,
+            None,
+        )],
+        &[&pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .process(
+            &[instructions::claim_rewards(
+                investor.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                11 * USDC,
+            )],
+            &[&investor, &distribution_authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::ExceedsClaimableRewards);
+}
+
+#[tokio::test]
+async fn reserve_required() {
+    let (mut env, pool, _) = setup(None).await;
+    let operator: Keypair = configure_revenue(&mut env, &pool).await;
+
+    // Once configured, the reserve can't be left out of the revenue waterfall
+    let result = env
+        .process(
+            &[instructions::report_revenue(
+                operator.pubkey(),
+                pool.address,
+                10 * USDC,
+                false,
+            )],
+            &[&operator],
+        )
+        .await;
+    assert_error(result, ErrorCode::ReserveRequired);
+}
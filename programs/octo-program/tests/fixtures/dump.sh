@@ -0,0 +1,7 @@
+#!/usr/bin/env sh
+# Dumps the mainnet Metaplex token-metadata program next to this script. The
+# integration tests that create pools load it from tests/fixtures.
+set -e
+
+cd "$(dirname "$0")"
+solana program dump -u m metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s mpl_token_metadata.so
@@ -0,0 +1,91 @@
+//! Property test over random instruction sequences; see `common::scenario`.
+//!
+//! Amounts are kept to realistic ranges here. The `instruction_sequence` fuzz target in
+//! `fuzz/` drives the same scenario with unbounded inputs.
+
+mod common;
+
+use common::{
+    scenario::{Action, Scenario, INVESTORS, POOL_SHARES},
+    USDC,
+};
+use proptest::prelude::*;
+
+fn investor() -> impl Strategy<Value = u8> {
+    0..INVESTORS as u8
+}
+
+fn buy() -> impl Strategy<Value = Action> {
+    (investor(), 1..=POOL_SHARES / 4)
+        .prop_map(|(investor, shares)| Action::Buy { investor, shares })
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    prop_oneof![
+        4 => buy(),
+        1 => investor().prop_map(|investor| Action::Release { investor }),
+        2 => (investor(), 1..=POOL_SHARES / 4, 1..=2 * USDC).prop_map(
+            |(investor, shares, price_per_share)| Action::List {
+                investor,
+                shares,
+                price_per_share,
+            }
+        ),
+        1 => investor().prop_map(|investor| Action::CancelListing { investor }),
+        2 => (investor(), investor(), 1..=POOL_SHARES / 4).prop_map(
+            |(buyer, seller, shares)| Action::FillListing {
+                buyer,
+                seller,
+                shares,
+            }
+        ),
+        1 => (0..=POOL_SHARES / 4).prop_map(|shares| Action::Withdraw { shares }),
+        1 => (1..=5 * USDC).prop_map(|amount| Action::SpendReserve { amount }),
+        1 => (9 * USDC / 10..=11 * USDC / 10)
+            .prop_map(|nav_per_share| Action::UpdateNav { nav_per_share }),
+        2 => (1..=5_u8, 1..=10 * USDC, any::<bool>()).prop_map(
+            |(days, liquidity, from_distribution)| Action::OpenRedemptionWindow {
+                days,
+                liquidity,
+                from_distribution,
+            }
+        ),
+        2 => (investor(), 1..=POOL_SHARES / 4)
+            .prop_map(|(investor, shares)| Action::Tender { investor, shares }),
+        2 => investor().prop_map(|investor| Action::Settle { investor }),
+        1 => Just(Action::CloseRedemptionWindow),
+        1 => any::<u8>().prop_map(|status| Action::Transition { status }),
+        1 => Just(Action::Activate),
+        1 => Just(Action::ClosePool),
+        1 => (investor(), 1..=POOL_SHARES / 4)
+            .prop_map(|(investor, shares)| Action::Refund { investor, shares }),
+        2 => (1..=100 * USDC).prop_map(|amount| Action::Distribute { amount }),
+        2 => (investor(), 1..=5 * USDC)
+            .prop_map(|(investor, rewards)| Action::Claim { investor, rewards }),
+        2 => (0..=10_u8).prop_map(|days| Action::Warp { days }),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 16,
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn pool_invariants_hold(
+        locked in any::<bool>(),
+        purchases in prop::collection::vec(buy(), 2..6),
+        actions in prop::collection::vec(action(), 1..48),
+    ) {
+        // Seed the pool with a few holders so later actions have shares to work with
+        let actions: Vec<Action> = purchases.into_iter().chain(actions).collect();
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async {
+                Scenario::start(locked).await.run(&actions).await;
+            });
+    }
+}
@@ -0,0 +1,2846 @@
+mod common;
+
+use anchor_lang::{prelude::Pubkey, Space};
+use anchor_spl::token::spl_token::{self, error::TokenError};
+use common::{assert_error, shares_address, TestEnv, DAY, USDC};
+use mpl_token_metadata::{accounts::Metadata, types::CollectionDetails};
+use octo_client::{instructions, pda};
+use octo_program::{
+    instruction::CreatePool, AssetTranche, BuyQuote, BuyerEligibility, ClassPosition, CreatorPools,
+    Distribution, ErrorCode, ExpenseCategory, InvestorPosition, Listing, LockupConfig, NavQuote,
+    Pool, PoolCreatorData, PoolRegistryEntry, PoolReserve, PoolStatus, PoolSummary, PoolTemplate,
+    PoolTemplateTerms, ProjectData, ReceiptedExpense, RedemptionSource, ReserveExpense,
+    RevenueBucket, RevenueConfig, ShareClass, ShareLockup, TrancheSummary, VehicleAsset,
+    VehicleAssetDetails, VehicleStatus,
+};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+#[tokio::test]
+async fn program_administration() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let grand_authority = env.create_user().await;
+    let next_grand_authority = env.create_user().await;
+    let creator: Pubkey = Pubkey::new_unique();
+    let payer: Pubkey = env.payer();
+
+    env.process(
+        &[instructions::initialize_program(
+            payer,
+            grand_authority.pubkey(),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instructions::add_pool_creator(
+            grand_authority.pubkey(),
+            creator,
+            true,
+        )],
+        &[&grand_authority],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instructions::update_program_grand_authority(
+            grand_authority.pubkey(),
+            next_grand_authority.pubkey(),
+        )],
+        &[&grand_authority],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instructions::update_pool_creator(
+            next_grand_authority.pubkey(),
+            creator,
+            false,
+        )],
+        &[&next_grand_authority],
+    )
+    .await
+    .unwrap();
+
+    let project: ProjectData = env.account(&pda::find_project_address().0).await;
+    assert_eq!(project.grand_authority, next_grand_authority.pubkey());
+    let pool_creator: PoolCreatorData = env
+        .account(&pda::find_pool_creator_address(&creator).0)
+        .await;
+    assert_eq!(pool_creator.creator, creator);
+    assert!(!pool_creator.can_create);
+}
+
+#[tokio::test]
+async fn registered_pools_are_enumerable_by_id_and_creator() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let payer: Pubkey = env.payer();
+    env.process(&[instructions::initialize_program(payer, payer)], &[])
+        .await
+        .unwrap();
+    let start_date: u64 = env.now().await + DAY;
+    let first = env.inject_pool(100, start_date, None).await;
+    let second = env.inject_pool(100, start_date, None).await;
+    let third = env.inject_pool(100, start_date, None).await;
+
+    // The first two pools share a creator
+    let creator: Pubkey = first.authority.pubkey();
+    let mut account: Pool = env.account(&second.address).await;
+    account.creator = creator;
+    env.set_program_account(&second.address, &account, 8 + Pool::INIT_SPACE);
+
+    for (id, pool, creator_index) in [(0, &first, 0), (1, &second, 1), (2, &third, 0)] {
+        let account: Pool = env.account(&pool.address).await;
+        env.process(
+            &[instructions::register_pool(
+                payer,
+                pool.address,
+                account.creator,
+                id,
+                creator_index,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+
+    let project: ProjectData = env.account(&pda::find_project_address().0).await;
+    assert_eq!(project.pools, 3);
+    for (id, pool) in [&first, &second, &third].into_iter().enumerate() {
+        let entry: PoolRegistryEntry = env
+            .account(&pda::find_pool_entry_address(id as u64).0)
+            .await;
+        assert_eq!(entry.pool, pool.address);
+        let account: Pool = env.account(&pool.address).await;
+        assert_eq!(account.id, Some(id as u64));
+    }
+
+    let creator_pools: CreatorPools = env
+        .account(&pda::find_creator_pools_address(&creator).0)
+        .await;
+    assert_eq!(creator_pools.pools, 2);
+    for (index, pool) in [&first, &second].into_iter().enumerate() {
+        let entry: PoolRegistryEntry = env
+            .account(&pda::find_creator_pool_entry_address(&creator, index as u64).0)
+            .await;
+        assert_eq!(entry.pool, pool.address);
+        assert_eq!(entry.creator_index, index as u64);
+    }
+}
+
+#[tokio::test]
+async fn create_pool_rejects_taken_registry_ids() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let payer: Pubkey = env.payer();
+    env.process(&[instructions::initialize_program(payer, payer)], &[])
+        .await
+        .unwrap();
+    let start_date: u64 = env.now().await + DAY;
+    let registered = env.inject_pool(100, start_date, None).await;
+    let account: Pool = env.account(&registered.address).await;
+    env.process(
+        &[instructions::register_pool(
+            payer,
+            registered.address,
+            account.creator,
+            0,
+            0,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    env.set_collection_mint();
+    let creator = env.create_user().await;
+    env.fund_usdc(&creator.pubkey(), 1_000 * USDC).await;
+    let reference: Pubkey = Pubkey::new_unique();
+    let terms = || CreatePool {
+        reference,
+        authority: creator.pubkey(),
+        shares: 1_000,
+        deposit: 100 * USDC,
+        name: "BlockRide Pool".to_string(),
+        symbol: "BRP".to_string(),
+        uri: "https://blockride.xyz/pool.json".to_string(),
+        start_date,
+        maturity_date: start_date + 365 * DAY,
+        apy: 12,
+        lockup: None,
+    };
+
+    // Pool id 0 and creator index 1 are not the next free entries
+    for (id, creator_index) in [(0, 0), (1, 1)] {
+        let result = env
+            .process(
+                &[instructions::create_pool(
+                    creator.pubkey(),
+                    terms(),
+                    id,
+                    creator_index,
+                )],
+                &[&creator],
+            )
+            .await;
+        assert_error(result, anchor_lang::error::ErrorCode::ConstraintSeeds);
+    }
+}
+
+#[tokio::test]
+async fn create_pool_mints_creator_shares() {
+    let mut env: TestEnv = TestEnv::start_with_metadata().await;
+    env.create_collection().await;
+    let creator = env.create_user().await;
+    env.fund_usdc(&creator.pubkey(), 1_000 * USDC).await;
+    let reference: Pubkey = Pubkey::new_unique();
+    let pool: Pubkey = pda::find_pool_address(&reference).0;
+    let start_date: u64 = env.now().await + DAY;
+
+    env.process(
+        &[instructions::create_pool(
+            creator.pubkey(),
+            CreatePool {
+                reference,
+                authority: creator.pubkey(),
+                shares: 1_000,
+                deposit: 100 * USDC,
+                name: "BlockRide Pool".to_string(),
+                symbol: "BRP".to_string(),
+                uri: "https://blockride.xyz/pool.json".to_string(),
+                start_date,
+                maturity_date: start_date + 365 * DAY,
+                apy: 12,
+                lockup: None,
+            },
+            0,
+            0,
+        )],
+        &[&creator],
+    )
+    .await
+    .unwrap();
+
+    let account: Pool = env.account(&pool).await;
+    assert_eq!(account.minted, 100);
+    assert_eq!(account.id, Some(0));
+    assert_eq!(account.status, PoolStatus::Draft);
+    let entry: PoolRegistryEntry = env
+        .account(&pda::find_creator_pool_entry_address(&creator.pubkey(), 0).0)
+        .await;
+    assert_eq!(entry.pool, pool);
+    assert_eq!(
+        env.token_balance(&pda::get_shares_address(&pool, &creator.pubkey()))
+            .await,
+        100
+    );
+    assert_eq!(
+        env.token_balance(&pda::get_usdc_address(&pool)).await,
+        100 * USDC
+    );
+
+    // The creator is verified and the mint is part of the BlockRide collection
+    let metadata: Metadata = env.metadata(&account.mint).await;
+    let creators = metadata.creators.unwrap();
+    assert_eq!(creators.len(), 1);
+    assert_eq!(creators[0].address, creator.pubkey());
+    assert!(creators[0].verified);
+    let collection = metadata.collection.unwrap();
+    assert_eq!(collection.key, pda::find_collection_mint_address().0);
+    assert!(collection.verified);
+
+    // The pool authority can rename the token without touching the rest
+    env.process(
+        &[instructions::update_pool_metadata(
+            creator.pubkey(),
+            pool,
+            "BlockRide Fleet 1".to_string(),
+            "BRF1".to_string(),
+            "https://blockride.xyz/fleet-1.json".to_string(),
+        )],
+        &[&creator],
+    )
+    .await
+    .unwrap();
+
+    let metadata: Metadata = env.metadata(&account.mint).await;
+    assert_eq!(metadata.name.trim_end_matches('\0'), "BlockRide Fleet 1");
+    assert_eq!(metadata.symbol.trim_end_matches('\0'), "BRF1");
+    assert_eq!(
+        metadata.uri.trim_end_matches('\0'),
+        "https://blockride.xyz/fleet-1.json"
+    );
+    assert!(metadata.creators.unwrap()[0].verified);
+    assert!(metadata.collection.unwrap().verified);
+}
+
+#[tokio::test]
+async fn collection_is_created_and_joined_by_older_pools() {
+    let mut env: TestEnv = TestEnv::start_with_metadata().await;
+    env.create_collection().await;
+    let project: Pubkey = pda::find_project_address().0;
+    let collection_mint: Pubkey = pda::find_collection_mint_address().0;
+
+    let collection: Metadata = env.metadata(&collection_mint).await;
+    assert_eq!(collection.name.trim_end_matches('\0'), "BlockRide Pools");
+    assert_eq!(collection.update_authority, project);
+    assert_eq!(
+        collection.collection_details,
+        Some(CollectionDetails::V1 { size: 0 })
+    );
+    assert_eq!(
+        env.token_balance(&anchor_spl::associated_token::get_associated_token_address(
+            &project,
+            &collection_mint
+        ))
+        .await,
+        1
+    );
+
+    // A pool whose token predates the collection joins it
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    env.set_legacy_metadata(&pool.mint, &pool.address);
+    env.process(
+        &[instructions::join_pool_collection(
+            pool.authority.pubkey(),
+            pool.address,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+
+    let metadata: Metadata = env.metadata(&pool.mint).await;
+    let joined = metadata.collection.unwrap();
+    assert_eq!(joined.key, collection_mint);
+    assert!(joined.verified);
+    let collection: Metadata = env.metadata(&collection_mint).await;
+    assert_eq!(
+        collection.collection_details,
+        Some(CollectionDetails::V1 { size: 1 })
+    );
+}
+
+#[tokio::test]
+async fn pool_lifecycle_from_purchase_to_closed_accounts() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let distribution_authority = env.create_user().await;
+    let investor = env.create_user().await;
+    let investor_usdc: Pubkey = env.fund_usdc(&investor.pubkey(), 1_000 * USDC).await;
+    let authority_usdc: Pubkey = env.fund_usdc(&pool.authority.pubkey(), 50 * USDC).await;
+
+    // Buy
+    env.process(
+        &[instructions::buy_shares(
+            investor.pubkey(),
+            None,
+            pool.address,
+            10,
+            false,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.account::<Pool>(&pool.address).await.minted, 10);
+    assert_eq!(
+        env.token_balance(&shares_address(&pool, &investor.pubkey()))
+            .await,
+        10
+    );
+    assert_eq!(env.token_balance(&investor_usdc).await, 990 * USDC);
+
+    // Register the vehicle the pool finances, which unlocks withdrawals
+    let payer: Pubkey = env.payer();
+    let creator: Pubkey = pool.authority.pubkey();
+    let vin_hash: [u8; 32] = pda::hash_vin("jtdbr32e720123456");
+    env.process(
+        &[
+            instructions::initialize_program(payer, payer),
+            instructions::add_pool_creator(payer, creator, true),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[
+            instructions::register_vehicle_asset(
+                creator,
+                VehicleAssetDetails {
+                    vin_hash,
+                    make: "Toyota".to_string(),
+                    model: "Corolla".to_string(),
+                    purchase_price: 10 * USDC,
+                    jurisdiction: "NG-LA".to_string(),
+                    custodian: creator,
+                    title_hash: [7; 32],
+                },
+            ),
+            instructions::link_vehicle_asset(creator, pool.address, &vin_hash),
+            instructions::update_vehicle_asset_status(
+                creator,
+                &vin_hash,
+                VehicleStatus::InMaintenance,
+            ),
+        ],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    let vehicle_asset: VehicleAsset = env
+        .account(&pda::find_vehicle_asset_address(&pda::hash_vin("JTDBR32E720123456")).0)
+        .await;
+    assert_eq!(vehicle_asset.pool, Some(pool.address));
+    assert_eq!(vehicle_asset.creator, creator);
+    assert_eq!(vehicle_asset.status, VehicleStatus::InMaintenance);
+    assert_eq!(vehicle_asset.title_hash, [7; 32]);
+    assert_eq!(env.account::<Pool>(&pool.address).await.assets, 1);
+
+    // Withdraw the principal to buy the vehicle; without a reserve all of it goes out
+    env.process(
+        &[instructions::withdraw_from_pool(
+            pool.authority.pubkey(),
+            pool.address,
+            authority_usdc,
+            10,
+            None,
+            false,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&authority_usdc).await, 60 * USDC);
+
+    // Close and distribute
+    env.process(
+        &[instructions::close_pool(
+            pool.authority.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+        )],
+        &[&pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.account::<Pool>(&pool.address).await.status,
+        PoolStatus::Settled
+    );
+
+    env.process(
+        &[instructions::distribute(
+            pool.authority.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+            0,
+            60 * USDC,
+            None,
+        )],
+        &[&pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+
+    // Claim
+    env.process(
+        &[instructions::claim_rewards(
+            investor.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+            0,
+            60 * USDC,
+        )],
+        &[&investor, &distribution_authority],
+    )
+    .await
+    .unwrap();
+    let distribution: Distribution = env
+        .account(&pda::find_distribution_address(&pool.address, 0).0)
+        .await;
+    assert_eq!(distribution.rewards, 60 * USDC);
+    assert_eq!(distribution.claimed, 60 * USDC);
+    assert_eq!(env.token_balance(&investor_usdc).await, 1_050 * USDC);
+    let position: InvestorPosition = env
+        .account(&pda::find_position_address(&pool.address, &investor.pubkey()).0)
+        .await;
+    assert_eq!(position.shares, 10);
+    assert_eq!(position.cost_basis, 10 * USDC);
+    assert_eq!(position.rewards_claimed, 60 * USDC);
+    assert!(position.opened_at > 0);
+
+    // Close the accounts
+    env.process(
+        &[instructions::close_pool_accounts(
+            pool.authority.pubkey(),
+            pool.address,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    assert!(env.raw_account(&pool.address).await.is_none());
+}
+
+#[tokio::test]
+async fn pool_pays_out_of_several_distributions() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let investor = env.create_user().await;
+    let interest_authority = env.create_user().await;
+    let recovery_authority = env.create_user().await;
+    let next_recovery_authority = env.create_user().await;
+    let investor_usdc: Pubkey = env.fund_usdc(&investor.pubkey(), 100 * USDC).await;
+    env.fund_usdc(&pool.authority.pubkey(), 30 * USDC).await;
+    env.process(
+        &[instructions::buy_shares(
+            investor.pubkey(),
+            None,
+            pool.address,
+            10,
+            false,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+
+    // Interest is paid out of the primary distribution and recoveries out of a second one
+    env.process(
+        &[
+            instructions::close_pool(
+                pool.authority.pubkey(),
+                interest_authority.pubkey(),
+                pool.address,
+            ),
+            instructions::create_distribution(
+                pool.authority.pubkey(),
+                recovery_authority.pubkey(),
+                pool.address,
+                1,
+            ),
+        ],
+        &[&pool.authority, &interest_authority, &recovery_authority],
+    )
+    .await
+    .unwrap();
+    let recovery: Pubkey = pda::find_distribution_address(&pool.address, 1).0;
+    let distribution: Distribution = env.account(&recovery).await;
+    assert_eq!(distribution.id, 1);
+    assert_eq!(distribution.pool, pool.address);
+    assert_eq!(distribution.authority, recovery_authority.pubkey());
+    let state: Pool = env.account(&pool.address).await;
+    assert_eq!(state.distributions, 1);
+    assert_eq!(state.open_distributions, 1);
+
+    env.process(
+        &[
+            instructions::distribute(
+                pool.authority.pubkey(),
+                interest_authority.pubkey(),
+                pool.address,
+                0,
+                20 * USDC,
+                None,
+            ),
+            instructions::distribute(
+                pool.authority.pubkey(),
+                recovery_authority.pubkey(),
+                pool.address,
+                1,
+                10 * USDC,
+                None,
+            ),
+        ],
+        &[&pool.authority, &interest_authority, &recovery_authority],
+    )
+    .await
+    .unwrap();
+
+    // The pool authority rotates the recovery authority, which then co-signs claims
+    env.process(
+        &[instructions::set_distribution_authority(
+            pool.authority.pubkey(),
+            next_recovery_authority.pubkey(),
+            pool.address,
+            1,
+        )],
+        &[&pool.authority, &next_recovery_authority],
+    )
+    .await
+    .unwrap();
+    let distribution: Distribution = env.account(&recovery).await;
+    assert_eq!(distribution.authority, next_recovery_authority.pubkey());
+
+    env.process(
+        &[
+            instructions::claim_rewards(
+                investor.pubkey(),
+                interest_authority.pubkey(),
+                pool.address,
+                0,
+                20 * USDC,
+            ),
+            instructions::claim_rewards(
+                investor.pubkey(),
+                next_recovery_authority.pubkey(),
+                pool.address,
+                1,
+                10 * USDC,
+            ),
+        ],
+        &[&investor, &interest_authority, &next_recovery_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&investor_usdc).await, 120 * USDC);
+    let primary: Distribution = env
+        .account(&pda::find_distribution_address(&pool.address, 0).0)
+        .await;
+    assert_eq!(primary.claimed, 20 * USDC);
+    let distribution: Distribution = env.account(&recovery).await;
+    assert_eq!(distribution.claimed, 10 * USDC);
+    let position: InvestorPosition = env
+        .account(&pda::find_position_address(&pool.address, &investor.pubkey()).0)
+        .await;
+    assert_eq!(position.rewards_claimed, 30 * USDC);
+
+    // Fully claimed distributions other than the primary one close on their own
+    env.process(
+        &[instructions::close_distribution(
+            pool.authority.pubkey(),
+            pool.address,
+            1,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    assert!(env.raw_account(&recovery).await.is_none());
+    assert!(env
+        .raw_account(&pda::get_usdc_address(&recovery))
+        .await
+        .is_none());
+    assert_eq!(
+        env.account::<Pool>(&pool.address).await.open_distributions,
+        0
+    );
+}
+
+#[tokio::test]
+async fn claimable_rewards_count_escrowed_shares() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let lockup: LockupConfig = LockupConfig {
+        cliff: 0,
+        duration: 10 * DAY,
+    };
+    let pool = env.inject_pool(100, start_date, Some(lockup)).await;
+    let investor = env.create_user().await;
+    let other = env.create_user().await;
+    let distribution_authority = env.create_user().await;
+    env.fund_usdc(&investor.pubkey(), 30 * USDC).await;
+    env.fund_usdc(&other.pubkey(), 10 * USDC).await;
+    env.fund_usdc(&pool.authority.pubkey(), 40 * USDC).await;
+    env.process(
+        &[
+            instructions::buy_shares(investor.pubkey(), None, pool.address, 30, true),
+            instructions::buy_shares(other.pubkey(), None, pool.address, 10, true),
+        ],
+        &[&investor, &other],
+    )
+    .await
+    .unwrap();
+
+    // Half of the investor's shares vest; 5 of those are listed and 15 stay locked
+    env.warp_to(start_date + 5 * DAY).await;
+    env.process(
+        &[
+            instructions::release_shares(investor.pubkey(), pool.address),
+            instructions::list_shares(investor.pubkey(), pool.address, 5, USDC),
+        ],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.token_balance(&shares_address(&pool, &investor.pubkey()))
+            .await,
+        10
+    );
+
+    env.process(
+        &[
+            instructions::close_pool(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+            ),
+            instructions::distribute(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                40 * USDC,
+                None,
+            ),
+            instructions::claim_rewards(
+                investor.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                12 * USDC,
+            ),
+        ],
+        &[&pool.authority, &distribution_authority, &investor],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.account::<Pool>(&pool.address).await.rewards, 40 * USDC);
+
+    // 30 of the 40 shares are the investor's, less what they already claimed
+    let claimable: u64 = env
+        .simulate(instructions::get_claimable_rewards(
+            pool.address,
+            0,
+            investor.pubkey(),
+        ))
+        .await;
+    assert_eq!(claimable, 18 * USDC);
+    let principal: u64 = env
+        .simulate(instructions::get_redeemable_principal(
+            pool.address,
+            investor.pubkey(),
+        ))
+        .await;
+    assert_eq!(principal, 30 * USDC);
+
+    let claimable: u64 = env
+        .simulate(instructions::get_claimable_rewards(
+            pool.address,
+            0,
+            other.pubkey(),
+        ))
+        .await;
+    assert_eq!(claimable, 10 * USDC);
+}
+
+#[tokio::test]
+async fn rewards_accrue_to_the_shares_held_when_paid() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let early = env.create_user().await;
+    let late = env.create_user().await;
+    let distribution_authority = env.create_user().await;
+    env.fund_usdc(&early.pubkey(), 10 * USDC).await;
+    env.fund_usdc(&late.pubkey(), 10 * USDC).await;
+    env.fund_usdc(&pool.authority.pubkey(), 20 * USDC).await;
+    let distribute = || {
+        instructions::distribute(
+            pool.authority.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+            0,
+            10 * USDC,
+            None,
+        )
+    };
+    let claimable = |holder: Pubkey| instructions::get_claimable_rewards(pool.address, 0, holder);
+
+    env.process(
+        &[
+            instructions::buy_shares(early.pubkey(), None, pool.address, 10, false),
+            instructions::activate_pool(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+            ),
+            distribute(),
+        ],
+        &[&early, &pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+
+    // A later buyer takes no part in what was paid before they bought
+    env.process(
+        &[instructions::buy_shares(
+            late.pubkey(),
+            None,
+            pool.address,
+            10,
+            false,
+        )],
+        &[&late],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.simulate::<u64>(claimable(early.pubkey())).await,
+        10 * USDC
+    );
+    assert_eq!(env.simulate::<u64>(claimable(late.pubkey())).await, 0);
+    let result = env
+        .process(
+            &[instructions::claim_rewards(
+                late.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                USDC,
+            )],
+            &[&late, &distribution_authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::ExceedsClaimableRewards);
+
+    // Both share what is paid once they both hold shares
+    env.process(&[distribute()], &[&pool.authority, &distribution_authority])
+        .await
+        .unwrap();
+    assert_eq!(
+        env.simulate::<u64>(claimable(early.pubkey())).await,
+        15 * USDC
+    );
+    assert_eq!(
+        env.simulate::<u64>(claimable(late.pubkey())).await,
+        5 * USDC
+    );
+    env.process(
+        &[
+            instructions::claim_rewards(
+                early.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                15 * USDC,
+            ),
+            instructions::claim_rewards(
+                late.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                5 * USDC,
+            ),
+        ],
+        &[&early, &late, &distribution_authority],
+    )
+    .await
+    .unwrap();
+    let distribution: Distribution = env
+        .account(&pda::find_distribution_address(&pool.address, 0).0)
+        .await;
+    assert_eq!(distribution.claimed, distribution.rewards);
+}
+
+#[tokio::test]
+async fn claimed_rewards_are_not_claimed_again_after_a_transfer() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let holder = env.create_user().await;
+    let other = env.create_user().await;
+    let recipient = env.create_user().await;
+    let distribution_authority = env.create_user().await;
+    env.fund_usdc(&holder.pubkey(), 10 * USDC).await;
+    env.fund_usdc(&other.pubkey(), 10 * USDC).await;
+    env.fund_usdc(&recipient.pubkey(), 0).await;
+    env.fund_usdc(&pool.authority.pubkey(), 20 * USDC).await;
+    let claim = |claimant: &Keypair, rewards: u64| {
+        instructions::claim_rewards(
+            claimant.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+            0,
+            rewards,
+        )
+    };
+    env.process(
+        &[
+            instructions::buy_shares(holder.pubkey(), None, pool.address, 10, false),
+            instructions::buy_shares(other.pubkey(), None, pool.address, 10, false),
+            instructions::activate_pool(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+            ),
+            instructions::distribute(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                20 * USDC,
+                None,
+            ),
+        ],
+        &[&holder, &other, &pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+
+    // The holder claims, then moves the claimed shares on
+    env.process(
+        &[claim(&holder, 10 * USDC)],
+        &[&holder, &distribution_authority],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instructions::transfer_shares(
+            holder.pubkey(),
+            recipient.pubkey(),
+            None,
+            pool.address,
+            10,
+        )],
+        &[&holder],
+    )
+    .await
+    .unwrap();
+
+    // Neither side of the transfer can claim the same rewards again
+    for claimant in [&recipient, &holder] {
+        let result = env
+            .process(
+                &[claim(claimant, USDC)],
+                &[claimant, &distribution_authority],
+            )
+            .await;
+        assert_error(result, ErrorCode::ExceedsClaimableRewards);
+    }
+    env.process(
+        &[claim(&other, 10 * USDC)],
+        &[&other, &distribution_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.token_balance(&pda::get_usdc_address(&recipient.pubkey()))
+            .await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn active_pool_distributes_during_its_term() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let investor = env.create_user().await;
+    let distribution_authority = env.create_user().await;
+    let investor_usdc: Pubkey = env.fund_usdc(&investor.pubkey(), 100 * USDC).await;
+    env.fund_usdc(&pool.authority.pubkey(), 30 * USDC).await;
+    let distribution: Pubkey = pda::find_distribution_address(&pool.address, 0).0;
+    env.process(
+        &[
+            instructions::buy_shares(investor.pubkey(), None, pool.address, 10, false),
+            instructions::activate_pool(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+            ),
+        ],
+        &[&investor, &pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.account::<Pool>(&pool.address).await.status,
+        PoolStatus::Active
+    );
+
+    // Monthly yield is paid and claimed while shares keep selling
+    env.process(
+        &[
+            instructions::distribute(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                10 * USDC,
+                None,
+            ),
+            instructions::claim_rewards(
+                investor.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                10 * USDC,
+            ),
+            instructions::buy_shares(investor.pubkey(), None, pool.address, 10, false),
+        ],
+        &[&investor, &pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&investor_usdc).await, 90 * USDC);
+
+    // Settlement keeps the distribution and what it has paid so far
+    env.process(
+        &[
+            instructions::close_pool(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+            ),
+            instructions::distribute(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                20 * USDC,
+                None,
+            ),
+        ],
+        &[&pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+    let state: Distribution = env.account(&distribution).await;
+    assert_eq!(state.authority, distribution_authority.pubkey());
+    assert_eq!(state.rewards, 30 * USDC);
+    assert_eq!(state.claimed, 10 * USDC);
+    assert_eq!(
+        env.account::<Pool>(&pool.address).await.status,
+        PoolStatus::Settled
+    );
+}
+
+#[tokio::test]
+async fn pool_moves_through_its_lifecycle() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let investor = env.create_user().await;
+    let distribution_authority = env.create_user().await;
+    env.fund_usdc(&investor.pubkey(), 100 * USDC).await;
+    let buy = || instructions::buy_shares(investor.pubkey(), None, pool.address, 10, false);
+
+    // Pools are created in draft, which doesn't sell shares
+    let mut state: Pool = env.account(&pool.address).await;
+    state.status = PoolStatus::Draft;
+    env.set_program_account(&pool.address, &state, 8 + Pool::INIT_SPACE);
+    let result = env.process(&[buy()], &[&investor]).await;
+    assert_error(result, ErrorCode::InvalidPoolState);
+
+    env.process(
+        &[
+            instructions::transition_pool(
+                pool.authority.pubkey(),
+                pool.address,
+                PoolStatus::Fundraising,
+            ),
+            buy(),
+            instructions::transition_pool(
+                pool.authority.pubkey(),
+                pool.address,
+                PoolStatus::Funded,
+            ),
+        ],
+        &[&pool.authority, &investor],
+    )
+    .await
+    .unwrap();
+    let result = env.process(&[buy()], &[&investor]).await;
+    assert_error(result, ErrorCode::InvalidPoolState);
+
+    // Activation and settlement only go through their own instructions
+    for status in [PoolStatus::Active, PoolStatus::Settled] {
+        let result = env
+            .process(
+                &[instructions::transition_pool(
+                    pool.authority.pubkey(),
+                    pool.address,
+                    status,
+                )],
+                &[&pool.authority],
+            )
+            .await;
+        assert_error(result, ErrorCode::InvalidPoolState);
+    }
+    let mature = || {
+        instructions::transition_pool(pool.authority.pubkey(), pool.address, PoolStatus::Matured)
+    };
+    env.process(
+        &[instructions::activate_pool(
+            pool.authority.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+        )],
+        &[&pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+
+    // Pools mature on their maturity date
+    let result = env.process(&[mature()], &[&pool.authority]).await;
+    assert_error(result, ErrorCode::MaturityDateNotPassed);
+    env.warp_to(start_date + 365 * DAY).await;
+    env.process(
+        &[
+            mature(),
+            instructions::close_pool(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+            ),
+        ],
+        &[&pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.account::<Pool>(&pool.address).await.status,
+        PoolStatus::Settled
+    );
+
+    // Settled pools stay settled
+    let result = env
+        .process(
+            &[instructions::activate_pool(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+            )],
+            &[&pool.authority, &distribution_authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidPoolState);
+}
+
+#[tokio::test]
+async fn failed_raise_refunds_its_holders() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let first = env.create_user().await;
+    let second = env.create_user().await;
+    env.fund_usdc(&first.pubkey(), 100 * USDC).await;
+    env.fund_usdc(&second.pubkey(), 100 * USDC).await;
+    let refunding = || {
+        instructions::transition_pool(pool.authority.pubkey(), pool.address, PoolStatus::Refunding)
+    };
+    let cancel = || {
+        instructions::transition_pool(pool.authority.pubkey(), pool.address, PoolStatus::Cancelled)
+    };
+    env.process(
+        &[
+            instructions::buy_shares(first.pubkey(), None, pool.address, 30, false),
+            instructions::buy_shares(second.pubkey(), None, pool.address, 10, false),
+        ],
+        &[&first, &second],
+    )
+    .await
+    .unwrap();
+
+    // Nothing is refunded until the raise has failed, which is once the pool should start
+    let result = env
+        .process(
+            &[instructions::refund_shares(
+                first.pubkey(),
+                pool.address,
+                30,
+            )],
+            &[&first],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidPoolState);
+    let result = env.process(&[refunding()], &[&pool.authority]).await;
+    assert_error(result, ErrorCode::StartDateNotPassed);
+    env.warp_to(start_date).await;
+    env.process(&[refunding()], &[&pool.authority])
+        .await
+        .unwrap();
+
+    // Holders are paid back pro-rata, and the pool is cancelled once they all are
+    env.process(
+        &[instructions::refund_shares(
+            first.pubkey(),
+            pool.address,
+            30,
+        )],
+        &[&first],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.token_balance(&pda::get_usdc_address(&first.pubkey()))
+            .await,
+        100 * USDC
+    );
+    let result = env.process(&[cancel()], &[&pool.authority]).await;
+    assert_error(result, ErrorCode::SharesNotRefunded);
+    env.process(
+        &[
+            instructions::refund_shares(second.pubkey(), pool.address, 10),
+            cancel(),
+        ],
+        &[&second, &pool.authority],
+    )
+    .await
+    .unwrap();
+    let state: Pool = env.account(&pool.address).await;
+    assert_eq!(state.minted, 0);
+    assert_eq!(state.status, PoolStatus::Cancelled);
+    assert_eq!(
+        env.token_balance(&pda::get_usdc_address(&pool.address))
+            .await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn failed_raise_refunds_locked_and_listed_shares() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let lockup = LockupConfig {
+        cliff: 0,
+        duration: 10 * DAY,
+    };
+    let pool = env.inject_pool(100, start_date, Some(lockup)).await;
+    let holder = env.create_user().await;
+    env.fund_usdc(&holder.pubkey(), 30 * USDC).await;
+    env.process(
+        &[instructions::buy_shares(
+            holder.pubkey(),
+            None,
+            pool.address,
+            30,
+            true,
+        )],
+        &[&holder],
+    )
+    .await
+    .unwrap();
+
+    // A tenth vests over the first day and goes up for sale
+    env.warp_to(start_date + DAY).await;
+    env.process(
+        &[
+            instructions::release_shares(holder.pubkey(), pool.address),
+            instructions::list_shares(holder.pubkey(), pool.address, 3, 2 * USDC),
+            instructions::transition_pool(
+                pool.authority.pubkey(),
+                pool.address,
+                PoolStatus::Refunding,
+            ),
+        ],
+        &[&holder, &pool.authority],
+    )
+    .await
+    .unwrap();
+
+    // The lockup lets go of what is still vesting and the listing hands its shares
+    // back, so all of them are refunded
+    env.process(
+        &[
+            instructions::release_shares(holder.pubkey(), pool.address),
+            instructions::cancel_listing(holder.pubkey(), pool.address),
+            instructions::refund_shares(holder.pubkey(), pool.address, 30),
+            instructions::transition_pool(
+                pool.authority.pubkey(),
+                pool.address,
+                PoolStatus::Cancelled,
+            ),
+        ],
+        &[&holder, &pool.authority],
+    )
+    .await
+    .unwrap();
+    let lockup: ShareLockup = env
+        .account(&pda::find_lockup_address(&pool.address, &holder.pubkey()).0)
+        .await;
+    assert_eq!(lockup.released, 30);
+    assert_eq!(
+        env.token_balance(&pda::get_usdc_address(&holder.pubkey()))
+            .await,
+        30 * USDC
+    );
+    assert_eq!(
+        env.account::<Pool>(&pool.address).await.status,
+        PoolStatus::Cancelled
+    );
+}
+
+#[tokio::test]
+async fn abandoned_draft_refunds_its_seed() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    env.fund_usdc(&pool.authority.pubkey(), 10 * USDC).await;
+    env.process(
+        &[instructions::buy_shares(
+            pool.authority.pubkey(),
+            None,
+            pool.address,
+            10,
+            false,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    // Stand the bought shares in for the creator's seed
+    let mut state: Pool = env.account(&pool.address).await;
+    state.status = PoolStatus::Draft;
+    state.creator_shares = state.minted;
+    env.set_program_account(&pool.address, &state, 8 + Pool::INIT_SPACE);
+    let cancel = || {
+        instructions::transition_pool(pool.authority.pubkey(), pool.address, PoolStatus::Cancelled)
+    };
+
+    // A seeded draft is not cancelled over its seed, which is refunded before the start date
+    let result = env.process(&[cancel()], &[&pool.authority]).await;
+    assert_error(result, ErrorCode::SharesNotRefunded);
+    env.process(
+        &[
+            instructions::transition_pool(
+                pool.authority.pubkey(),
+                pool.address,
+                PoolStatus::Refunding,
+            ),
+            instructions::refund_shares(pool.authority.pubkey(), pool.address, 10),
+            cancel(),
+        ],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.token_balance(&pda::get_usdc_address(&pool.authority.pubkey()))
+            .await,
+        10 * USDC
+    );
+    assert_eq!(
+        env.account::<Pool>(&pool.address).await.status,
+        PoolStatus::Cancelled
+    );
+}
+
+#[tokio::test]
+async fn created_pool_takes_share_classes_until_shares_are_sold() {
+    let mut env: TestEnv = TestEnv::start_with_metadata().await;
+    env.create_collection().await;
+    let creator = env.create_user().await;
+    env.fund_usdc(&creator.pubkey(), 1_000 * USDC).await;
+    let investor = env.create_user().await;
+    env.fund_usdc(&investor.pubkey(), 100 * USDC).await;
+    let reference: Pubkey = Pubkey::new_unique();
+    let pool: Pubkey = pda::find_pool_address(&reference).0;
+    let start_date: u64 = env.now().await + DAY;
+    env.process(
+        &[instructions::create_pool(
+            creator.pubkey(),
+            CreatePool {
+                reference,
+                authority: creator.pubkey(),
+                shares: 1_000,
+                deposit: 100 * USDC,
+                name: "BlockRide Pool".to_string(),
+                symbol: "BRP".to_string(),
+                uri: "https://blockride.xyz/pool.json".to_string(),
+                start_date,
+                maturity_date: start_date + 365 * DAY,
+                apy: 12,
+                lockup: None,
+            },
+            0,
+            0,
+        )],
+        &[&creator],
+    )
+    .await
+    .unwrap();
+    let create_share_class = |class_id: u8, shares: u64| {
+        instructions::create_share_class(
+            creator.pubkey(),
+            pool,
+            class_id,
+            shares,
+            10,
+            BuyerEligibility::Open,
+        )
+    };
+
+    // The creator's seed leaves the rest of the pool's shares to its classes
+    let result = env
+        .process(&[create_share_class(0, 1_000)], &[&creator])
+        .await;
+    assert_error(result, ErrorCode::ExceedsAvailableShares);
+    env.process(&[create_share_class(0, 600)], &[&creator])
+        .await
+        .unwrap();
+
+    // Classes are locked once class shares are sold
+    env.process(
+        &[
+            instructions::transition_pool(creator.pubkey(), pool, PoolStatus::Fundraising),
+            instructions::buy_class_shares(investor.pubkey(), None, pool, 0, 10),
+        ],
+        &[&creator, &investor],
+    )
+    .await
+    .unwrap();
+    let result = env
+        .process(&[create_share_class(1, 300)], &[&creator])
+        .await;
+    assert_error(result, ErrorCode::ShareClassesLocked);
+}
+
+#[tokio::test]
+async fn failed_class_raise_refunds_its_class_holders() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let investor = env.create_user().await;
+    env.fund_usdc(&investor.pubkey(), 100 * USDC).await;
+    env.process(
+        &[instructions::create_share_class(
+            pool.authority.pubkey(),
+            pool.address,
+            0,
+            100,
+            10,
+            BuyerEligibility::Open,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instructions::buy_class_shares(
+            investor.pubkey(),
+            None,
+            pool.address,
+            0,
+            40,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+    env.warp_to(start_date).await;
+
+    // Class shares are refunded like pool shares, and cancelling the pool waits for them
+    env.process(
+        &[instructions::transition_pool(
+            pool.authority.pubkey(),
+            pool.address,
+            PoolStatus::Refunding,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[
+            instructions::refund_class_shares(investor.pubkey(), pool.address, 0, 40),
+            instructions::transition_pool(
+                pool.authority.pubkey(),
+                pool.address,
+                PoolStatus::Cancelled,
+            ),
+        ],
+        &[&investor, &pool.authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.token_balance(&pda::get_usdc_address(&investor.pubkey()))
+            .await,
+        100 * USDC
+    );
+    let share_class: ShareClass = env
+        .account(&pda::find_share_class_address(&pool.address, 0).0)
+        .await;
+    assert_eq!(share_class.minted, 0);
+    let class_position: ClassPosition = env
+        .account(&pda::find_class_position_address(&pool.address, 0, &investor.pubkey()).0)
+        .await;
+    assert_eq!(class_position.shares, 0);
+    let state: Pool = env.account(&pool.address).await;
+    assert_eq!(state.minted, 0);
+    assert_eq!(state.status, PoolStatus::Cancelled);
+}
+
+#[tokio::test]
+async fn locked_shares_release_on_schedule() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let lockup: LockupConfig = LockupConfig {
+        cliff: 10 * DAY,
+        duration: 10 * DAY,
+    };
+    let pool = env.inject_pool(100, start_date, Some(lockup)).await;
+    let investor = env.create_user().await;
+    env.fund_usdc(&investor.pubkey(), 1_000 * USDC).await;
+    let investor_shares: Pubkey = shares_address(&pool, &investor.pubkey());
+
+    env.process(
+        &[instructions::buy_shares(
+            investor.pubkey(),
+            None,
+            pool.address,
+            10,
+            true,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&investor_shares).await, 0);
+    let escrow: Pubkey = pda::find_escrow_address(&pool.address).0;
+    assert_eq!(env.token_balance(&escrow).await, 10);
+
+    // Half way through the vesting period
+    env.warp_to(start_date + 15 * DAY).await;
+    env.process(
+        &[instructions::release_shares(
+            investor.pubkey(),
+            pool.address,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&investor_shares).await, 5);
+
+    // Fully vested
+    env.warp_to(start_date + 20 * DAY).await;
+    env.process(
+        &[instructions::release_shares(
+            investor.pubkey(),
+            pool.address,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&investor_shares).await, 10);
+
+    let lockup: ShareLockup = env
+        .account(&pda::find_lockup_address(&pool.address, &investor.pubkey()).0)
+        .await;
+    assert_eq!(lockup.released, 10);
+}
+
+#[tokio::test]
+async fn late_purchases_vest_from_their_own_lock() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let lockup: LockupConfig = LockupConfig {
+        cliff: 10 * DAY,
+        duration: 10 * DAY,
+    };
+    let pool = env.inject_pool(100, start_date, Some(lockup)).await;
+    let investor = env.create_user().await;
+    env.fund_usdc(&investor.pubkey(), 1_000 * USDC).await;
+    let investor_shares: Pubkey = shares_address(&pool, &investor.pubkey());
+    let buy = || instructions::buy_shares(investor.pubkey(), None, pool.address, 10, true);
+    let release = || instructions::release_shares(investor.pubkey(), pool.address);
+
+    env.process(&[buy()], &[&investor]).await.unwrap();
+
+    // The first purchase has vested by the time the second one is locked
+    env.warp_to(start_date + 20 * DAY).await;
+    env.process(&[buy()], &[&investor]).await.unwrap();
+    env.process(&[release()], &[&investor]).await.unwrap();
+    assert_eq!(env.token_balance(&investor_shares).await, 10);
+
+    // Half way through the second purchase's vesting period
+    env.warp_to(start_date + 35 * DAY).await;
+    env.process(&[release()], &[&investor]).await.unwrap();
+    assert_eq!(env.token_balance(&investor_shares).await, 15);
+
+    env.warp_to(start_date + 40 * DAY).await;
+    env.process(&[release()], &[&investor]).await.unwrap();
+    assert_eq!(env.token_balance(&investor_shares).await, 20);
+
+    let lockup: ShareLockup = env
+        .account(&pda::find_lockup_address(&pool.address, &investor.pubkey()).0)
+        .await;
+    assert_eq!(lockup.vested, 10);
+    assert_eq!(lockup.lots.len(), 1);
+}
+
+#[tokio::test]
+async fn listed_shares_are_sold_and_returned() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let seller = env.create_user().await;
+    let buyer = env.create_user().await;
+    let seller_usdc: Pubkey = env.fund_usdc(&seller.pubkey(), 100 * USDC).await;
+    let buyer_usdc: Pubkey = env.fund_usdc(&buyer.pubkey(), 100 * USDC).await;
+
+    env.process(
+        &[
+            instructions::buy_shares(seller.pubkey(), None, pool.address, 10, false),
+            instructions::list_shares(seller.pubkey(), pool.address, 10, 2 * USDC),
+        ],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instructions::fill_listing(
+            buyer.pubkey(),
+            None,
+            pool.address,
+            seller.pubkey(),
+            4,
+        )],
+        &[&buyer],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        env.token_balance(&shares_address(&pool, &buyer.pubkey()))
+            .await,
+        4
+    );
+    assert_eq!(env.token_balance(&buyer_usdc).await, 92 * USDC);
+    assert_eq!(env.token_balance(&seller_usdc).await, 98 * USDC);
+    let listing: Pubkey = pda::find_listing_address(&pool.address, &seller.pubkey()).0;
+    assert_eq!(env.account::<Listing>(&listing).await.shares, 6);
+
+    // The seller realizes 4 shares bought at par for 8 USDC, the buyer holds them at 8 USDC
+    let seller_position: Pubkey = pda::find_position_address(&pool.address, &seller.pubkey()).0;
+    let buyer_position: Pubkey = pda::find_position_address(&pool.address, &buyer.pubkey()).0;
+    let position: InvestorPosition = env.account(&seller_position).await;
+    assert_eq!(position.shares, 6);
+    assert_eq!(position.cost_basis, 6 * USDC);
+    assert_eq!(position.realized_cost_basis, 4 * USDC);
+    assert_eq!(position.principal_returned, 8 * USDC);
+    let position: InvestorPosition = env.account(&buyer_position).await;
+    assert_eq!(position.shares, 4);
+    assert_eq!(position.cost_basis, 8 * USDC);
+
+    env.process(
+        &[instructions::cancel_listing(seller.pubkey(), pool.address)],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.token_balance(&shares_address(&pool, &seller.pubkey()))
+            .await,
+        6
+    );
+    assert!(env.raw_account(&listing).await.is_none());
+
+    // A transfer carries the cost basis over without realizing anything
+    env.process(
+        &[instructions::transfer_shares(
+            seller.pubkey(),
+            buyer.pubkey(),
+            None,
+            pool.address,
+            3,
+        )],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+    let position: InvestorPosition = env.account(&seller_position).await;
+    assert_eq!(position.shares, 3);
+    assert_eq!(position.cost_basis, 3 * USDC);
+    assert_eq!(position.realized_cost_basis, 4 * USDC);
+    let position: InvestorPosition = env.account(&buyer_position).await;
+    assert_eq!(position.shares, 7);
+    assert_eq!(position.cost_basis, 11 * USDC);
+    assert_eq!(
+        env.token_balance(&shares_address(&pool, &buyer.pubkey()))
+            .await,
+        7
+    );
+}
+
+#[tokio::test]
+async fn approved_pools_gate_secondary_buyers() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let seller = env.create_user().await;
+    let buyer = env.create_user().await;
+    env.fund_usdc(&seller.pubkey(), 100 * USDC).await;
+    env.fund_usdc(&buyer.pubkey(), 100 * USDC).await;
+    env.process(
+        &[
+            instructions::buy_shares(seller.pubkey(), None, pool.address, 10, false),
+            instructions::list_shares(seller.pubkey(), pool.address, 5, USDC),
+        ],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+    let mut state: Pool = env.account(&pool.address).await;
+    state.eligibility = BuyerEligibility::Approved;
+    env.set_program_account(&pool.address, &state, 8 + Pool::INIT_SPACE);
+    let fill = |approver: Option<Pubkey>| {
+        instructions::fill_listing(buyer.pubkey(), approver, pool.address, seller.pubkey(), 2)
+    };
+    let transfer = |approver: Option<Pubkey>| {
+        instructions::transfer_shares(seller.pubkey(), buyer.pubkey(), approver, pool.address, 3)
+    };
+
+    // Neither a fill nor a transfer reaches an unapproved wallet
+    let result = env.process(&[fill(None)], &[&buyer]).await;
+    assert_error(result, ErrorCode::BuyerNotEligible);
+    let result = env.process(&[transfer(None)], &[&seller]).await;
+    assert_error(result, ErrorCode::BuyerNotEligible);
+
+    let approver: Option<Pubkey> = Some(pool.authority.pubkey());
+    env.process(
+        &[fill(approver), transfer(approver)],
+        &[&buyer, &seller, &pool.authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.token_balance(&shares_address(&pool, &buyer.pubkey()))
+            .await,
+        5
+    );
+}
+
+#[tokio::test]
+async fn oversubscribed_redemption_fills_pro_rata() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let first = env.create_user().await;
+    let second = env.create_user().await;
+    let first_usdc: Pubkey = env.fund_usdc(&first.pubkey(), 30 * USDC).await;
+    let second_usdc: Pubkey = env.fund_usdc(&second.pubkey(), 10 * USDC).await;
+
+    env.process(
+        &[instructions::buy_shares(
+            first.pubkey(),
+            None,
+            pool.address,
+            30,
+            false,
+        )],
+        &[&first],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instructions::buy_shares(
+            second.pubkey(),
+            None,
+            pool.address,
+            10,
+            false,
+        )],
+        &[&second],
+    )
+    .await
+    .unwrap();
+
+    // 20 USDC of liquidity against 40 USDC of tenders fills half of each
+    let close_date: u64 = env.now().await + 2 * DAY;
+    env.process(
+        &[instructions::open_redemption_window(
+            pool.authority.pubkey(),
+            pool.address,
+            close_date,
+            20 * USDC,
+            RedemptionSource::Pool,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instructions::tender_shares(
+            first.pubkey(),
+            pool.address,
+            30,
+        )],
+        &[&first],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instructions::tender_shares(
+            second.pubkey(),
+            pool.address,
+            10,
+        )],
+        &[&second],
+    )
+    .await
+    .unwrap();
+
+    env.warp_to(close_date).await;
+    for holder in [first.pubkey(), second.pubkey()] {
+        env.process(
+            &[instructions::settle_tender(
+                env.payer(),
+                pool.address,
+                holder,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+    env.process(
+        &[instructions::close_redemption_window(
+            pool.authority.pubkey(),
+            pool.address,
+            RedemptionSource::Pool,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(env.token_balance(&first_usdc).await, 15 * USDC);
+    assert_eq!(env.token_balance(&second_usdc).await, 5 * USDC);
+    assert_eq!(
+        env.token_balance(&shares_address(&pool, &first.pubkey()))
+            .await,
+        15
+    );
+    assert_eq!(
+        env.token_balance(&shares_address(&pool, &second.pubkey()))
+            .await,
+        5
+    );
+    assert_eq!(env.account::<Pool>(&pool.address).await.minted, 20);
+    assert_eq!(
+        env.token_balance(&pda::get_usdc_address(&pool.address))
+            .await,
+        20 * USDC
+    );
+    let position: InvestorPosition = env
+        .account(&pda::find_position_address(&pool.address, &first.pubkey()).0)
+        .await;
+    assert_eq!(position.shares, 15);
+    assert_eq!(position.cost_basis, 15 * USDC);
+    assert_eq!(position.realized_cost_basis, 15 * USDC);
+    assert_eq!(position.principal_returned, 15 * USDC);
+}
+
+#[tokio::test]
+async fn sold_shares_carry_their_claimed_rewards() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let seller = env.create_user().await;
+    let buyer = env.create_user().await;
+    let distribution_authority = env.create_user().await;
+    env.fund_usdc(&seller.pubkey(), 20 * USDC).await;
+    env.fund_usdc(&buyer.pubkey(), 20 * USDC).await;
+    env.fund_usdc(&pool.authority.pubkey(), 30 * USDC).await;
+    env.process(
+        &[
+            instructions::buy_shares(seller.pubkey(), None, pool.address, 20, false),
+            instructions::activate_pool(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+            ),
+            instructions::distribute(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                20 * USDC,
+                None,
+            ),
+            instructions::claim_rewards(
+                seller.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                10 * USDC,
+            ),
+        ],
+        &[&seller, &pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+
+    // Half of the shares change hands with half of the rewards still owed on them
+    env.process(
+        &[instructions::list_shares(
+            seller.pubkey(),
+            pool.address,
+            10,
+            USDC,
+        )],
+        &[&seller],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instructions::fill_listing(
+            buyer.pubkey(),
+            None,
+            pool.address,
+            seller.pubkey(),
+            10,
+        )],
+        &[&buyer],
+    )
+    .await
+    .unwrap();
+
+    let seller_position: InvestorPosition = env
+        .account(&pda::find_position_address(&pool.address, &seller.pubkey()).0)
+        .await;
+    assert_eq!(seller_position.rewards_claimed, 10 * USDC);
+    assert_eq!(seller_position.rewards_owed, 5 * USDC);
+    let buyer_position: InvestorPosition = env
+        .account(&pda::find_position_address(&pool.address, &buyer.pubkey()).0)
+        .await;
+    assert_eq!(buyer_position.rewards_claimed, 0);
+    assert_eq!(buyer_position.rewards_owed, 5 * USDC);
+
+    for holder in [&seller, &buyer] {
+        let claimable: u64 = env
+            .simulate(instructions::get_claimable_rewards(
+                pool.address,
+                0,
+                holder.pubkey(),
+            ))
+            .await;
+        assert_eq!(claimable, 5 * USDC);
+    }
+
+    // Later rewards accrue to the new holder
+    env.process(
+        &[instructions::distribute(
+            pool.authority.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+            0,
+            10 * USDC,
+            None,
+        )],
+        &[&pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+    let claimable: u64 = env
+        .simulate(instructions::get_claimable_rewards(
+            pool.address,
+            0,
+            buyer.pubkey(),
+        ))
+        .await;
+    assert_eq!(claimable, 10 * USDC);
+
+    // A transfer hands the owed rewards back along with the shares
+    env.process(
+        &[instructions::transfer_shares(
+            buyer.pubkey(),
+            seller.pubkey(),
+            None,
+            pool.address,
+            10,
+        )],
+        &[&buyer],
+    )
+    .await
+    .unwrap();
+    let seller_position: InvestorPosition = env
+        .account(&pda::find_position_address(&pool.address, &seller.pubkey()).0)
+        .await;
+    assert_eq!(seller_position.rewards_owed, 20 * USDC);
+    let claimable: u64 = env
+        .simulate(instructions::get_claimable_rewards(
+            pool.address,
+            0,
+            seller.pubkey(),
+        ))
+        .await;
+    assert_eq!(claimable, 20 * USDC);
+}
+
+#[tokio::test]
+async fn launched_pool_is_priced_at_nav() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let investor = env.create_user().await;
+    let investor_usdc: Pubkey = env.fund_usdc(&investor.pubkey(), 100 * USDC).await;
+
+    env.warp_to(start_date).await;
+    env.process(
+        &[instructions::update_nav(
+            pool.authority.pubkey(),
+            pool.address,
+            1_100_000,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+
+    let quote: BuyQuote = env
+        .simulate(instructions::quote_buy(pool.address, 10))
+        .await;
+    assert_eq!(quote.deposit, 11 * USDC);
+    assert_eq!(quote.available, 100);
+
+    env.process(
+        &[instructions::buy_shares(
+            investor.pubkey(),
+            None,
+            pool.address,
+            10,
+            false,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&investor_usdc).await, 89 * USDC);
+
+    let nav: NavQuote = env.simulate(instructions::get_nav(pool.address)).await;
+    assert_eq!(nav.nav_per_share, 1_100_000);
+    assert!(!nav.stale);
+
+    let summary: PoolSummary = env
+        .simulate(instructions::get_pool_summary(pool.address, false))
+        .await;
+    assert_eq!(summary.minted, 10);
+    assert_eq!(summary.available, 90);
+
+    let principal: u64 = env
+        .simulate(instructions::get_redeemable_principal(
+            pool.address,
+            investor.pubkey(),
+        ))
+        .await;
+    assert_eq!(principal, 11 * USDC);
+
+    // The authority withdraws principal as deposited, even once NAV has gone stale
+    let authority_usdc: Pubkey = env.fund_usdc(&pool.authority.pubkey(), 0).await;
+    env.inject_vehicle_asset(&pool).await;
+    env.warp_to(start_date + Pool::MAX_NAV_AGE + DAY).await;
+    env.process(
+        &[instructions::withdraw_from_pool(
+            pool.authority.pubkey(),
+            pool.address,
+            authority_usdc,
+            5,
+            None,
+            false,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&authority_usdc).await, 5 * USDC);
+}
+
+#[tokio::test]
+async fn principal_is_withdrawn_at_the_issue_price() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let mut state: Pool = env.account(&pool.address).await;
+    state.share_price = 2 * USDC;
+    state.nav_per_share = 2 * USDC;
+    state.nav_period_nav = 2 * USDC;
+    env.set_program_account(&pool.address, &state, 8 + Pool::INIT_SPACE);
+    let investor = env.create_user().await;
+    let investor_usdc: Pubkey = env.fund_usdc(&investor.pubkey(), 100 * USDC).await;
+    env.process(
+        &[instructions::buy_shares(
+            investor.pubkey(),
+            None,
+            pool.address,
+            10,
+            false,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&investor_usdc).await, 80 * USDC);
+
+    // A NAV above the 2 USDC issue price doesn't raise the principal withdrawn
+    env.warp_to(start_date).await;
+    env.process(
+        &[instructions::update_nav(
+            pool.authority.pubkey(),
+            pool.address,
+            2_200_000,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    let authority_usdc: Pubkey = env.fund_usdc(&pool.authority.pubkey(), 0).await;
+    env.inject_vehicle_asset(&pool).await;
+    env.process(
+        &[instructions::withdraw_from_pool(
+            pool.authority.pubkey(),
+            pool.address,
+            authority_usdc,
+            10,
+            None,
+            false,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&authority_usdc).await, 20 * USDC);
+}
+
+#[tokio::test]
+async fn reported_revenue_flows_down_the_waterfall() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let operator = env.create_user().await;
+    let operator_usdc: Pubkey = env.fund_usdc(&operator.pubkey(), 1_000 * USDC).await;
+    let payer: Pubkey = env.payer();
+    let treasury: Pubkey = env.fund_usdc(&payer, 0).await;
+
+    // 5% protocol fee, 30% to the driver, 10% to the reserve and the rest to investors
+    env.process(
+        &[
+            instructions::initialize_program(payer, payer),
+            instructions::set_protocol_fee(payer, 500),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instructions::configure_revenue(
+            pool.authority.pubkey(),
+            pool.address,
+            operator.pubkey(),
+            3_000,
+            1_000,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+
+    // Revenue can come in before the reserve is configured, which then counts its share
+    env.process(
+        &[instructions::report_revenue(
+            operator.pubkey(),
+            pool.address,
+            1_000 * USDC,
+            false,
+        )],
+        &[&operator],
+    )
+    .await
+    .unwrap();
+    env.configure_reserve(&pool, 0, 0).await;
+    assert_eq!(env.token_balance(&operator_usdc).await, 0);
+    for (bucket, expected) in [
+        (RevenueBucket::Driver, 300 * USDC),
+        (RevenueBucket::Protocol, 50 * USDC),
+        (RevenueBucket::Investors, 550 * USDC),
+    ] {
+        let address: Pubkey = pda::find_revenue_bucket_address(&pool.address, bucket).0;
+        assert_eq!(env.token_balance(&address).await, expected);
+    }
+    assert_eq!(
+        env.token_balance(&pda::find_reserve_vault_address(&pool.address).0)
+            .await,
+        100 * USDC
+    );
+    let reserve: PoolReserve = env
+        .account(&pda::find_reserve_address(&pool.address).0)
+        .await;
+    assert_eq!(reserve.funded, 100 * USDC);
+    let revenue_config: RevenueConfig = env
+        .account(&pda::find_revenue_config_address(&pool.address).0)
+        .await;
+    assert_eq!(revenue_config.reported, 1_000 * USDC);
+
+    // Each bucket pays out to its owner
+    env.process(
+        &[instructions::withdraw_revenue(
+            operator.pubkey(),
+            pool.address,
+            RevenueBucket::Driver,
+            operator_usdc,
+            300 * USDC,
+        )],
+        &[&operator],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instructions::withdraw_revenue(
+            payer,
+            pool.address,
+            RevenueBucket::Protocol,
+            treasury,
+            50 * USDC,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&operator_usdc).await, 300 * USDC);
+    assert_eq!(env.token_balance(&treasury).await, 50 * USDC);
+
+    // The investor share lands in the distribution once the pool closes
+    let distribution_authority = env.create_user().await;
+    env.process(
+        &[instructions::close_pool(
+            pool.authority.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+        )],
+        &[&pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+    let result = env
+        .process(
+            &[instructions::distribute_revenue(
+                distribution_authority.pubkey(),
+                pool.address,
+                None,
+            )],
+            &[&distribution_authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::SignerNotAuthorized);
+    env.process(
+        &[instructions::distribute_revenue(
+            operator.pubkey(),
+            pool.address,
+            None,
+        )],
+        &[&operator],
+    )
+    .await
+    .unwrap();
+    let distribution: Distribution = env
+        .account(&pda::find_distribution_address(&pool.address, 0).0)
+        .await;
+    assert_eq!(distribution.rewards, 550 * USDC);
+}
+
+#[tokio::test]
+async fn reserve_is_funded_from_principal_and_spent_on_receipts() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    env.inject_vehicle_asset(&pool).await;
+    let investor = env.create_user().await;
+    env.fund_usdc(&investor.pubkey(), 1_000 * USDC).await;
+    let authority_usdc: Pubkey = env.fund_usdc(&pool.authority.pubkey(), 0).await;
+    let garage_usdc: Pubkey = env.fund_usdc(&Pubkey::new_unique(), 0).await;
+    let reserve: Pubkey = pda::find_reserve_address(&pool.address).0;
+    let vault: Pubkey = pda::find_reserve_vault_address(&pool.address).0;
+
+    // 20% of withdrawn principal goes to the reserve
+    env.configure_reserve(&pool, 2_000, 15 * USDC).await;
+    env.process(
+        &[instructions::buy_shares(
+            investor.pubkey(),
+            None,
+            pool.address,
+            50,
+            false,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instructions::withdraw_from_pool(
+            pool.authority.pubkey(),
+            pool.address,
+            authority_usdc,
+            50,
+            None,
+            true,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&authority_usdc).await, 40 * USDC);
+    assert_eq!(env.token_balance(&vault).await, 10 * USDC);
+
+    // Pay the garage and keep the receipt on-chain
+    env.process(
+        &[instructions::spend_reserve(
+            pool.authority.pubkey(),
+            pool.address,
+            0,
+            garage_usdc,
+            ReceiptedExpense {
+                category: ExpenseCategory::Repair,
+                amount: 4 * USDC,
+                recipient: garage_usdc,
+                receipt_hash: [9; 32],
+            },
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&garage_usdc).await, 4 * USDC);
+    assert_eq!(env.token_balance(&vault).await, 6 * USDC);
+
+    let account: PoolReserve = env.account(&reserve).await;
+    assert_eq!(account.funded, 10 * USDC);
+    assert_eq!(account.spent, 4 * USDC);
+    assert_eq!(account.expenses, 1);
+    let expense: ReserveExpense = env
+        .account(&pda::find_reserve_expense_address(&reserve, 0).0)
+        .await;
+    assert_eq!(expense.index, 0);
+    assert_eq!(expense.expense.category, ExpenseCategory::Repair);
+    assert_eq!(expense.expense.amount, 4 * USDC);
+    assert_eq!(expense.expense.recipient, garage_usdc);
+    assert_eq!(expense.expense.receipt_hash, [9; 32]);
+}
+
+#[tokio::test]
+async fn fleet_pool_aggregates_tranches_into_one_distribution() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let investor = env.create_user().await;
+    env.fund_usdc(&investor.pubkey(), 1_000 * USDC).await;
+    let authority_usdc: Pubkey = env.fund_usdc(&pool.authority.pubkey(), 0).await;
+    let distribution_authority = env.create_user().await;
+
+    env.process(
+        &[instructions::buy_shares(
+            investor.pubkey(),
+            None,
+            pool.address,
+            100,
+            false,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+
+    // Two vehicles share the principal raised
+    let first: u16 = env.add_asset_tranche(&pool, 60 * USDC).await;
+    let second: u16 = env.add_asset_tranche(&pool, 40 * USDC).await;
+    let account: Pool = env.account(&pool.address).await;
+    assert_eq!(account.tranches, 2);
+    assert_eq!(account.allocated, 100 * USDC);
+    assert_eq!(account.raised, 100 * USDC);
+
+    // Each withdrawal draws on one tranche
+    for (tranche, shares) in [(first, 60), (second, 40)] {
+        env.process(
+            &[instructions::withdraw_from_pool(
+                pool.authority.pubkey(),
+                pool.address,
+                authority_usdc,
+                shares,
+                Some(tranche),
+                false,
+            )],
+            &[&pool.authority],
+        )
+        .await
+        .unwrap();
+    }
+    assert_eq!(env.token_balance(&authority_usdc).await, 100 * USDC);
+
+    env.process(
+        &[instructions::close_pool(
+            pool.authority.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+        )],
+        &[&pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+
+    // Repayments from both vehicles land in the single distribution
+    for (tranche, amount) in [(first, 30 * USDC), (second, 20 * USDC)] {
+        env.process(
+            &[instructions::distribute(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                amount,
+                Some(tranche),
+            )],
+            &[&pool.authority, &distribution_authority],
+        )
+        .await
+        .unwrap();
+    }
+    let distribution: Distribution = env
+        .account(&pda::find_distribution_address(&pool.address, 0).0)
+        .await;
+    assert_eq!(distribution.rewards, 50 * USDC);
+
+    let summary: TrancheSummary = env
+        .simulate(instructions::get_tranche_summary(pool.address, first))
+        .await;
+    assert_eq!(summary.principal_allocation, 60 * USDC);
+    assert_eq!(summary.deployed, 60 * USDC);
+    assert_eq!(summary.repaid, 30 * USDC);
+    assert_eq!(summary.undeployed, 0);
+
+    let tranche: AssetTranche = env
+        .account(&pda::find_tranche_address(&pool.address, second).0)
+        .await;
+    assert_eq!(tranche.index, second);
+    assert_eq!(tranche.pool, pool.address);
+    assert_eq!(tranche.deployed, 40 * USDC);
+    assert_eq!(tranche.repaid, 20 * USDC);
+
+    let summary: PoolSummary = env
+        .simulate(instructions::get_pool_summary(pool.address, true))
+        .await;
+    assert_eq!(summary.tranches, 2);
+    assert_eq!(summary.rewards, 50 * USDC);
+}
+
+#[tokio::test]
+async fn senior_class_is_paid_before_junior_class() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let investor = env.create_user().await;
+    let investor_usdc: Pubkey = env.fund_usdc(&investor.pubkey(), 1_000 * USDC).await;
+    let authority_usdc: Pubkey = env.fund_usdc(&pool.authority.pubkey(), 130 * USDC).await;
+    let distribution_authority = env.create_user().await;
+
+    // A 10% senior class for approved investors and a creator-held junior class
+    env.process(
+        &[
+            instructions::create_share_class(
+                pool.authority.pubkey(),
+                pool.address,
+                0,
+                60,
+                10,
+                BuyerEligibility::Approved,
+            ),
+            instructions::create_share_class(
+                pool.authority.pubkey(),
+                pool.address,
+                1,
+                40,
+                0,
+                BuyerEligibility::Creator,
+            ),
+        ],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.account::<Pool>(&pool.address).await.share_classes, 2);
+
+    env.process(
+        &[instructions::buy_class_shares(
+            investor.pubkey(),
+            Some(pool.authority.pubkey()),
+            pool.address,
+            0,
+            60,
+        )],
+        &[&investor, &pool.authority],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instructions::buy_class_shares(
+            pool.authority.pubkey(),
+            None,
+            pool.address,
+            1,
+            40,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.token_balance(&pda::get_class_shares_address(
+            &pool.address,
+            0,
+            &investor.pubkey()
+        ))
+        .await,
+        60
+    );
+    assert_eq!(env.account::<Pool>(&pool.address).await.minted, 100);
+
+    env.process(
+        &[instructions::close_pool(
+            pool.authority.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+        )],
+        &[&pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+
+    // The senior class is owed 60 USDC of principal and a 6 USDC coupon first
+    for amount in [80 * USDC, 10 * USDC] {
+        env.process(
+            &[instructions::distribute(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                amount,
+                None,
+            )],
+            &[&pool.authority, &distribution_authority],
+        )
+        .await
+        .unwrap();
+        env.process(
+            &[instructions::allocate_class_rewards(pool.address, 2)],
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+    let senior: ShareClass = env
+        .account(&pda::find_share_class_address(&pool.address, 0).0)
+        .await;
+    let junior: ShareClass = env
+        .account(&pda::find_share_class_address(&pool.address, 1).0)
+        .await;
+    assert_eq!(senior.rewards, 66 * USDC);
+    assert_eq!(junior.rewards, 24 * USDC);
+    let distribution: Distribution = env
+        .account(&pda::find_distribution_address(&pool.address, 0).0)
+        .await;
+    assert_eq!(distribution.allocated, 90 * USDC);
+
+    let claimable: u64 = env
+        .simulate(instructions::get_claimable_class_rewards(
+            pool.address,
+            0,
+            investor.pubkey(),
+        ))
+        .await;
+    assert_eq!(claimable, 66 * USDC);
+    env.process(
+        &[instructions::claim_class_rewards(
+            investor.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+            0,
+            claimable,
+        )],
+        &[&investor, &distribution_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&investor_usdc).await, 1_006 * USDC);
+    assert_eq!(env.token_balance(&authority_usdc).await, 0);
+    let position: InvestorPosition = env
+        .account(&pda::find_position_address(&pool.address, &investor.pubkey()).0)
+        .await;
+    assert_eq!(position.rewards_claimed, 66 * USDC);
+}
+
+#[tokio::test]
+async fn class_rewards_accrue_to_the_class_shares_held() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool = env.inject_pool(100, start_date, None).await;
+    let distribution_authority = env.create_user().await;
+    env.fund_usdc(&pool.authority.pubkey(), 40 * USDC).await;
+    env.process(
+        &[instructions::create_share_class(
+            pool.authority.pubkey(),
+            pool.address,
+            0,
+            100,
+            0,
+            BuyerEligibility::Open,
+        )],
+        &[&pool.authority],
+    )
+    .await
+    .unwrap();
+    let first = env.create_user().await;
+    let second = env.create_user().await;
+    let buy = |investor: &Keypair| {
+        instructions::buy_class_shares(investor.pubkey(), None, pool.address, 0, 50)
+    };
+    let pay = |amount: u64| {
+        [
+            instructions::distribute(
+                pool.authority.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                amount,
+                None,
+            ),
+            instructions::allocate_class_rewards(pool.address, 1),
+        ]
+    };
+
+    // The first holder has the class to themselves for the first payout
+    env.fund_usdc(&first.pubkey(), 50 * USDC).await;
+    env.process(&[buy(&first)], &[&first]).await.unwrap();
+    env.process(
+        &[instructions::activate_pool(
+            pool.authority.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+        )],
+        &[&pool.authority, &distribution_authority],
+    )
+    .await
+    .unwrap();
+    env.process(&pay(20 * USDC), &[&pool.authority, &distribution_authority])
+        .await
+        .unwrap();
+    env.fund_usdc(&second.pubkey(), 50 * USDC).await;
+    env.process(&[buy(&second)], &[&second]).await.unwrap();
+    env.process(&pay(20 * USDC), &[&pool.authority, &distribution_authority])
+        .await
+        .unwrap();
+
+    for (investor, expected) in [(&first, 30 * USDC), (&second, 10 * USDC)] {
+        let claimable: u64 = env
+            .simulate(instructions::get_claimable_class_rewards(
+                pool.address,
+                0,
+                investor.pubkey(),
+            ))
+            .await;
+        assert_eq!(claimable, expected);
+    }
+    env.process(
+        &[instructions::claim_class_rewards(
+            first.pubkey(),
+            distribution_authority.pubkey(),
+            pool.address,
+            0,
+            30 * USDC,
+        )],
+        &[&first, &distribution_authority],
+    )
+    .await
+    .unwrap();
+    let class_position: ClassPosition = env
+        .account(&pda::find_class_position_address(&pool.address, 0, &first.pubkey()).0)
+        .await;
+    assert_eq!(class_position.shares, 50);
+    assert_eq!(class_position.rewards_claimed, 30 * USDC);
+    assert_eq!(class_position.rewards_owed, 0);
+
+    // Claimed class shares can't be moved to claim their rewards again
+    let result = env
+        .process(
+            &[spl_token::instruction::transfer(
+                &spl_token::ID,
+                &pda::get_class_shares_address(&pool.address, 0, &first.pubkey()),
+                &pda::get_class_shares_address(&pool.address, 0, &second.pubkey()),
+                &first.pubkey(),
+                &[],
+                50,
+            )
+            .unwrap()],
+            &[&first],
+        )
+        .await;
+    assert_error(result, TokenError::AccountFrozen as u32);
+    let result = env
+        .process(
+            &[instructions::claim_class_rewards(
+                first.pubkey(),
+                distribution_authority.pubkey(),
+                pool.address,
+                0,
+                USDC,
+            )],
+            &[&first, &distribution_authority],
+        )
+        .await;
+    assert_error(result, ErrorCode::ExceedsClassRewards);
+    let claimable: u64 = env
+        .simulate(instructions::get_claimable_class_rewards(
+            pool.address,
+            0,
+            second.pubkey(),
+        ))
+        .await;
+    assert_eq!(claimable, 10 * USDC);
+}
+
+fn template_terms(authority: Pubkey) -> PoolTemplateTerms {
+    PoolTemplateTerms {
+        authority,
+        name: "BlockRide Fleet".to_string(),
+        symbol: "BRF".to_string(),
+        uri_pattern: "https://blockride.xyz/fleet-{}.json".to_string(),
+        share_price: USDC,
+        term: 365 * DAY,
+        apy: 12,
+        lockup: None,
+        eligibility: BuyerEligibility::Open,
+    }
+}
+
+#[tokio::test]
+async fn pool_template_is_managed_by_its_creator() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let grand_authority = env.create_user().await;
+    let creator = env.create_user().await;
+    let payer: Pubkey = env.payer();
+    env.process(
+        &[instructions::initialize_program(
+            payer,
+            grand_authority.pubkey(),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let reference: Pubkey = Pubkey::new_unique();
+    let template: Pubkey = pda::find_template_address(&reference).0;
+    env.process(
+        &[instructions::create_pool_template(
+            creator.pubkey(),
+            reference,
+            template_terms(creator.pubkey()),
+        )],
+        &[&creator],
+    )
+    .await
+    .unwrap();
+
+    // The series moves to a 2 year term for approved buyers only
+    env.process(
+        &[instructions::update_pool_template(
+            creator.pubkey(),
+            template,
+            PoolTemplateTerms {
+                term: 730 * DAY,
+                eligibility: BuyerEligibility::Approved,
+                ..template_terms(creator.pubkey())
+            },
+        )],
+        &[&creator],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[instructions::set_template_fee_override(
+            grand_authority.pubkey(),
+            template,
+            Some(250),
+        )],
+        &[&grand_authority],
+    )
+    .await
+    .unwrap();
+
+    let account: PoolTemplate = env.account(&template).await;
+    assert_eq!(account.creator, creator.pubkey());
+    assert_eq!(account.reference, reference);
+    assert_eq!(account.name, "BlockRide Fleet");
+    assert_eq!(account.term, 730 * DAY);
+    assert_eq!(account.share_price, USDC);
+    assert_eq!(account.eligibility, BuyerEligibility::Approved);
+    assert_eq!(account.protocol_fee_bps, Some(250));
+    assert_eq!(account.pools, 0);
+}
+
+#[tokio::test]
+async fn template_launches_numbered_pools() {
+    let mut env: TestEnv = TestEnv::start_with_metadata().await;
+    env.create_collection().await;
+    let creator = env.create_user().await;
+    env.fund_usdc(&creator.pubkey(), 1_000 * USDC).await;
+    let payer: Pubkey = env.payer();
+    let reference: Pubkey = Pubkey::new_unique();
+    let template: Pubkey = pda::find_template_address(&reference).0;
+    env.process(
+        &[
+            instructions::add_pool_creator(payer, creator.pubkey(), true),
+            instructions::create_pool_template(
+                creator.pubkey(),
+                reference,
+                PoolTemplateTerms {
+                    share_price: 3 * USDC,
+                    eligibility: BuyerEligibility::Approved,
+                    ..template_terms(creator.pubkey())
+                },
+            ),
+            instructions::set_template_fee_override(payer, template, Some(250)),
+        ],
+        &[&creator],
+    )
+    .await
+    .unwrap();
+
+    let start_date: u64 = env.now().await + DAY;
+    for number in 1..=2_u64 {
+        let reference: Pubkey = Pubkey::new_unique();
+        env.process(
+            &[instructions::create_pool_from_template(
+                creator.pubkey(),
+                template,
+                reference,
+                1_000,
+                start_date,
+                number - 1,
+                number - 1,
+            )],
+            &[&creator],
+        )
+        .await
+        .unwrap();
+
+        let pool: Pool = env.account(&pda::find_pool_address(&reference).0).await;
+        // The creator's seed is the fewest 3 USDC shares worth 100 USDC
+        assert_eq!(pool.minted, 34);
+        assert_eq!(pool.nav_per_share, 3 * USDC);
+        assert_eq!(pool.maturity_date, start_date + 365 * DAY);
+        assert_eq!(pool.apy, 12);
+        assert_eq!(pool.eligibility, BuyerEligibility::Approved);
+        assert_eq!(pool.protocol_fee_bps, Some(250));
+        assert_eq!(pool.id, Some(number - 1));
+        let metadata: Metadata = env.metadata(&pool.mint).await;
+        assert_eq!(
+            metadata.name.trim_end_matches('\0'),
+            format!("BlockRide Fleet #{number}")
+        );
+        assert_eq!(
+            metadata.uri.trim_end_matches('\0'),
+            format!("https://blockride.xyz/fleet-{number}.json")
+        );
+    }
+
+    let account: PoolTemplate = env.account(&template).await;
+    assert_eq!(account.pools, 2);
+}
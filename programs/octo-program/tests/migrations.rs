@@ -0,0 +1,164 @@
+mod common;
+
+use anchor_lang::{prelude::Pubkey, AnchorSerialize, Discriminator};
+use common::{assert_error, TestEnv, TestPool, DAY, USDC};
+use octo_client::{instructions, pda};
+use octo_program::{
+    Distribution, ErrorCode, LegacyDistribution, LegacyPool, LegacyProjectData, Pool, PoolStatus,
+    ProjectData,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+// Sizes the accounts were allocated with before they were versioned
+const LEGACY_PROJECT_SPACE: usize = 8 + 32;
+const LEGACY_POOL_SPACE: usize = 8 + 168;
+const LEGACY_DISTRIBUTION_SPACE: usize = 8 + 88;
+
+fn legacy_account<T: AnchorSerialize>(discriminator: [u8; 8], value: &T) -> Vec<u8> {
+    let mut data: Vec<u8> = discriminator.to_vec();
+    value.serialize(&mut data).unwrap();
+    data
+}
+
+/// Rewrites an injected pool the way it was laid out before versioning.
+async fn legacy_pool(env: &mut TestEnv) -> TestPool {
+    let start_date: u64 = env.now().await + DAY;
+    let pool: TestPool = env.inject_pool(100, start_date, None).await;
+    let current: Pool = env.account(&pool.address).await;
+    let legacy: LegacyPool = LegacyPool {
+        creator: current.creator,
+        authority: current.authority,
+        reference: current.reference,
+        mint: current.mint,
+        shares: current.shares,
+        minted: current.minted,
+        closed: current.status == PoolStatus::Settled,
+        bump: current.bump,
+        start_date: current.start_date,
+        maturity_date: current.maturity_date,
+        apy: current.apy,
+    };
+    env.set_raw_account(
+        &pool.address,
+        legacy_account(Pool::DISCRIMINATOR, &legacy),
+        LEGACY_POOL_SPACE,
+    );
+    pool
+}
+
+#[tokio::test]
+async fn legacy_pool_is_migrated_in_place() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let pool: TestPool = legacy_pool(&mut env).await;
+    let payer: Pubkey = env.payer();
+
+    env.process(&[instructions::migrate_pool(payer, pool.address)], &[])
+        .await
+        .unwrap();
+
+    let account: Pool = env.account(&pool.address).await;
+    assert_eq!(account.version, Pool::VERSION);
+    assert_eq!(account.reference, pool.reference);
+    assert_eq!(account.shares, 100);
+    assert_eq!(account.lockup, None);
+    assert_eq!(account.valuator, pool.authority.pubkey());
+    assert_eq!(account.share_price, USDC);
+    assert_eq!(account.nav_per_share, USDC);
+    assert_eq!(account.nav_updated_at, account.start_date);
+
+    // The migrated pool works like any other
+    let investor: Keypair = env.create_user().await;
+    env.fund_usdc(&investor.pubkey(), 10 * USDC).await;
+    env.process(
+        &[instructions::buy_shares(
+            investor.pubkey(),
+            None,
+            pool.address,
+            10,
+            false,
+        )],
+        &[&investor],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.account::<Pool>(&pool.address).await.minted, 10);
+
+    let result = env
+        .process(&[instructions::migrate_pool(payer, pool.address)], &[])
+        .await;
+    assert_error(result, ErrorCode::AccountAlreadyMigrated);
+}
+
+#[tokio::test]
+async fn legacy_distribution_is_migrated_in_place() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let start_date: u64 = env.now().await + DAY;
+    let pool: Pubkey = env.inject_pool(100, start_date, None).await.address;
+    let (distribution, bump): (Pubkey, u8) = pda::find_distribution_address(&pool, 0);
+    let authority: Pubkey = Pubkey::new_unique();
+    let legacy: LegacyDistribution = LegacyDistribution {
+        pool,
+        authority,
+        rewards: 50 * USDC,
+        claimed: 20 * USDC,
+        bump,
+    };
+    env.set_raw_account(
+        &distribution,
+        legacy_account(Distribution::DISCRIMINATOR, &legacy),
+        LEGACY_DISTRIBUTION_SPACE,
+    );
+    let payer: Pubkey = env.payer();
+
+    env.process(&[instructions::migrate_distribution(payer, pool)], &[])
+        .await
+        .unwrap();
+
+    let account: Distribution = env.account(&distribution).await;
+    assert_eq!(account.version, Distribution::VERSION);
+    assert_eq!(account.pool, pool);
+    assert_eq!(account.authority, authority);
+    assert_eq!(account.rewards, 50 * USDC);
+    assert_eq!(account.claimed, 20 * USDC);
+    assert_eq!(account.bump, bump);
+    assert_eq!(env.account::<Pool>(&pool).await.rewards, 50 * USDC);
+}
+
+#[tokio::test]
+async fn legacy_project_is_migrated_in_place() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let grand_authority: Pubkey = Pubkey::new_unique();
+    env.set_raw_account(
+        &pda::find_project_address().0,
+        legacy_account(
+            ProjectData::DISCRIMINATOR,
+            &LegacyProjectData { grand_authority },
+        ),
+        LEGACY_PROJECT_SPACE,
+    );
+    let payer: Pubkey = env.payer();
+
+    env.process(&[instructions::migrate_project(payer)], &[])
+        .await
+        .unwrap();
+
+    let account: ProjectData = env.account(&pda::find_project_address().0).await;
+    assert_eq!(account.version, ProjectData::VERSION);
+    assert_eq!(account.grand_authority, grand_authority);
+}
+
+#[tokio::test]
+async fn new_accounts_start_at_the_current_version() {
+    let mut env: TestEnv = TestEnv::start().await;
+    let payer: Pubkey = env.payer();
+    env.process(&[instructions::initialize_program(payer, payer)], &[])
+        .await
+        .unwrap();
+
+    let account: ProjectData = env.account(&pda::find_project_address().0).await;
+    assert_eq!(account.version, ProjectData::VERSION);
+    let result = env
+        .process(&[instructions::migrate_project(payer)], &[])
+        .await;
+    assert_error(result, ErrorCode::AccountAlreadyMigrated);
+}